@@ -0,0 +1,75 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::beacon_entries::BeaconEntry;
+use forest_db::Store;
+use fvm_ipld_encoding::{from_slice, to_vec};
+
+const BEACON_ENTRY_PREFIX: &str = "drand";
+
+/// Cache of beacon entries which have been verified, either when fetched from `Drand` or when
+/// found in block headers.
+pub trait BeaconEntryCache: Send + Sync {
+    /// Returns the cached entry of the given round, if any.
+    fn get(&self, round: u64) -> anyhow::Result<Option<BeaconEntry>>;
+
+    /// Caches a verified entry.
+    fn insert(&self, entry: &BeaconEntry) -> anyhow::Result<()>;
+}
+
+/// Beacon entry cache persisted in a key-value store. Entries are namespaced by the hash of the
+/// `Drand` chain, as rounds of different `Drand` networks overlap.
+pub struct StoreBeaconEntryCache<DB> {
+    db: DB,
+    prefix: Vec<u8>,
+}
+
+impl<DB> StoreBeaconEntryCache<DB> {
+    pub fn new(db: DB, chain_hash: &str) -> Self {
+        Self {
+            db,
+            prefix: format!("{BEACON_ENTRY_PREFIX}/{chain_hash}/").into_bytes(),
+        }
+    }
+
+    fn key(&self, round: u64) -> Vec<u8> {
+        let mut key = self.prefix.clone();
+        key.extend_from_slice(&round.to_be_bytes());
+        key
+    }
+}
+
+impl<DB> BeaconEntryCache for StoreBeaconEntryCache<DB>
+where
+    DB: Store + Send + Sync,
+{
+    fn get(&self, round: u64) -> anyhow::Result<Option<BeaconEntry>> {
+        match self.db.read(self.key(round))? {
+            Some(bytes) => Ok(Some(from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn insert(&self, entry: &BeaconEntry) -> anyhow::Result<()> {
+        Ok(self.db.write(self.key(entry.round()), to_vec(entry)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use forest_db::MemoryDB;
+
+    #[test]
+    fn entries_are_namespaced_by_chain() {
+        let db = MemoryDB::default();
+        let mainnet = StoreBeaconEntryCache::new(db.clone(), "mainnet");
+        let incentinet = StoreBeaconEntryCache::new(db, "incentinet");
+
+        let entry = BeaconEntry::new(7, vec![1, 2, 3]);
+        mainnet.insert(&entry).unwrap();
+        assert_eq!(mainnet.get(7).unwrap(), Some(entry));
+        assert_eq!(mainnet.get(8).unwrap(), None);
+        assert_eq!(incentinet.get(7).unwrap(), None);
+    }
+}
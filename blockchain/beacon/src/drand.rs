@@ -2,15 +2,16 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::beacon_entries::BeaconEntry;
-use ahash::HashMap;
+use super::cache::{BeaconEntryCache, StoreBeaconEntryCache};
 use anyhow::Context;
 use async_trait::async_trait;
 use bls_signatures::{PublicKey, Serialize, Signature};
 use byteorder::{BigEndian, WriteBytesExt};
+use forest_db::Store;
 use forest_shim::version::NetworkVersion;
 use forest_utils::net::{https_client, HyperBodyExt};
 use fvm_shared::clock::ChainEpoch;
-use parking_lot::RwLock;
+use log::warn;
 use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize};
 use sha2::Digest;
 use std::borrow::Cow;
@@ -45,12 +46,15 @@ pub enum DrandNetwork {
 #[derive(Clone)]
 /// Configuration used when initializing a `Drand` beacon.
 pub struct DrandConfig<'a> {
-    /// URL endpoint to send JSON HTTP requests to.
-    pub server: &'static str,
+    /// URL endpoints to send JSON HTTP requests to, tried in order until one responds.
+    pub servers: &'a [&'a str],
     /// Info about the beacon chain, used to verify correctness of endpoint.
     pub chain_info: ChainInfo<'a>,
     /// Network type
     pub network_type: DrandNetwork,
+    /// Never contact the `Drand` servers, entries are only served from those verified in block
+    /// headers.
+    pub offline: bool,
 }
 
 /// Contains the vector of `BeaconPoint`, which are mappings of epoch to the `Randomness` beacons used.
@@ -136,8 +140,8 @@ where
     /// Verify a new beacon entry against the most recent one before it.
     fn verify_entry(&self, curr: &BeaconEntry, prev: &BeaconEntry) -> Result<bool, anyhow::Error>;
 
-    /// Returns a `BeaconEntry` given a round. It fetches the `BeaconEntry` from a `Drand` node over [`gRPC`](https://grpc.io/)
-    /// In the future, we will cache values, and support streaming.
+    /// Returns a `BeaconEntry` given a round. Verified entries are served from the cache, others
+    /// are fetched from a `Drand` node over HTTP.
     async fn entry(&self, round: u64) -> Result<BeaconEntry, anyhow::Error>;
 
     /// Returns the most recent beacon round for the given Filecoin chain epoch.
@@ -174,7 +178,8 @@ pub struct BeaconEntryJson {
 /// `Drand` randomness beacon that can be used to generate randomness for the Filecoin chain.
 /// Primary use is to satisfy the [Beacon] trait.
 pub struct DrandBeacon {
-    url: &'static str,
+    servers: Vec<String>,
+    offline: bool,
 
     pub_key: DrandPublic,
     /// Interval between beacons, in seconds.
@@ -183,25 +188,34 @@ pub struct DrandBeacon {
     fil_gen_time: u64,
     fil_round_time: u64,
 
-    /// Keeps track of computed beacon entries.
-    local_cache: RwLock<HashMap<u64, BeaconEntry>>,
+    /// Keeps track of verified beacon entries.
+    cache: Box<dyn BeaconEntryCache>,
 }
 
 impl DrandBeacon {
-    /// Construct a new `DrandBeacon`.
-    pub fn new(
+    /// Construct a new `DrandBeacon`, caching verified entries in the given store.
+    pub fn new<DB>(
         genesis_ts: u64,
         interval: u64,
         config: &DrandConfig<'_>,
-    ) -> Result<Self, anyhow::Error> {
+        db: DB,
+    ) -> Result<Self, anyhow::Error>
+    where
+        DB: Store + Send + Sync + 'static,
+    {
         if genesis_ts == 0 {
             panic!("Genesis timestamp cannot be 0")
         }
+        anyhow::ensure!(
+            config.offline || !config.servers.is_empty(),
+            "No Drand server configured"
+        );
 
         let chain_info = &config.chain_info;
 
-        if cfg!(debug_assertions) && config.network_type == DrandNetwork::Mainnet {
-            let server = config.server;
+        if cfg!(debug_assertions) && config.network_type == DrandNetwork::Mainnet && !config.offline
+        {
+            let server = config.servers[0].to_owned();
             let remote_chain_info = std::thread::spawn(move || {
                 let rt = tokio::runtime::Runtime::new()?;
                 rt.block_on(async {
@@ -222,7 +236,8 @@ impl DrandBeacon {
         }
 
         Ok(Self {
-            url: config.server,
+            servers: config.servers.iter().map(|s| s.to_string()).collect(),
+            offline: config.offline,
             pub_key: DrandPublic {
                 coefficient: hex::decode(chain_info.public_key.as_ref())?,
             },
@@ -230,9 +245,22 @@ impl DrandBeacon {
             drand_gen_time: chain_info.genesis_time as u64,
             fil_round_time: interval,
             fil_gen_time: genesis_ts,
-            local_cache: Default::default(),
+            cache: Box::new(StoreBeaconEntryCache::new(db, &chain_info.hash)),
         })
     }
+
+    async fn fetch_entry(server: &str, round: u64) -> Result<BeaconEntry, anyhow::Error> {
+        let url = format!("{server}/public/{round}");
+        let client = https_client();
+        let resp: BeaconEntryJson = client
+            .get(url.try_into()?)
+            .await?
+            .into_body()
+            .json()
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(BeaconEntry::new(resp.round, hex::decode(resp.signature)?))
+    }
 }
 
 #[async_trait]
@@ -254,30 +282,32 @@ impl Beacon for DrandBeacon {
         let sig_match = bls_signatures::verify_messages(&sig, &[&digest], &[self.pub_key.key()?]);
 
         // Cache the result
-        let contains_curr = self.local_cache.read().contains_key(&curr.round());
-        if sig_match && !contains_curr {
-            self.local_cache.write().insert(curr.round(), curr.clone());
+        if sig_match && self.cache.get(curr.round())?.is_none() {
+            self.cache.insert(curr)?;
         }
         Ok(sig_match)
     }
 
     async fn entry(&self, round: u64) -> Result<BeaconEntry, anyhow::Error> {
-        let cached: Option<BeaconEntry> = self.local_cache.read().get(&round).cloned();
-        match cached {
-            Some(cached_entry) => Ok(cached_entry),
-            None => {
-                let url = format!("{}/public/{}", self.url, round);
-                let client = https_client();
-                let resp: BeaconEntryJson = client
-                    .get(url.try_into()?)
-                    .await?
-                    .into_body()
-                    .json()
-                    .await
-                    .map_err(|e| anyhow::anyhow!("{}", e))?;
-                Ok(BeaconEntry::new(resp.round, hex::decode(resp.signature)?))
+        if let Some(cached_entry) = self.cache.get(round)? {
+            return Ok(cached_entry);
+        }
+        anyhow::ensure!(
+            !self.offline,
+            "Drand round {round} has not been verified from block headers, and Drand is offline"
+        );
+
+        let mut last_error = None;
+        for server in &self.servers {
+            match Self::fetch_entry(server, round).await {
+                Ok(entry) => return Ok(entry),
+                Err(e) => {
+                    warn!("Failed to fetch Drand round {round} from {server}: {e}");
+                    last_error = Some(e);
+                }
             }
         }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No Drand server configured")))
     }
 
     fn max_beacon_round_for_epoch(
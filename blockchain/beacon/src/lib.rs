@@ -2,9 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 pub mod beacon_entries;
+mod cache;
 mod drand;
 mod mock_beacon;
 
 pub use beacon_entries::*;
+pub use cache::*;
 pub use drand::*;
 pub use mock_beacon::*;
@@ -1,8 +1,14 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use forest_beacon::{Beacon, ChainInfo, DrandBeacon, DrandConfig};
+use forest_beacon::{
+    Beacon, BeaconEntry, BeaconEntryCache, ChainInfo, DrandBeacon, DrandConfig, DrandNetwork,
+    StoreBeaconEntryCache,
+};
+use forest_db::MemoryDB;
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
 
 fn new_beacon() -> DrandBeacon {
     DrandBeacon::new(
@@ -10,14 +16,16 @@ fn new_beacon() -> DrandBeacon {
         25,
         // TODO this could maybe be referencing existing config
         &DrandConfig {
-            server: "https://pl-us.incentinet.drand.sh",
+            servers: &["https://pl-us.incentinet.drand.sh"],
             chain_info: ChainInfo {
                 public_key: "922a2e93828ff83345bae533f5172669a26c02dc76d6bf59c80892e12ab1455c229211886f35bb56af6d5bea981024df"
                     .into(),
                 ..Default::default()
             },
-            network_type: forest_beacon::DrandNetwork::Incentinet
+            network_type: forest_beacon::DrandNetwork::Incentinet,
+            offline: false,
         },
+        MemoryDB::default(),
     )
     .unwrap()
 }
@@ -43,3 +51,88 @@ async fn ask_and_verify_beacon_entry_fail() {
     let e3 = beacon.entry(3).await.unwrap();
     assert!(!beacon.verify_entry(&e2, &e3).unwrap());
 }
+
+const INCENTINET_HASH: &str = "80c8b872c714f4c00fdd3daa465d5514049f457f01f85a4caf68cdcd394ba039";
+
+fn local_beacon(servers: &[&str], offline: bool, db: MemoryDB) -> DrandBeacon {
+    DrandBeacon::new(
+        15904451751,
+        25,
+        &DrandConfig {
+            servers,
+            chain_info: ChainInfo {
+                public_key: "922a2e93828ff83345bae533f5172669a26c02dc76d6bf59c80892e12ab1455c229211886f35bb56af6d5bea981024df"
+                    .into(),
+                hash: INCENTINET_HASH.into(),
+                ..Default::default()
+            },
+            network_type: DrandNetwork::Incentinet,
+            offline,
+        },
+        db,
+    )
+    .unwrap()
+}
+
+/// Serves the same `Drand` round to every request, returning the server URL.
+async fn serve_round(round: u64, signature: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = socket.read(&mut buf).await;
+            let body = format!(
+                r#"{{"round":{round},"randomness":"00","signature":"{signature}","previous_signature":"00"}}"#
+            );
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+    url
+}
+
+/// Returns the URL of a server that refuses connections.
+async fn unreachable_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+#[tokio::test]
+async fn entry_fails_over_to_next_server() {
+    let down = unreachable_server().await;
+    let up = serve_round(5, "abcd").await;
+    let beacon = local_beacon(&[down.as_str(), up.as_str()], false, MemoryDB::default());
+
+    let entry = beacon.entry(5).await.unwrap();
+    assert_eq!(entry, BeaconEntry::new(5, vec![0xab, 0xcd]));
+}
+
+#[tokio::test]
+async fn entry_fails_when_all_servers_are_down() {
+    let first = unreachable_server().await;
+    let second = unreachable_server().await;
+    let beacon = local_beacon(
+        &[first.as_str(), second.as_str()],
+        false,
+        MemoryDB::default(),
+    );
+    assert!(beacon.entry(5).await.is_err());
+}
+
+#[tokio::test]
+async fn cached_entries_are_served_offline() {
+    let db = MemoryDB::default();
+    let entry = BeaconEntry::new(5, vec![1, 2, 3]);
+    StoreBeaconEntryCache::new(db.clone(), INCENTINET_HASH)
+        .insert(&entry)
+        .unwrap();
+
+    let beacon = local_beacon(&[], true, db);
+    assert_eq!(beacon.entry(5).await.unwrap(), entry);
+    assert!(beacon.entry(6).await.is_err());
+}
@@ -328,6 +328,10 @@ impl BlockHeader {
             fvm_ipld_encoding::to_vec(self).expect("header serialization cannot fail")
         })
     }
+    /// Size of the `CBOR` encoding of the header, computed once.
+    pub fn encoded_len(&self) -> usize {
+        self.cached_bytes().len()
+    }
     /// Check to ensure block signature is valid
     pub fn check_block_signature(&self, addr: &Address) -> Result<(), Error> {
         // If the block has already been validated, short circuit
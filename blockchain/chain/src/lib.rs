@@ -1,6 +1,8 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 mod store;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 mod weight;
 
 pub use self::store::*;
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::index::checkpoint_tipsets;
-use super::{index::ChainIndex, tipset_tracker::TipsetTracker, Error};
+use super::{index::ChainIndex, reorg::MAX_HEAD_CHANGE_WALK, tipset_tracker::TipsetTracker, Error};
 use crate::Scale;
 use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
 use anyhow::Result;
@@ -43,7 +43,7 @@ use serde::Serialize;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
 use std::{collections::VecDeque, time::SystemTime};
-use tokio::io::AsyncWrite;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio::sync::broadcast::{self, Sender as Publisher};
 use tokio::sync::Mutex as TokioMutex;
 use tokio_util::compat::TokioAsyncWriteCompatExt;
@@ -87,6 +87,9 @@ pub struct ChainStore<DB> {
 
     /// Tracks blocks for the purpose of forming tipsets.
     tipset_tracker: TipsetTracker<DB>,
+
+    /// Tipset that the heaviest chain must include, set manually.
+    pub(super) checkpoint: Mutex<Option<Arc<Tipset>>>,
 }
 
 impl<DB> BitswapStore for ChainStore<DB>
@@ -132,11 +135,16 @@ where
             db,
             ts_cache,
             heaviest: Mutex::new(genesis_ts.clone()),
+            checkpoint: Default::default(),
         };
 
         // Result intentionally ignored, doesn't matter if heaviest doesn't exist in store yet
         let _ = cs.load_heaviest_tipset();
 
+        if let Err(e) = cs.load_checkpoint() {
+            warn!("Failed to load the checkpoint: {e}");
+        }
+
         cs.set_genesis(genesis_block_header)?;
 
         if cs.blockstore().read(HEAD_KEY)?.is_none() {
@@ -149,13 +157,50 @@ where
     /// Sets heaviest tipset within `ChainStore` and store its tipset keys under `HEAD_KEY`
     pub fn set_heaviest_tipset(&self, ts: Arc<Tipset>) -> Result<(), Error> {
         self.db.write(HEAD_KEY, ts.key().marshal_cbor()?)?;
-        *self.heaviest.lock() = ts.clone();
-        if self.publisher.send(HeadChange::Apply(ts)).is_err() {
-            debug!("did not publish head change, no active receivers");
+        let old = std::mem::replace(&mut *self.heaviest.lock(), ts.clone());
+        for change in self.head_changes(old, ts) {
+            if self.publisher.send(change).is_err() {
+                debug!("did not publish head change, no active receivers");
+                break;
+            }
         }
         Ok(())
     }
 
+    /// Returns the head changes to publish when the head moves from `old` to `new`. When `new` is
+    /// not a descendant of `old`, the reverted tipsets are published before the applied ones and
+    /// the reorganization is logged. Otherwise, only `new` is applied.
+    fn head_changes(&self, old: Arc<Tipset>, new: Arc<Tipset>) -> Vec<HeadChange> {
+        if new.key() == old.key() || new.parents() == old.key() {
+            return vec![HeadChange::Apply(new)];
+        }
+        // Don't start a walk that would most likely give up, e.g. while catching up
+        if new.epoch().abs_diff(old.epoch()) > MAX_HEAD_CHANGE_WALK as u64 {
+            return vec![HeadChange::Apply(new)];
+        }
+        match self.reorg_ops(old.clone(), new.clone(), Some(MAX_HEAD_CHANGE_WALK)) {
+            Ok(Some((reverts, applies))) if !reverts.is_empty() => {
+                if let Err(e) = self.log_reorg(&old, &new, reverts.len()) {
+                    warn!("Failed to log chain reorganization: {e}");
+                }
+                reverts
+                    .into_iter()
+                    .map(HeadChange::Revert)
+                    .chain(applies.into_iter().rev().map(HeadChange::Apply))
+                    .collect()
+            }
+            Ok(_) => vec![HeadChange::Apply(new)],
+            Err(e) => {
+                debug!(
+                    "Failed to find the path from {:?} to {:?}: {e}",
+                    old.key(),
+                    new.key()
+                );
+                vec![HeadChange::Apply(new)]
+            }
+        }
+    }
+
     /// Writes genesis to `blockstore`.
     pub fn set_genesis(&self, header: &BlockHeader) -> Result<Cid, Error> {
         set_genesis(self.blockstore(), header)
@@ -248,7 +293,13 @@ where
         let curr_weight = heaviest_weight;
 
         if new_weight > curr_weight {
-            // TODO potentially need to deal with re-orgs here
+            if !self.includes_checkpoint(&[ts.clone()])? {
+                warn!(
+                    "Ignoring heavier tipset {:?} that doesn't include the checkpoint",
+                    ts.key()
+                );
+                return Ok(());
+            }
             info!("New heaviest tipset: {:?}", ts.key());
             self.set_heaviest_tipset(ts)?;
         }
@@ -551,7 +602,15 @@ where
             .expect("time cannot go backwards");
         info!("export finished, took {} seconds", time.as_secs());
 
-        let digest = writer.lock().await.get_mut().finalize();
+        // Flush the writer, and end the stream of compressing writers, before computing the
+        // checksum.
+        let mut writer = writer.lock().await;
+        writer
+            .get_mut()
+            .shutdown()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to write blocks in export: {e}")))?;
+        let digest = writer.get_mut().finalize();
         Ok(digest)
     }
 
@@ -859,6 +918,21 @@ where
     Ok(receipts.cloned())
 }
 
+/// Returns the receipts of the messages of the parent tipset of the block, in order.
+pub fn get_parent_receipts<DB>(db: &DB, block_header: &BlockHeader) -> Result<Vec<Receipt>, Error>
+where
+    DB: Blockstore,
+{
+    let amt = Amt::<Receipt, _>::load(block_header.message_receipts(), db)?;
+    let mut receipts = Vec::with_capacity(amt.count());
+    amt.for_each(|_, receipt| {
+        receipts.push(receipt.clone());
+        Ok(())
+    })
+    .map_err(|e| Error::State(e.to_string()))?;
+    Ok(receipts)
+}
+
 pub mod headchange_json {
     use super::*;
     use forest_blocks::tipset_json::TipsetJson;
@@ -959,7 +1033,9 @@ pub fn persist_block_messages<DB: Blockstore>(
 
 #[cfg(test)]
 mod tests {
+    use super::super::reorg::MAX_PATH_LEN;
     use super::*;
+    use crate::test_utils::{child, child_at, setup};
     use cid::multihash::Code::{Blake2b256, Identity};
     use cid::multihash::MultihashDigest;
     use cid::Cid;
@@ -1002,4 +1078,102 @@ mod tests {
         cs.mark_block_as_validated(&cid).unwrap();
         assert!(cs.is_block_validated(&cid).unwrap());
     }
+
+    #[test]
+    fn reorg_path_and_log() {
+        let (cs, genesis) = setup();
+        let db = cs.blockstore();
+        let a1 = child(db, &genesis, 1, &[], &[]);
+        let a2 = child(db, &a1, 1, &[], &[]);
+        let b1 = child(db, &genesis, 2, &[], &[]);
+
+        cs.set_heaviest_tipset(a1).unwrap();
+        cs.set_heaviest_tipset(a2.clone()).unwrap();
+        assert!(cs.reorg_log().unwrap().is_empty());
+
+        cs.set_heaviest_tipset(b1.clone()).unwrap();
+        let log = cs.reorg_log().unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].depth, 2);
+        assert_eq!(log[0].old_epoch, 2);
+        assert_eq!(log[0].new_epoch, 1);
+
+        let path = cs.get_path(a2.key(), b1.key()).unwrap();
+        let path: Vec<_> = path
+            .iter()
+            .map(|change| match change {
+                HeadChange::Revert(ts) => ("revert", ts.epoch()),
+                HeadChange::Apply(ts) => ("apply", ts.epoch()),
+                HeadChange::Current(ts) => ("current", ts.epoch()),
+            })
+            .collect();
+        assert_eq!(path, vec![("revert", 2), ("revert", 1), ("apply", 1)]);
+    }
+
+    #[test]
+    fn distant_heads_are_not_walked() {
+        let (cs, genesis) = setup();
+        let db = cs.blockstore();
+        let a1 = child(db, &genesis, 1, &[], &[]);
+        // Fork after a long run of null rounds
+        let epoch = MAX_HEAD_CHANGE_WALK as ChainEpoch + 2;
+        let b1 = child_at(db, &genesis, epoch, 2, &[], &[]);
+
+        let changes = cs.head_changes(a1.clone(), b1.clone());
+        assert!(matches!(changes.as_slice(), [HeadChange::Apply(ts)] if ts == &b1));
+        assert!(cs.reorg_log().unwrap().is_empty());
+
+        // The path is still found on request
+        let path = cs.get_path(a1.key(), b1.key()).unwrap();
+        assert_eq!(path.len(), 2);
+    }
+
+    #[test]
+    fn get_path_is_bounded() {
+        let (cs, genesis) = setup();
+        let mut chain = vec![genesis];
+        for _ in 0..=MAX_PATH_LEN {
+            let ts = child(cs.blockstore(), chain.last().unwrap(), 1, &[], &[]);
+            chain.push(ts);
+        }
+        let key = |i: usize| chain[i].key();
+
+        let path = cs.get_path(key(1), key(MAX_PATH_LEN + 1)).unwrap();
+        assert_eq!(path.len(), MAX_PATH_LEN);
+        assert!(cs.get_path(key(0), key(MAX_PATH_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn checkpoint_pins_chain() {
+        let (cs, genesis) = setup();
+        let db = cs.blockstore().clone();
+        let chain_config = Arc::new(ChainConfig::default());
+        let gen_block = genesis.min_ticket_block();
+        let a1 = child(&db, &genesis, 1, &[], &[]);
+        let a2 = child(&db, &a1, 1, &[], &[]);
+        let b1 = child(&db, &genesis, 2, &[], &[]);
+        let b2 = child(&db, &b1, 2, &[], &[]);
+
+        cs.set_heaviest_tipset(a2.clone()).unwrap();
+        assert!(cs.includes_checkpoint(&[a2.clone()]).unwrap());
+
+        // The head moves to the checkpoint when the heaviest chain doesn't include it.
+        cs.set_checkpoint(b1.clone()).unwrap();
+        assert_eq!(cs.heaviest_tipset().key(), b1.key());
+        assert!(!cs.includes_checkpoint(&[a2.clone()]).unwrap());
+        assert!(!cs.includes_checkpoint(&[a1]).unwrap());
+        assert!(cs.includes_checkpoint(&[b2.clone()]).unwrap());
+        assert!(cs.includes_checkpoint(&[b2, b1.clone()]).unwrap());
+
+        // The checkpoint survives restarts.
+        let cs = ChainStore::new(db.clone(), chain_config.clone(), gen_block).unwrap();
+        assert_eq!(cs.checkpoint().unwrap().key(), b1.key());
+
+        // Until it's cleared.
+        cs.clear_checkpoint().unwrap();
+        assert!(cs.checkpoint().is_none());
+        assert!(cs.includes_checkpoint(&[a2]).unwrap());
+        let cs = ChainStore::new(db, chain_config, gen_block).unwrap();
+        assert!(cs.checkpoint().is_none());
+    }
 }
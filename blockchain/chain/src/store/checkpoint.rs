@@ -0,0 +1,89 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Manual checkpoint pinning the node to a chain, e.g. during a chain split.
+
+use super::{ChainStore, Error};
+use forest_blocks::{Tipset, TipsetKeys};
+use forest_db::Store;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{from_slice, Cbor};
+use log::{info, warn};
+use std::sync::Arc;
+
+const CHECKPOINT_KEY: &str = "sync_checkpoint";
+
+impl<DB> ChainStore<DB>
+where
+    DB: Blockstore + Store + Send + Sync,
+{
+    /// Returns the checkpoint, if any. Chains that don't include it are rejected.
+    pub fn checkpoint(&self) -> Option<Arc<Tipset>> {
+        self.checkpoint.lock().clone()
+    }
+
+    /// Persists `ts` as the checkpoint. When the heaviest chain doesn't include it, the head is
+    /// moved to the checkpoint.
+    pub fn set_checkpoint(&self, ts: Arc<Tipset>) -> Result<(), Error> {
+        self.db.write(CHECKPOINT_KEY, ts.key().marshal_cbor()?)?;
+        *self.checkpoint.lock() = Some(ts.clone());
+        info!("Checkpoint set to {:?} ({})", ts.key(), ts.epoch());
+
+        if !self.includes_checkpoint(&[self.heaviest_tipset()])? {
+            warn!(
+                "Heaviest chain doesn't include the checkpoint, moving the head to {:?}",
+                ts.key()
+            );
+            self.set_heaviest_tipset(ts)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the checkpoint, so that the heaviest chain is followed again.
+    pub fn clear_checkpoint(&self) -> Result<(), Error> {
+        self.db.delete(CHECKPOINT_KEY)?;
+        if let Some(ts) = self.checkpoint.lock().take() {
+            info!("Checkpoint {:?} ({}) cleared", ts.key(), ts.epoch());
+        }
+        Ok(())
+    }
+
+    /// Loads the checkpoint persisted in the database.
+    pub(crate) fn load_checkpoint(&self) -> Result<(), Error> {
+        if let Some(bz) = self.db.read(CHECKPOINT_KEY)? {
+            let tsk: TipsetKeys = from_slice(&bz)?;
+            *self.checkpoint.lock() = Some(self.tipset_from_keys(&tsk)?);
+        }
+        Ok(())
+    }
+
+    /// Checks whether the chain of `tipsets`, ordered from the head down with each tipset being
+    /// the parent of the previous one, can include the checkpoint.
+    ///
+    /// When the checkpoint is older than all of `tipsets`, the ancestors of the oldest one are
+    /// loaded from the store. If they aren't known yet, the chain is assumed to include it.
+    pub fn includes_checkpoint(&self, tipsets: &[Arc<Tipset>]) -> Result<bool, Error> {
+        let checkpoint = match self.checkpoint() {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(true),
+        };
+        let oldest = match tipsets.iter().find(|ts| ts.epoch() <= checkpoint.epoch()) {
+            // The highest tipset at or below the checkpoint epoch is the checkpoint itself, or
+            // the chain went another way.
+            Some(ts) => return Ok(ts.key() == checkpoint.key()),
+            None => match tipsets.last() {
+                Some(oldest) => oldest,
+                None => return Ok(true),
+            },
+        };
+        let parent = match self.tipset_from_keys(oldest.parents()) {
+            Ok(parent) => parent,
+            Err(_) => return Ok(true),
+        };
+        if parent.epoch() < checkpoint.epoch() {
+            return Ok(false);
+        }
+        let ts = self.tipset_by_height(checkpoint.epoch(), parent, true)?;
+        Ok(ts.key() == checkpoint.key())
+    }
+}
@@ -3,10 +3,15 @@
 
 pub mod base_fee;
 mod chain_store;
+mod checkpoint;
 mod errors;
 mod index;
+mod msg_index;
+mod reorg;
 mod tipset_tracker;
 
 pub use self::base_fee::*;
 pub use self::chain_store::*;
 pub use self::errors::*;
+pub use self::msg_index::*;
+pub use self::reorg::*;
@@ -0,0 +1,239 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! On-disk index of the location of messages on chain, so that a message and its receipt can be
+//! found without walking back through tipsets.
+
+use super::{get_parent_reciept, ChainStore, Error, HeadChange};
+use cid::Cid;
+use forest_blocks::{Tipset, TipsetKeys};
+use forest_db::Store;
+use forest_encoding::tuple::*;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{from_slice, to_vec, Cbor};
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::receipt::Receipt;
+use log::{debug, warn};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+const MSG_INDEX_PREFIX: &[u8] = b"msg_index/";
+
+/// Location of a message on chain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct MsgInfo {
+    /// Keys of the tipset including the message.
+    pub tipset: TipsetKeys,
+    /// Position of the message among the messages executed for the tipset, which is also the
+    /// position of its receipt in the child tipset.
+    pub index: u64,
+    /// Epoch of the tipset including the message.
+    pub epoch: ChainEpoch,
+}
+
+fn msg_index_key(msg: &Cid) -> Vec<u8> {
+    let mut key = Vec::new();
+    key.extend_from_slice(MSG_INDEX_PREFIX);
+    key.extend(msg.to_bytes());
+    key
+}
+
+impl<DB> ChainStore<DB>
+where
+    DB: Blockstore + Store + Send + Sync,
+{
+    /// Returns the indexed location of a message, if any. The location is not guaranteed to be
+    /// on the current chain.
+    pub fn get_msg_info(&self, msg: &Cid) -> Result<Option<MsgInfo>, Error> {
+        match self.blockstore().read(msg_index_key(msg))? {
+            Some(bz) => Ok(Some(from_slice(&bz)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Records the location of every message executed for the tipset, overwriting the location
+    /// of messages previously indexed on another fork.
+    pub fn index_tipset_messages(&self, ts: &Tipset) -> Result<(), Error> {
+        for (index, msg) in self.messages_for_tipset(ts)?.iter().enumerate() {
+            let info = MsgInfo {
+                tipset: ts.key().clone(),
+                index: index as u64,
+                epoch: ts.epoch(),
+            };
+            self.blockstore()
+                .write(msg_index_key(&msg.cid()?), to_vec(&info)?)?;
+        }
+        Ok(())
+    }
+
+    /// Looks a message up in the index. Returns the tipset in which the message was executed,
+    /// i.e. the child of the tipset including it, along with the message receipt.
+    ///
+    /// Returns `None` if the message is not indexed, if the indexed tipset is not on the chain
+    /// of `head` or if the message has not been executed yet.
+    pub fn search_msg_index(
+        &self,
+        head: Arc<Tipset>,
+        msg: &Cid,
+    ) -> Result<Option<(Arc<Tipset>, Receipt)>, Error> {
+        let info = match self.get_msg_info(msg)? {
+            Some(info) if info.epoch < head.epoch() => info,
+            _ => return Ok(None),
+        };
+
+        let executed = self.tipset_by_height(info.epoch + 1, head, false)?;
+        if executed.parents() != &info.tipset {
+            // The message was indexed on a fork that has since been reverted
+            return Ok(None);
+        }
+
+        let header = executed.blocks().first().ok_or(Error::NoBlocks)?;
+        match get_parent_reciept(self.blockstore(), header, info.index as usize)? {
+            Some(receipt) => Ok(Some((executed, receipt))),
+            None => Ok(None),
+        }
+    }
+
+    /// Indexes the messages of the tipsets of the current chain with an epoch within
+    /// `from..=to`. Returns the number of indexed tipsets.
+    pub fn backfill_msg_index(&self, from: ChainEpoch, to: ChainEpoch) -> Result<usize, Error> {
+        let mut indexed = 0;
+        let mut ts = self.heaviest_tipset();
+        while ts.epoch() >= from {
+            if ts.epoch() <= to {
+                self.index_tipset_messages(&ts)?;
+                indexed += 1;
+            }
+            if ts.epoch() == 0 {
+                break;
+            }
+            ts = self.tipset_from_keys(ts.parents())?;
+        }
+        Ok(indexed)
+    }
+
+    /// Keeps the message index up to date with head changes. On every new head, the tipsets
+    /// that were not part of the previous chain are indexed.
+    pub async fn index_head_changes(self: Arc<Self>) -> anyhow::Result<()> {
+        let mut subscriber = self.publisher().subscribe();
+        let mut last = self.heaviest_tipset();
+        if let Err(e) = self.index_tipset_messages(&last) {
+            warn!("Failed to index messages of tipset {:?}: {e}", last.key());
+        }
+
+        loop {
+            match subscriber.recv().await {
+                Ok(HeadChange::Apply(ts)) => {
+                    if let Err(e) = self.index_new_tipsets(&ts, &last) {
+                        warn!("Failed to index messages up to tipset {:?}: {e}", ts.key());
+                    }
+                    last = ts;
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(i)) => {
+                    debug!("message index head change subscriber lagged, skipped {i} events");
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Indexes the tipsets from `head` back to the most recent tipset shared with the chain of
+    /// `last`.
+    fn index_new_tipsets(&self, head: &Arc<Tipset>, last: &Arc<Tipset>) -> Result<(), Error> {
+        let mut ts = head.clone();
+        loop {
+            if ts.epoch() <= last.epoch()
+                && self.tipset_by_height(ts.epoch(), last.clone(), true)?.key() == ts.key()
+            {
+                break;
+            }
+            self.index_tipset_messages(&ts)?;
+            if ts.epoch() == 0 {
+                break;
+            }
+            ts = self.tipset_from_keys(ts.parents())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{child, message, receipt, setup};
+
+    #[test]
+    fn index_follows_forks() {
+        let (cs, genesis) = setup();
+        let db = cs.blockstore();
+        let msg = message(0);
+        let msg_cid = msg.cid().unwrap();
+
+        let a1 = child(db, &genesis, 1, &[msg.clone()], &[]);
+        let a2 = child(db, &a1, 1, &[], &[receipt(1)]);
+        cs.index_new_tipsets(&a2, &genesis).unwrap();
+        assert_eq!(
+            cs.search_msg_index(a2.clone(), &msg_cid).unwrap(),
+            Some((a2.clone(), receipt(1)))
+        );
+        // Not executed yet on the chain of `a1`.
+        assert_eq!(cs.search_msg_index(a1.clone(), &msg_cid).unwrap(), None);
+
+        // The message isn't on the chain of another fork.
+        let b1 = child(db, &genesis, 2, &[], &[]);
+        let b2 = child(db, &b1, 2, &[], &[]);
+        cs.index_new_tipsets(&b2, &a2).unwrap();
+        assert_eq!(cs.search_msg_index(b2, &msg_cid).unwrap(), None);
+
+        // A fork including the message again takes the index over.
+        let c1 = child(db, &genesis, 3, &[msg], &[]);
+        let c2 = child(db, &c1, 3, &[], &[receipt(2)]);
+        cs.index_new_tipsets(&c2, &a2).unwrap();
+        assert_eq!(
+            cs.get_msg_info(&msg_cid).unwrap().unwrap().tipset,
+            *c1.key()
+        );
+        assert_eq!(
+            cs.search_msg_index(c2.clone(), &msg_cid).unwrap(),
+            Some((c2, receipt(2)))
+        );
+        assert_eq!(cs.search_msg_index(a2, &msg_cid).unwrap(), None);
+    }
+
+    #[test]
+    fn backfill_indexes_epoch_range() {
+        let (cs, genesis) = setup();
+        let db = cs.blockstore();
+        let first = message(0);
+        let second = message(1);
+
+        let ts1 = child(db, &genesis, 1, &[first.clone()], &[]);
+        let ts2 = child(db, &ts1, 1, &[second.clone()], &[receipt(1)]);
+        let ts3 = child(db, &ts2, 1, &[], &[receipt(2)]);
+        cs.set_heaviest_tipset(ts3.clone()).unwrap();
+
+        assert_eq!(cs.backfill_msg_index(2, 5).unwrap(), 2);
+        assert_eq!(cs.get_msg_info(&first.cid().unwrap()).unwrap(), None);
+        assert_eq!(
+            cs.search_msg_index(ts3.clone(), &second.cid().unwrap())
+                .unwrap(),
+            Some((ts3.clone(), receipt(2)))
+        );
+
+        assert_eq!(cs.backfill_msg_index(1, 1).unwrap(), 1);
+        assert_eq!(
+            cs.get_msg_info(&first.cid().unwrap()).unwrap(),
+            Some(MsgInfo {
+                tipset: ts1.key().clone(),
+                index: 0,
+                epoch: 1,
+            })
+        );
+        assert_eq!(
+            cs.search_msg_index(ts3, &first.cid().unwrap()).unwrap(),
+            Some((ts2, receipt(1)))
+        );
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Paths between tipsets and the log of the reorganizations of the heaviest chain.
+
+use super::{ChainStore, Error, HeadChange};
+use forest_blocks::{Tipset, TipsetKeys};
+use forest_db::Store;
+use forest_encoding::tuple::*;
+use forest_metrics::metrics;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{from_slice, to_vec};
+use fvm_shared::clock::ChainEpoch;
+use log::{debug, info};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const REORG_LOG_KEY: &str = "reorg_log";
+
+/// Number of reorganizations kept in the log, the oldest ones are dropped first.
+const MAX_REORG_LOG_ENTRIES: usize = 256;
+
+/// Maximum number of tipsets walked to find the path between two heads. Larger head changes,
+/// e.g. while catching up with the network, are not checked for reorganizations.
+pub(crate) const MAX_HEAD_CHANGE_WALK: usize = 900;
+
+/// Maximum number of head changes returned by [`ChainStore::get_path`].
+pub const MAX_PATH_LEN: usize = 2000;
+
+/// Reorganization of the heaviest chain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ReorgLogEntry {
+    /// Time of the reorganization in UNIX seconds.
+    pub timestamp: u64,
+    /// Number of tipsets reverted.
+    pub depth: u64,
+    pub old_head: TipsetKeys,
+    pub old_epoch: ChainEpoch,
+    pub new_head: TipsetKeys,
+    pub new_epoch: ChainEpoch,
+}
+
+impl<DB> ChainStore<DB>
+where
+    DB: Blockstore + Store + Send + Sync,
+{
+    /// Finds the tipsets to revert and to apply to go from the chain of `from` to the chain of
+    /// `to`, both ordered from the head down to the common ancestor, which is excluded.
+    ///
+    /// Gives up with `None` after walking `limit` tipsets, if provided.
+    pub fn reorg_ops(
+        &self,
+        from: Arc<Tipset>,
+        to: Arc<Tipset>,
+        limit: Option<usize>,
+    ) -> Result<Option<(Vec<Arc<Tipset>>, Vec<Arc<Tipset>>)>, Error> {
+        let mut left = from;
+        let mut right = to;
+        let mut reverts = Vec::new();
+        let mut applies = Vec::new();
+        while left.key() != right.key() {
+            if limit.map_or(false, |limit| reverts.len() + applies.len() >= limit) {
+                return Ok(None);
+            }
+            if left.epoch() > right.epoch() {
+                let parent = self.tipset_from_keys(left.parents())?;
+                reverts.push(left);
+                left = parent;
+            } else {
+                let parent = self.tipset_from_keys(right.parents())?;
+                applies.push(right);
+                right = parent;
+            }
+        }
+        Ok(Some((reverts, applies)))
+    }
+
+    /// Returns the head changes to go from the `from` tipset to the `to` one: the reverts, from
+    /// `from` down to the common ancestor, followed by the applies, from the common ancestor up
+    /// to `to`. Fails if the path is longer than [`MAX_PATH_LEN`] tipsets.
+    pub fn get_path(&self, from: &TipsetKeys, to: &TipsetKeys) -> Result<Vec<HeadChange>, Error> {
+        let from = self.tipset_from_keys(from)?;
+        let to = self.tipset_from_keys(to)?;
+        let (reverts, applies) = self
+            .reorg_ops(from, to, Some(MAX_PATH_LEN))?
+            .ok_or_else(|| Error::Other(format!("Path is longer than {MAX_PATH_LEN} tipsets")))?;
+        Ok(reverts
+            .into_iter()
+            .map(HeadChange::Revert)
+            .chain(applies.into_iter().rev().map(HeadChange::Apply))
+            .collect())
+    }
+
+    /// Returns the logged reorganizations of the heaviest chain, oldest first.
+    pub fn reorg_log(&self) -> Result<Vec<ReorgLogEntry>, Error> {
+        match self.blockstore().read(REORG_LOG_KEY)? {
+            Some(bz) => Ok(from_slice(&bz)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Records a reorganization of the heaviest chain in the log and the metrics.
+    pub(crate) fn log_reorg(
+        &self,
+        old_head: &Tipset,
+        new_head: &Tipset,
+        depth: usize,
+    ) -> Result<(), Error> {
+        info!(
+            "Chain reorganization of depth {depth}: {:?} ({}) replaced by {:?} ({})",
+            old_head.key(),
+            old_head.epoch(),
+            new_head.key(),
+            new_head.epoch()
+        );
+        metrics::REORG_TOTAL.inc();
+        metrics::REORG_DEPTH.observe(depth as f64);
+
+        let mut log = self.reorg_log().unwrap_or_else(|e| {
+            debug!("Failed to read the reorg log, starting a new one: {e}");
+            Vec::new()
+        });
+        if log.len() >= MAX_REORG_LOG_ENTRIES {
+            log.drain(..=log.len() - MAX_REORG_LOG_ENTRIES);
+        }
+        log.push(ReorgLogEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            depth: depth as u64,
+            old_head: old_head.key().clone(),
+            old_epoch: old_head.epoch(),
+            new_head: new_head.key().clone(),
+            new_epoch: new_head.epoch(),
+        });
+        self.blockstore().write(REORG_LOG_KEY, to_vec(&log)?)?;
+        Ok(())
+    }
+}
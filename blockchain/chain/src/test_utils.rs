@@ -0,0 +1,103 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Helpers to build chains in tests.
+
+use crate::{persist_objects, ChainStore};
+use cid::multihash::Code::Blake2b256;
+use cid::Cid;
+use forest_blocks::{BlockHeader, Tipset, TxMeta};
+use forest_db::MemoryDB;
+use forest_legacy_ipld_amt::Amt;
+use forest_networks::ChainConfig;
+use forest_utils::db::BlockstoreExt;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::receipt::Receipt;
+use std::sync::Arc;
+
+/// Returns an in-memory `ChainStore` along with its genesis tipset.
+pub fn setup() -> (ChainStore<MemoryDB>, Arc<Tipset>) {
+    let gen_block = BlockHeader::builder()
+        .miner_address(Address::new_id(0))
+        .build()
+        .unwrap();
+    let cs = ChainStore::new(
+        MemoryDB::default(),
+        Arc::new(ChainConfig::default()),
+        &gen_block,
+    )
+    .unwrap();
+    (cs, Arc::new(Tipset::from(gen_block)))
+}
+
+/// Returns a message from `f0100` to `f0101` with the given sequence.
+pub fn message(sequence: u64) -> Message {
+    Message {
+        version: 0,
+        from: Address::new_id(100),
+        to: Address::new_id(101),
+        sequence,
+        value: Default::default(),
+        method_num: 0,
+        params: RawBytes::default(),
+        gas_limit: 0,
+        gas_fee_cap: Default::default(),
+        gas_premium: Default::default(),
+    }
+}
+
+/// Returns a successful receipt with the given gas usage.
+pub fn receipt(gas_used: i64) -> Receipt {
+    Receipt {
+        exit_code: ExitCode::OK,
+        return_data: RawBytes::default(),
+        gas_used,
+    }
+}
+
+/// Persists a child block of `parent` including `msgs`, with `receipts` as the receipts of
+/// the messages of `parent`.
+pub fn child<DB: Blockstore>(
+    db: &DB,
+    parent: &Tipset,
+    miner: u64,
+    msgs: &[Message],
+    receipts: &[Receipt],
+) -> Arc<Tipset> {
+    child_at(db, parent, parent.epoch() + 1, miner, msgs, receipts)
+}
+
+/// Same as [`child`], at the given epoch, the epochs in between being null rounds.
+pub fn child_at<DB: Blockstore>(
+    db: &DB,
+    parent: &Tipset,
+    epoch: ChainEpoch,
+    miner: u64,
+    msgs: &[Message],
+    receipts: &[Receipt],
+) -> Arc<Tipset> {
+    let bls_cids = msgs
+        .iter()
+        .map(|msg| db.put_obj(msg, Blake2b256))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let meta = TxMeta {
+        bls_message_root: Amt::new_from_iter(db, bls_cids).unwrap(),
+        secp_message_root: Amt::new_from_iter(db, Vec::<Cid>::new()).unwrap(),
+    };
+    let header = BlockHeader::builder()
+        .parents(parent.key().clone())
+        .epoch(epoch)
+        .miner_address(Address::new_id(miner))
+        .messages(db.put_obj(&meta, Blake2b256).unwrap())
+        .message_receipts(Amt::new_from_iter(db, receipts.iter().cloned()).unwrap())
+        .build()
+        .unwrap();
+    persist_objects(db, &[header.clone()]).unwrap();
+    Arc::new(Tipset::from(header))
+}
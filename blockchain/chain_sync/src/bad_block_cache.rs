@@ -2,44 +2,404 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use cid::Cid;
+use forest_db::Store;
+use forest_encoding::tuple::*;
+use forest_libp2p::{PeerId, PeerManager};
+use fvm_ipld_encoding::{from_slice, to_vec};
+use log::{debug, warn};
 use lru::LruCache;
 use parking_lot::Mutex;
 use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BAD_BLOCK_PREFIX: &[u8] = b"bad_block/";
+
+/// Key of the range of sequence numbers of the persisted entries.
+const BAD_BLOCK_RANGE_KEY: &str = "bad_block_range";
+
+const DEFAULT_CACHE_SIZE: NonZeroUsize = forest_utils::const_option!(NonZeroUsize::new(1 << 15));
+
+/// Number of block sources remembered to attribute bad blocks to the peers they came from.
+const DEFAULT_SOURCES_SIZE: NonZeroUsize = forest_utils::const_option!(NonZeroUsize::new(1 << 12));
+
+/// Bad block, as persisted in the database.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+struct BadBlock {
+    cid: Cid,
+    reason: String,
+    /// Peer the block was received from, if known.
+    peer: Option<String>,
+    /// Time the block was marked bad in UNIX seconds.
+    timestamp: u64,
+    /// Time after which the block is no longer considered bad in UNIX seconds, `None` if it
+    /// never expires.
+    expiration: Option<u64>,
+}
+
+impl BadBlock {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expiration
+            .map_or(false, |expiration| now >= expiration)
+    }
+}
+
+/// Entries are keyed by sequence number, so that they can all be found without scanning the
+/// database for the prefix, which not all stores support.
+fn bad_block_key(seq: u64) -> Vec<u8> {
+    let mut key = BAD_BLOCK_PREFIX.to_vec();
+    key.extend(seq.to_be_bytes());
+    key
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Bad blocks with their sequence numbers, and the range of sequence numbers in the database.
+struct Entries {
+    cache: LruCache<Cid, (u64, BadBlock)>,
+    start: u64,
+    end: u64,
+}
 
 /// Thread-safe cache for tracking bad blocks.
 /// This cache is checked before validating a block, to ensure no duplicate work.
-#[derive(Debug)]
-pub struct BadBlockCache {
-    cache: Mutex<LruCache<Cid, String>>,
+///
+/// Entries are persisted in the database, so that bad chains aren't downloaded and validated
+/// again after a restart, and expire after the configured time to live. They are loaded when
+/// the cache is created, and the least recently used ones are dropped once it's full.
+pub struct BadBlockCache<DB> {
+    db: DB,
+    entries: Mutex<Entries>,
+    /// Peers that recently sent blocks, to record where bad blocks came from.
+    sources: Mutex<LruCache<Cid, PeerId>>,
+    ttl: Option<Duration>,
+    peer_manager: Arc<PeerManager>,
 }
 
-impl Default for BadBlockCache {
-    fn default() -> Self {
-        Self::new(forest_utils::const_option!(NonZeroUsize::new(1 << 15)))
+impl<DB> BadBlockCache<DB>
+where
+    DB: Store,
+{
+    /// Creates a cache backed by `db`, whose entries expire after `ttl` unless it's `None`. The
+    /// peers bad blocks came from are reported to `peer_manager`.
+    pub fn new(db: DB, peer_manager: Arc<PeerManager>, ttl: Option<Duration>) -> Self {
+        Self::with_capacity(db, peer_manager, ttl, DEFAULT_CACHE_SIZE)
     }
-}
 
-impl BadBlockCache {
-    pub fn new(cap: NonZeroUsize) -> Self {
+    pub fn with_capacity(
+        db: DB,
+        peer_manager: Arc<PeerManager>,
+        ttl: Option<Duration>,
+        cap: NonZeroUsize,
+    ) -> Self {
+        let entries = Self::load(&db, cap).unwrap_or_else(|e| {
+            warn!("Failed to load the bad blocks: {e}");
+            Entries {
+                cache: LruCache::new(cap),
+                start: 0,
+                end: 0,
+            }
+        });
         Self {
-            cache: Mutex::new(LruCache::new(cap)),
+            db,
+            entries: Mutex::new(entries),
+            sources: Mutex::new(LruCache::new(DEFAULT_SOURCES_SIZE)),
+            ttl,
+            peer_manager,
         }
     }
 
-    /// Puts a bad block `Cid` in the cache with a given reason.
+    /// Loads the persisted entries, deleting the expired ones. When entries were removed, the
+    /// others are moved after the range so that their sequence numbers are contiguous again.
+    fn load(db: &DB, cap: NonZeroUsize) -> anyhow::Result<Entries> {
+        let (start, end): (u64, u64) = match db.read(BAD_BLOCK_RANGE_KEY)? {
+            Some(bz) => from_slice(&bz)?,
+            None => (0, 0),
+        };
+        let keys: Vec<_> = (start..end).map(bad_block_key).collect();
+        let now = unix_secs();
+        let mut live = Vec::new();
+        for bz in db.bulk_read(&keys)?.into_iter().flatten() {
+            match from_slice::<BadBlock>(&bz) {
+                Ok(entry) if entry.is_expired(now) => debug!(
+                    "Bad block {} marked at {} from peer {:?} expired: {}",
+                    entry.cid, entry.timestamp, entry.peer, entry.reason
+                ),
+                Ok(entry) => live.push(entry),
+                Err(e) => warn!("Failed to decode a bad block: {e}"),
+            }
+        }
+        // Only the most recent entries fit in the cache
+        live.drain(..live.len().saturating_sub(cap.get()));
+
+        let mut entries = Entries {
+            cache: LruCache::new(cap),
+            start,
+            end,
+        };
+        if live.len() as u64 == end - start {
+            for (seq, entry) in (start..end).zip(live) {
+                entries.cache.put(entry.cid, (seq, entry));
+            }
+            return Ok(entries);
+        }
+
+        entries.start = end;
+        let mut values = Vec::with_capacity(live.len());
+        for entry in live {
+            let seq = entries.end;
+            entries.end += 1;
+            values.push((bad_block_key(seq), to_vec(&entry)?));
+            entries.cache.put(entry.cid, (seq, entry));
+        }
+        db.bulk_write(&values)?;
+        db.write(BAD_BLOCK_RANGE_KEY, to_vec(&(entries.start, entries.end))?)?;
+        db.bulk_delete(&keys)?;
+        Ok(entries)
+    }
+
+    /// Records the peer that sent the blocks, for bad blocks to be attributed to it.
+    pub fn record_source(&self, cids: &[Cid], peer: PeerId) {
+        let mut sources = self.sources.lock();
+        for cid in cids {
+            sources.put(*cid, peer);
+        }
+    }
+
+    /// Puts a bad block `Cid` in the cache with a given reason, expiring after the time to live.
+    /// The peer the block came from, if known, is reported to the peer manager the first time
+    /// the block is marked bad.
     pub fn put(&self, c: Cid, reason: String) -> Option<String> {
-        self.cache.lock().put(c, reason)
+        let expiration = self
+            .ttl
+            .map(|ttl| unix_secs().saturating_add(ttl.as_secs()));
+        let peer = self.sources.lock().peek(&c).copied();
+        let old = self.insert(c, reason, peer, expiration);
+        if let (None, Some(peer)) = (&old, peer) {
+            self.report_peer(peer);
+        }
+        old
+    }
+
+    /// Puts a bad block `Cid` in the cache with a given reason, which never expires.
+    pub fn put_permanent(&self, c: Cid, reason: String) -> Option<String> {
+        self.insert(c, reason, None, None)
+    }
+
+    fn insert(
+        &self,
+        c: Cid,
+        reason: String,
+        peer: Option<PeerId>,
+        expiration: Option<u64>,
+    ) -> Option<String> {
+        let entry = BadBlock {
+            cid: c,
+            reason,
+            peer: peer.map(|peer| peer.to_string()),
+            timestamp: unix_secs(),
+            expiration,
+        };
+        let mut entries = self.entries.lock();
+        let seq = match entries.cache.peek(&c) {
+            Some((seq, _)) => *seq,
+            None => {
+                let seq = entries.end;
+                entries.end += 1;
+                self.write(
+                    BAD_BLOCK_RANGE_KEY.as_bytes(),
+                    &(entries.start, entries.end),
+                );
+                seq
+            }
+        };
+        self.write(&bad_block_key(seq), &entry);
+        match entries.cache.push(c, (seq, entry)) {
+            Some((cid, (_, old))) if cid == c => Some(old.reason),
+            Some((cid, (seq, _))) => {
+                // The least recently used entry was dropped
+                self.delete(&cid, seq);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn write<T: serde::Serialize>(&self, key: &[u8], value: &T) {
+        match to_vec(value) {
+            Ok(bz) => {
+                if let Err(e) = self.db.write(key, bz) {
+                    warn!("Failed to persist a bad block: {e}");
+                }
+            }
+            Err(e) => warn!("Failed to encode a bad block: {e}"),
+        }
+    }
+
+    fn delete(&self, c: &Cid, seq: u64) {
+        if let Err(e) = self.db.delete(bad_block_key(seq)) {
+            warn!("Failed to delete bad block {c}: {e}");
+        }
+    }
+
+    fn report_peer(&self, peer: PeerId) {
+        let peer_manager = self.peer_manager.clone();
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move { peer_manager.report_bad_block(peer).await });
+            }
+            Err(_) => debug!("Can't report peer {peer} for a bad block outside of a runtime"),
+        }
     }
 
     /// Returns `Some` with the reason if the block CID is in bad block cache.
     /// This also updates the key to the head of the cache.
     pub fn get(&self, c: &Cid) -> Option<String> {
-        self.cache.lock().get(c).cloned()
+        self.lookup(c, true).map(|entry| entry.reason)
     }
 
     /// Returns `Some` with the reason if the block CID is in bad block cache.
     /// This function does not update the head position of the `Cid` key.
     pub fn peek(&self, c: &Cid) -> Option<String> {
-        self.cache.lock().peek(c).cloned()
+        self.lookup(c, false).map(|entry| entry.reason)
+    }
+
+    /// Returns the bad block entry of the block CID. Expired entries are removed.
+    fn lookup(&self, c: &Cid, promote: bool) -> Option<BadBlock> {
+        let mut entries = self.entries.lock();
+        let (seq, entry) = if promote {
+            entries.cache.get(c)
+        } else {
+            entries.cache.peek(c)
+        }?
+        .clone();
+        if entry.is_expired(unix_secs()) {
+            debug!(
+                "Bad block {c} marked at {} from peer {:?} expired: {}",
+                entry.timestamp, entry.peer, entry.reason
+            );
+            entries.cache.pop(c);
+            self.delete(c, seq);
+            return None;
+        }
+        Some(entry)
+    }
+
+    /// Removes a block `Cid` from the cache, returning the reason it was marked bad.
+    pub fn remove(&self, c: &Cid) -> Option<String> {
+        let (seq, entry) = self.entries.lock().cache.pop(c)?;
+        self.delete(c, seq);
+        Some(entry.reason)
+    }
+
+    /// Removes all the blocks from the cache and the database.
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock();
+        let keys: Vec<_> = (entries.start..entries.end).map(bad_block_key).collect();
+        if let Err(e) = self.db.bulk_delete(&keys) {
+            warn!("Failed to delete the bad blocks: {e}");
+        }
+        entries.start = entries.end;
+        self.write(
+            BAD_BLOCK_RANGE_KEY.as_bytes(),
+            &(entries.start, entries.end),
+        );
+        entries.cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cid::multihash::{Code::Blake2b256, MultihashDigest};
+    use forest_db::MemoryDB;
+
+    fn cid(i: u8) -> Cid {
+        Cid::new_v1(fvm_ipld_encoding::DAG_CBOR, Blake2b256.digest(&[i]))
+    }
+
+    fn persisted(db: &MemoryDB, seq: u64) -> Option<Cid> {
+        db.read(bad_block_key(seq))
+            .unwrap()
+            .map(|bz| from_slice::<BadBlock>(&bz).unwrap().cid)
+    }
+
+    #[test]
+    fn bad_blocks_persisted() {
+        let db = MemoryDB::default();
+        let peer_manager = Arc::new(PeerManager::default());
+        let cache = BadBlockCache::new(db.clone(), peer_manager.clone(), None);
+        cache.put(cid(0), "invalid".to_owned());
+        cache.put_permanent(cid(1), "marked".to_owned());
+
+        // Entries are loaded from the database after a restart.
+        let cache = BadBlockCache::new(db.clone(), peer_manager.clone(), None);
+        assert_eq!(cache.peek(&cid(0)).as_deref(), Some("invalid"));
+        assert_eq!(cache.get(&cid(1)).as_deref(), Some("marked"));
+        assert_eq!(cache.remove(&cid(0)).as_deref(), Some("invalid"));
+        assert_eq!(persisted(&db, 0), None);
+
+        // Cleared entries are deleted from the database.
+        cache.clear();
+        assert_eq!(persisted(&db, 1), None);
+        let cache = BadBlockCache::new(db.clone(), peer_manager.clone(), None);
+        assert_eq!(cache.peek(&cid(0)), None);
+        assert_eq!(cache.peek(&cid(1)), None);
+
+        // Entries expire after the time to live.
+        let cache = BadBlockCache::new(db, peer_manager, Some(Duration::ZERO));
+        cache.put(cid(2), "invalid".to_owned());
+        cache.put_permanent(cid(3), "marked".to_owned());
+        assert_eq!(cache.peek(&cid(2)), None);
+        assert_eq!(cache.peek(&cid(3)).as_deref(), Some("marked"));
+    }
+
+    #[test]
+    fn expired_bad_blocks_swept_at_startup() {
+        let db = MemoryDB::default();
+        let peer_manager = Arc::new(PeerManager::default());
+        let cache = BadBlockCache::new(db.clone(), peer_manager.clone(), Some(Duration::ZERO));
+        cache.put(cid(0), "invalid".to_owned());
+        cache.put_permanent(cid(1), "marked".to_owned());
+        cache.put(cid(2), "invalid".to_owned());
+        assert_eq!(persisted(&db, 0), Some(cid(0)));
+
+        // The expired entries are deleted, and the others moved after them.
+        let cache = BadBlockCache::new(db.clone(), peer_manager, None);
+        assert_eq!((0..3).find_map(|seq| persisted(&db, seq)), None);
+        assert_eq!(persisted(&db, 3), Some(cid(1)));
+        assert_eq!(cache.peek(&cid(1)).as_deref(), Some("marked"));
+        cache.put_permanent(cid(4), "marked".to_owned());
+        assert_eq!(persisted(&db, 4), Some(cid(4)));
+    }
+
+    #[test]
+    fn least_recently_used_bad_blocks_dropped() {
+        let db = MemoryDB::default();
+        let peer_manager = Arc::new(PeerManager::default());
+        let cap = NonZeroUsize::new(2).unwrap();
+        let cache = BadBlockCache::with_capacity(db.clone(), peer_manager.clone(), None, cap);
+        cache.put_permanent(cid(0), "marked".to_owned());
+        cache.put_permanent(cid(1), "marked".to_owned());
+        assert!(cache.get(&cid(0)).is_some());
+        cache.put_permanent(cid(2), "marked".to_owned());
+        assert_eq!(cache.peek(&cid(1)), None);
+        assert_eq!(persisted(&db, 1), None);
+
+        // Putting an entry again keeps its place in the database.
+        assert_eq!(
+            cache
+                .put_permanent(cid(2), "remarked".to_owned())
+                .as_deref(),
+            Some("marked")
+        );
+        let cache = BadBlockCache::with_capacity(db, peer_manager, None, cap);
+        assert_eq!(cache.peek(&cid(0)).as_deref(), Some("marked"));
+        assert_eq!(cache.peek(&cid(2)).as_deref(), Some("remarked"));
     }
 }
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use crate::bad_block_cache::BadBlockCache;
+use crate::clock_drift::ClockDriftEstimator;
 use crate::consensus::Consensus;
 use crate::metrics;
 use crate::network_context::SyncNetworkContext;
@@ -18,8 +19,9 @@ use forest_chain::{ChainStore, Error as ChainStoreError};
 use forest_db::Store;
 use forest_libp2p::PeerManager;
 use forest_libp2p::{
-    hello::HelloRequest, rpc::RequestResponseError, NetworkEvent, NetworkMessage, PeerId,
-    PubsubMessage,
+    hello::{unix_nanos, HelloRequest},
+    rpc::RequestResponseError,
+    NetworkEvent, NetworkMessage, PeerId, PubsubMessage,
 };
 use forest_message::SignedMessage;
 use forest_message_pool::{MessagePool, Provider};
@@ -37,7 +39,7 @@ use std::task::{Context, Poll};
 use thiserror::Error;
 
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 pub(crate) type WorkerState = Arc<RwLock<SyncState>>;
 
@@ -65,15 +67,23 @@ pub enum ChainMuxerError<C: Consensus> {
     Block(#[from] ForestBlockError),
     #[error("Following network unexpectedly failed: {0}")]
     NetworkFollowingFailure(String),
+    #[error("Tipset does not include the checkpoint: {0:?}")]
+    ChainWithoutCheckpoint(TipsetKeys),
 }
 
 /// Structure that defines syncing configuration options
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(default)]
 pub struct SyncConfig {
     /// Request window length for tipsets during chain exchange
     pub req_window: i64,
     /// Sample size of tipsets to acquire before determining what the network head is
     pub tipset_sample_size: usize,
+    /// Estimated clock drift, in milliseconds, above which warnings are logged
+    pub clock_drift_warn_threshold_ms: u64,
+    /// Time after which blocks that failed validation are no longer considered bad, in seconds,
+    /// 0 to never expire them. Blocks marked bad manually never expire.
+    pub bad_block_ttl_secs: u64,
 }
 
 impl Default for SyncConfig {
@@ -81,6 +91,9 @@ impl Default for SyncConfig {
         Self {
             req_window: 200,
             tipset_sample_size: 5,
+            // Blocks more than `ALLOWABLE_CLOCK_DRIFT` ahead are rejected.
+            clock_drift_warn_threshold_ms: fvm_shared::ALLOWABLE_CLOCK_DRIFT * 1000,
+            bad_block_ttl_secs: 7 * 24 * 60 * 60,
         }
     }
 }
@@ -130,8 +143,11 @@ pub struct ChainMuxer<DB, M, C: Consensus> {
     genesis: Arc<Tipset>,
 
     /// Bad blocks cache, updates based on invalid state transitions.
-    /// Will mark any invalid blocks and all children as bad in this bounded cache
-    bad_blocks: Arc<BadBlockCache>,
+    /// Will mark any invalid blocks and all children as bad in this bounded and persisted cache
+    bad_blocks: Arc<BadBlockCache<DB>>,
+
+    /// Estimator of the offset of the local clock from the network
+    clock_drift: Arc<ClockDriftEstimator>,
 
     /// Incoming network events to be handled by synchronizer
     net_handler: flume::Receiver<NetworkEvent>,
@@ -168,6 +184,11 @@ where
         tipset_receiver: flume::Receiver<Arc<Tipset>>,
         cfg: SyncConfig,
     ) -> Result<Self, ChainMuxerError<C>> {
+        let bad_blocks = Arc::new(BadBlockCache::new(
+            state_manager.blockstore().clone(),
+            peer_manager.clone(),
+            (cfg.bad_block_ttl_secs > 0).then(|| Duration::from_secs(cfg.bad_block_ttl_secs)),
+        ));
         let network = SyncNetworkContext::new(
             network_send,
             peer_manager,
@@ -181,7 +202,10 @@ where
             genesis,
             consensus,
             state_manager,
-            bad_blocks: Arc::new(BadBlockCache::default()),
+            bad_blocks,
+            clock_drift: Arc::new(ClockDriftEstimator::new(Duration::from_millis(
+                cfg.clock_drift_warn_threshold_ms,
+            ))),
             net_handler: network_rx,
             mpool,
             tipset_sender,
@@ -191,10 +215,15 @@ where
     }
 
     /// Returns a clone of the bad blocks cache to be used outside of chain sync.
-    pub fn bad_blocks_cloned(&self) -> Arc<BadBlockCache> {
+    pub fn bad_blocks_cloned(&self) -> Arc<BadBlockCache<DB>> {
         self.bad_blocks.clone()
     }
 
+    /// Returns a clone of the clock drift estimator to be used outside of chain sync.
+    pub fn clock_drift_cloned(&self) -> Arc<ClockDriftEstimator> {
+        self.clock_drift.clone()
+    }
+
     /// Returns a cloned `Arc` of the sync worker state.
     pub fn sync_state_cloned(&self) -> WorkerState {
         self.worker_state.clone()
@@ -246,6 +275,7 @@ where
         chain_store: Arc<ChainStore<DB>>,
         peer_id: PeerId,
         genesis_block_cid: Cid,
+        clock_drift: Arc<ClockDriftEstimator>,
     ) {
         // Query the heaviest TipSet from the store
         let heaviest = chain_store.heaviest_tipset();
@@ -265,14 +295,30 @@ where
                         return;
                     }
                 };
-            let dur = SystemTime::now()
+            let moment_received = SystemTime::now();
+            let dur = moment_received
                 .duration_since(moment_sent)
                 .unwrap_or_default();
 
             // Update the peer metadata based on the response
             match response {
-                Some(Ok(_res)) => {
-                    network.peer_manager().log_success(peer_id, dur).await;
+                Some(Ok(res)) => {
+                    // The round-trip time, without the time the peer took to respond, is the
+                    // initial latency of the peer.
+                    let rtt = res
+                        .processing_time()
+                        .and_then(|processing| dur.checked_sub(processing))
+                        .unwrap_or(dur);
+                    network.peer_manager().set_peer_latency(peer_id, rtt).await;
+                    if let Some(offset) =
+                        res.clock_offset(unix_nanos(moment_sent), unix_nanos(moment_received))
+                    {
+                        debug!(
+                            "Clock offset with {peer_id}: {:.3}ms",
+                            offset as f64 / 1_000_000.0
+                        );
+                        clock_drift.record_peer_offset(offset);
+                    }
                 }
                 Some(Err(why)) => match why {
                     RequestResponseError::ConnectionClosed
@@ -349,11 +395,12 @@ where
         event: NetworkEvent,
         network: SyncNetworkContext<DB>,
         chain_store: Arc<ChainStore<DB>>,
-        bad_block_cache: Arc<BadBlockCache>,
+        bad_block_cache: Arc<BadBlockCache<DB>>,
         mem_pool: Arc<MessagePool<M>>,
         genesis: Arc<Tipset>,
         message_processing_strategy: PubsubMessageProcessingStrategy,
         block_delay: u64,
+        clock_drift: Arc<ClockDriftEstimator>,
     ) -> Result<Option<(FullTipset, PeerId)>, ChainMuxerError<C>> {
         let (tipset, source) = match event {
             NetworkEvent::HelloRequestInbound { source, request } => {
@@ -408,6 +455,7 @@ where
                     chain_store.clone(),
                     peer_id,
                     *genesis.blocks()[0].cid(),
+                    clock_drift,
                 ));
                 return Ok(None);
             }
@@ -427,6 +475,7 @@ where
                     metrics::LIBP2P_MESSAGE_TOTAL
                         .with_label_values(&[metrics::values::PUBSUB_BLOCK])
                         .inc();
+                    clock_drift.record_block_timestamp(b.header.timestamp());
                     // Assemble full tipset from block
                     let tipset =
                         Self::gossipsub_block_to_full_tipset(b, source, network.clone()).await?;
@@ -468,6 +517,9 @@ where
             }
         };
 
+        // Remember where the blocks came from, in case they turn out to be bad
+        bad_block_cache.record_source(tipset.key().cids(), source);
+
         // Validate tipset
         if let Err(why) = TipsetValidator(&tipset).validate(
             chain_store.clone(),
@@ -483,6 +535,18 @@ where
             return Err(why.into());
         }
 
+        // Reject chains that don't include the checkpoint
+        let ts = Arc::new(tipset.clone().into_tipset());
+        if !chain_store.includes_checkpoint(&[ts.clone()])? {
+            warn!(
+                "Dropping tipset {:?} received through GossipSub that does not include the checkpoint",
+                tipset.key()
+            );
+            return Err(ChainMuxerError::ChainWithoutCheckpoint(
+                tipset.key().clone(),
+            ));
+        }
+
         // Store block messages in the block store
         for block in tipset.blocks() {
             forest_chain::persist_objects(&chain_store.db, &[block.header()])?;
@@ -492,10 +556,7 @@ where
 
         // Update the peer head
         // TODO: Determine if this can be executed concurrently
-        network
-            .peer_manager()
-            .update_peer_head(source, Arc::new(tipset.clone().into_tipset()))
-            .await;
+        network.peer_manager().update_peer_head(source, ts).await;
         metrics::PEER_TIPSET_EPOCH
             .with_label_values(&[source.to_string().as_str()])
             .set(tipset.epoch());
@@ -512,6 +573,7 @@ where
         let mem_pool = self.mpool.clone();
         let tipset_sample_size = self.sync_config.tipset_sample_size;
         let block_delay = self.state_manager.chain_config().block_delay_secs;
+        let clock_drift = self.clock_drift.clone();
 
         let evaluator = async move {
             let mut tipsets = vec![];
@@ -533,6 +595,7 @@ where
                     genesis.clone(),
                     PubsubMessageProcessingStrategy::Process,
                     block_delay,
+                    clock_drift.clone(),
                 )
                 .await
                 {
@@ -631,6 +694,7 @@ where
         let bad_block_cache = self.bad_blocks.clone();
         let mem_pool = self.mpool.clone();
         let block_delay = self.state_manager.chain_config().block_delay_secs;
+        let clock_drift = self.clock_drift.clone();
         let stream_processor: ChainMuxerFuture<(), ChainMuxerError<C>> = Box::pin(async move {
             loop {
                 let event = match p2p_messages.recv_async().await {
@@ -650,6 +714,7 @@ where
                     genesis.clone(),
                     PubsubMessageProcessingStrategy::DoNotProcess,
                     block_delay,
+                    clock_drift.clone(),
                 )
                 .await
                 {
@@ -723,6 +788,7 @@ where
         let mem_pool = self.mpool.clone();
         let tipset_sender = self.tipset_sender.clone();
         let block_delay = self.state_manager.chain_config().block_delay_secs;
+        let clock_drift = self.clock_drift.clone();
         let stream_processor: ChainMuxerFuture<UnexpectedReturnKind, ChainMuxerError<C>> = Box::pin(
             async move {
                 // If a tipset has been provided, pass it to the tipset processor
@@ -753,6 +819,7 @@ where
                         genesis.clone(),
                         PubsubMessageProcessingStrategy::Process,
                         block_delay,
+                        clock_drift.clone(),
                     )
                     .await
                     {
@@ -0,0 +1,232 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::metrics;
+use log::warn;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Number of samples kept per source.
+const MAX_SAMPLES: usize = 32;
+/// Number of samples of a source required before estimating the drift from it.
+const MIN_SAMPLES: usize = 3;
+/// Minimum interval between two warnings about the drift.
+const WARNING_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Delay in milliseconds between the timestamp of a gossiped block and its reception that is
+/// accounted to the propagation of the block rather than to the drift, same as the propagation
+/// delay of Lotus.
+const GOSSIP_PROPAGATION_ALLOWANCE: i64 = 6_000;
+
+#[derive(Default)]
+struct Samples {
+    /// Offsets of the local clock from the clocks of peers, measured by hello exchanges, in
+    /// milliseconds.
+    hello: VecDeque<i64>,
+    /// Delays between the timestamp of gossiped blocks and their reception, in milliseconds.
+    gossip: VecDeque<i64>,
+    last_warning: Option<Instant>,
+}
+
+fn push_sample(samples: &mut VecDeque<i64>, sample: i64) {
+    if samples.len() == MAX_SAMPLES {
+        samples.pop_front();
+    }
+    samples.push_back(sample);
+}
+
+/// Estimates the offset of the local clock from the network, since blocks timestamped ahead of
+/// the local time are rejected.
+///
+/// The clock offsets measured by hello exchanges are the most accurate source, their median is
+/// used once enough peers answered. Otherwise, the shortest delay between the timestamp of
+/// gossiped blocks and their reception is used. As it also includes the propagation time, delays
+/// up to [`GOSSIP_PROPAGATION_ALLOWANCE`] are not counted as drift, while blocks received before
+/// their timestamp always are.
+pub struct ClockDriftEstimator {
+    warn_threshold: Duration,
+    samples: Mutex<Samples>,
+}
+
+impl ClockDriftEstimator {
+    pub fn new(warn_threshold: Duration) -> Self {
+        Self {
+            warn_threshold,
+            samples: Default::default(),
+        }
+    }
+
+    /// Records the offset in nanoseconds of a peer clock, positive if the peer is ahead.
+    pub fn record_peer_offset(&self, offset_nanos: i64) {
+        let mut samples = self.samples.lock();
+        push_sample(&mut samples.hello, -offset_nanos / 1_000_000);
+        self.update(&mut samples);
+    }
+
+    /// Records the reception of a gossiped block, with its timestamp in seconds.
+    pub fn record_block_timestamp(&self, timestamp: u64) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        let mut samples = self.samples.lock();
+        push_sample(
+            &mut samples.gossip,
+            now - (timestamp as i64).saturating_mul(1000),
+        );
+        self.update(&mut samples);
+    }
+
+    /// Estimated offset in milliseconds of the local clock from the network, positive if the
+    /// local clock is ahead. `None` until enough samples are collected.
+    pub fn estimate_millis(&self) -> Option<i64> {
+        estimate(&self.samples.lock())
+    }
+
+    fn update(&self, samples: &mut Samples) {
+        let drift = match estimate(samples) {
+            Some(drift) => drift,
+            None => return,
+        };
+        metrics::CLOCK_DRIFT.set(drift);
+
+        if drift.unsigned_abs() as u128 <= self.warn_threshold.as_millis() {
+            return;
+        }
+        let now = Instant::now();
+        if samples
+            .last_warning
+            .map_or(true, |last| now.duration_since(last) >= WARNING_INTERVAL)
+        {
+            samples.last_warning = Some(now);
+            warn!(
+                "The local clock is estimated to be {}ms {} the network, over the {}ms threshold. \
+                Blocks may be rejected as coming from the future, check the time synchronization \
+                of this machine (e.g. NTP).",
+                drift.unsigned_abs(),
+                if drift > 0 { "ahead of" } else { "behind" },
+                self.warn_threshold.as_millis()
+            );
+        }
+    }
+}
+
+fn estimate(samples: &Samples) -> Option<i64> {
+    if samples.hello.len() >= MIN_SAMPLES {
+        let mut hello: Vec<_> = samples.hello.iter().copied().collect();
+        hello.sort_unstable();
+        Some(hello[hello.len() / 2])
+    } else if samples.gossip.len() >= MIN_SAMPLES {
+        samples.gossip.iter().copied().min().map(|delay| {
+            if delay > GOSSIP_PROPAGATION_ALLOWANCE {
+                delay - GOSSIP_PROPAGATION_ALLOWANCE
+            } else {
+                delay.min(0)
+            }
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(hello: &[i64], gossip: &[i64]) -> Samples {
+        Samples {
+            hello: hello.iter().copied().collect(),
+            gossip: gossip.iter().copied().collect(),
+            last_warning: None,
+        }
+    }
+
+    #[test]
+    fn hello_offsets_median() {
+        let estimator = ClockDriftEstimator::new(Duration::from_secs(1));
+        estimator.record_peer_offset(1_000_000);
+        estimator.record_peer_offset(200_000_000);
+        assert_eq!(estimator.estimate_millis(), None);
+        // The peers are ahead, so the local clock is behind
+        estimator.record_peer_offset(2_000_000);
+        assert_eq!(estimator.estimate_millis(), Some(-2));
+
+        assert_eq!(estimate(&samples(&[5, -300, 1, 2], &[])), Some(2));
+        // Hello samples take precedence over gossip ones
+        assert_eq!(estimate(&samples(&[5, 1, 2], &[-9_000; 3])), Some(2));
+    }
+
+    #[test]
+    fn samples_are_bounded() {
+        let mut samples = VecDeque::new();
+        for i in 0..MAX_SAMPLES as i64 + 2 {
+            push_sample(&mut samples, i);
+        }
+        assert_eq!(samples.len(), MAX_SAMPLES);
+        assert_eq!(samples.front(), Some(&2));
+    }
+
+    #[test]
+    fn gossip_fallback() {
+        assert_eq!(estimate(&samples(&[], &[])), None);
+        assert_eq!(estimate(&samples(&[5, 1], &[7_000, 8_000])), None);
+        // The shortest delay is used, minus the propagation allowance
+        assert_eq!(
+            estimate(&samples(&[5, 1], &[7_000, 8_000, 9_000])),
+            Some(1_000)
+        );
+        // Usual propagation delays are not counted as drift
+        assert_eq!(estimate(&samples(&[], &[500, 2_000, 6_000])), Some(0));
+        // Blocks received before their timestamp are
+        assert_eq!(
+            estimate(&samples(&[], &[-1_500, 2_000, 3_000])),
+            Some(-1_500)
+        );
+    }
+
+    #[test]
+    fn gossiped_blocks_are_recorded() {
+        let estimator = ClockDriftEstimator::new(Duration::from_secs(1));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        for _ in 0..MIN_SAMPLES {
+            estimator.record_block_timestamp(now - 1);
+        }
+        assert_eq!(estimator.estimate_millis(), Some(0));
+        // Blocks from 20s in the future
+        for _ in 0..MIN_SAMPLES {
+            estimator.record_block_timestamp(now + 20);
+        }
+        let drift = estimator.estimate_millis().unwrap();
+        assert!((-20_000..-18_000).contains(&drift), "{drift}");
+    }
+
+    #[test]
+    fn warnings_are_rate_limited() {
+        let threshold = Duration::from_secs(1);
+        let estimator = ClockDriftEstimator::new(threshold);
+        for offset in [-500, 500, 900] {
+            estimator.record_peer_offset(offset * 1_000_000);
+        }
+        assert_eq!(estimator.samples.lock().last_warning, None);
+
+        let estimator = ClockDriftEstimator::new(threshold);
+        let last_warning = || estimator.samples.lock().last_warning;
+        for _ in 0..MIN_SAMPLES {
+            estimator.record_peer_offset(5_000_000_000);
+        }
+        let warned_at = last_warning().unwrap();
+        estimator.record_peer_offset(5_000_000_000);
+        assert_eq!(last_warning(), Some(warned_at));
+
+        // Once the interval elapsed, the drift is reported again
+        let long_ago = Instant::now()
+            .checked_sub(WARNING_INTERVAL + Duration::from_secs(1))
+            .unwrap();
+        estimator.samples.lock().last_warning = Some(long_ago);
+        estimator.record_peer_offset(5_000_000_000);
+        assert!(last_warning().unwrap() > long_ago + WARNING_INTERVAL);
+    }
+}
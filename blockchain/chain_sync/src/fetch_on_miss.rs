@@ -0,0 +1,278 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::network_context::SyncNetworkContext;
+use cid::Cid;
+use forest_db::Store;
+use forest_libp2p::{NetworkMessage, PeerManager};
+use fvm_ipld_blockstore::Blockstore;
+use log::debug;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// Maximum time a call can spend fetching missing blocks.
+const FETCH_DEADLINE: Duration = Duration::from_secs(60);
+/// Maximum number of missing blocks a call can fetch.
+const MAX_FETCHED_BLOCKS: usize = 1024;
+
+/// Limits the blocks fetched on behalf of a single call, shared by the clones of the store.
+#[derive(Debug)]
+struct FetchBudget {
+    deadline: Instant,
+    remaining: AtomicUsize,
+}
+
+impl FetchBudget {
+    fn new() -> Self {
+        Self {
+            deadline: Instant::now() + FETCH_DEADLINE,
+            remaining: AtomicUsize::new(MAX_FETCHED_BLOCKS),
+        }
+    }
+
+    /// Takes a miss from the budget, returning the time left to fetch it.
+    fn take(&self) -> Option<Duration> {
+        let time_left = self.deadline.checked_duration_since(Instant::now())?;
+        self.remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .ok()?;
+        Some(time_left)
+    }
+}
+
+/// Blockstore fetching the blocks missing from the underlying store over `Bitswap`, so that nodes
+/// synced from a minimal snapshot can still load historical state, e.g. for `StateCall`. Fetched
+/// blocks are checked against their CID by `Bitswap` before being written to the underlying
+/// store.
+///
+/// [`Blockstore`] being synchronous, a miss blocks the current thread until the block is received
+/// or the `Bitswap` timeout elapses. Outside of a multi-threaded `tokio` runtime, misses are
+/// returned as is. The time spent and the number of blocks fetched are bounded, use
+/// [`Self::for_call`] to get a store with a fresh budget for each call.
+pub struct FetchOnMissBlockstore<DB> {
+    db: DB,
+    network: SyncNetworkContext<DB>,
+    budget: Arc<FetchBudget>,
+}
+
+impl<DB: Clone> Clone for FetchOnMissBlockstore<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            db: self.db.clone(),
+            network: self.network.clone(),
+            budget: self.budget.clone(),
+        }
+    }
+}
+
+impl<DB> FetchOnMissBlockstore<DB>
+where
+    DB: Blockstore + Clone,
+{
+    pub fn new(
+        db: DB,
+        network_send: flume::Sender<NetworkMessage>,
+        peer_manager: Arc<PeerManager>,
+    ) -> Self {
+        Self {
+            network: SyncNetworkContext::new(network_send, peer_manager, db.clone()),
+            db,
+            budget: Arc::new(FetchBudget::new()),
+        }
+    }
+
+    /// Returns a store sharing the same underlying store, with a new fetch budget.
+    pub fn for_call(&self) -> Self {
+        Self {
+            budget: Arc::new(FetchBudget::new()),
+            ..self.clone()
+        }
+    }
+
+    fn fetch(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        let handle = match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => handle,
+            _ => return Ok(None),
+        };
+        let time_left = match self.budget.take() {
+            Some(time_left) => time_left,
+            None => {
+                debug!("Fetch budget exhausted, not fetching missing block {k}");
+                return Ok(None);
+            }
+        };
+        let fetched = tokio::task::block_in_place(|| {
+            handle.block_on(tokio::time::timeout(
+                time_left,
+                self.network.bitswap_get_block(*k),
+            ))
+        });
+        match fetched {
+            Ok(Ok(data)) => Ok(Some(data)),
+            Ok(Err(e)) => {
+                debug!("Failed to fetch missing block {k}: {e}");
+                Ok(None)
+            }
+            Err(_) => {
+                debug!("Fetch deadline reached, missing block {k}");
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<DB> Blockstore for FetchOnMissBlockstore<DB>
+where
+    DB: Blockstore + Clone,
+{
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        match self.db.get(k)? {
+            Some(data) => Ok(Some(data)),
+            None => self.fetch(k),
+        }
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.db.put_keyed(k, block)
+    }
+
+    fn has(&self, k: &Cid) -> anyhow::Result<bool> {
+        self.db.has(k)
+    }
+}
+
+impl<DB: Store> Store for FetchOnMissBlockstore<DB> {
+    fn read<K>(&self, key: K) -> Result<Option<Vec<u8>>, forest_db::Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.db.read(key)
+    }
+
+    fn write<K, V>(&self, key: K, value: V) -> Result<(), forest_db::Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        self.db.write(key, value)
+    }
+
+    fn delete<K>(&self, key: K) -> Result<(), forest_db::Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.db.delete(key)
+    }
+
+    fn exists<K>(&self, key: K) -> Result<bool, forest_db::Error>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.db.exists(key)
+    }
+
+    fn flush(&self) -> Result<(), forest_db::Error> {
+        self.db.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cid::multihash::{Code::Blake2b256, MultihashDigest};
+    use forest_db::MemoryDB;
+
+    fn store(
+        db: &MemoryDB,
+    ) -> (
+        FetchOnMissBlockstore<MemoryDB>,
+        flume::Receiver<NetworkMessage>,
+    ) {
+        let (network_send, network_rx) = flume::unbounded();
+        let store = FetchOnMissBlockstore::new(db.clone(), network_send, Default::default());
+        (store, network_rx)
+    }
+
+    fn block() -> (Cid, Vec<u8>) {
+        let data = b"block".to_vec();
+        (Cid::new_v1(0x55, Blake2b256.digest(&data)), data)
+    }
+
+    #[test]
+    fn misses_are_not_fetched_outside_of_a_runtime() {
+        let (store, network_rx) = store(&MemoryDB::default());
+        assert_eq!(store.get(&block().0).unwrap(), None);
+        assert!(network_rx.is_empty());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn misses_are_not_fetched_in_a_current_thread_runtime() {
+        let (store, network_rx) = store(&MemoryDB::default());
+        assert_eq!(store.get(&block().0).unwrap(), None);
+        assert!(network_rx.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn misses_are_fetched_over_bitswap() {
+        let db = MemoryDB::default();
+        let (store, network_rx) = store(&db);
+        let (cid, data) = block();
+        // Stands for the network service, which writes the block once verified
+        let network = tokio::spawn({
+            let (db, data) = (db.clone(), data.clone());
+            async move {
+                match network_rx.recv_async().await {
+                    Ok(NetworkMessage::BitswapRequest {
+                        cid,
+                        response_channel,
+                    }) => {
+                        db.put_keyed(&cid, &data).unwrap();
+                        response_channel.send(true).unwrap();
+                    }
+                    _ => panic!("Expected a bitswap request"),
+                }
+            }
+        });
+        assert_eq!(store.get(&cid).unwrap(), Some(data));
+        network.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn misses_are_not_fetched_once_the_budget_is_spent() {
+        let (store, network_rx) = store(&MemoryDB::default());
+        store.budget.remaining.store(0, Ordering::Relaxed);
+        assert_eq!(store.get(&block().0).unwrap(), None);
+        assert!(network_rx.is_empty());
+
+        // Each call gets its own budget
+        let call = store.for_call();
+        assert_eq!(
+            call.budget.remaining.load(Ordering::Relaxed),
+            MAX_FETCHED_BLOCKS
+        );
+        assert_eq!(store.budget.remaining.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn budget_is_bounded_by_deadline_and_misses() {
+        let budget = FetchBudget {
+            deadline: Instant::now() + Duration::from_secs(10),
+            remaining: AtomicUsize::new(2),
+        };
+        assert!(budget.take().unwrap() <= Duration::from_secs(10));
+        assert!(budget.take().is_some());
+        assert!(budget.take().is_none());
+
+        let budget = FetchBudget {
+            deadline: Instant::now(),
+            remaining: AtomicUsize::new(2),
+        };
+        assert!(budget.take().is_none());
+    }
+}
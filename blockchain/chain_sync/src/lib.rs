@@ -5,7 +5,9 @@
 
 mod bad_block_cache;
 mod chain_muxer;
+mod clock_drift;
 pub mod consensus;
+mod fetch_on_miss;
 mod metrics;
 mod network_context;
 mod sync_state;
@@ -14,6 +16,8 @@ mod validation;
 
 pub use self::bad_block_cache::BadBlockCache;
 pub use self::chain_muxer::{ChainMuxer, SyncConfig};
+pub use self::clock_drift::ClockDriftEstimator;
 pub use self::consensus::{collect_errs, Consensus};
+pub use self::fetch_on_miss::FetchOnMissBlockstore;
 pub use self::sync_state::{SyncStage, SyncState};
 pub use self::validation::TipsetValidator;
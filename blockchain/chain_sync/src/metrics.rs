@@ -124,6 +124,19 @@ lazy_static! {
             .expect("Registering the last_validated_tipset_epoch metric with the metrics registry must succeed");
         last_validated_tipset_epoch
     };
+    pub static ref CLOCK_DRIFT: Box<GenericGauge<AtomicI64>> = {
+        let clock_drift = Box::new(
+            GenericGauge::new(
+                "clock_drift_milliseconds",
+                "Estimated offset of the local clock from the network, positive if ahead",
+            )
+            .expect("Defining the clock_drift_milliseconds metric must succeed"),
+        );
+        prometheus::default_registry()
+            .register(clock_drift.clone())
+            .expect("Registering the clock_drift_milliseconds metric with the metrics registry must succeed");
+        clock_drift
+    };
     pub static ref PEER_TIPSET_EPOCH: Box<GenericGaugeVec<AtomicI64>> = {
         let peer_tipset_epoch = Box::new(
             GenericGaugeVec::new(
@@ -248,6 +261,7 @@ mod tests {
         test_counter!(TIPSET_RANGE_SYNC_FAILURE_TOTAL);
         test_counter!(HEAD_EPOCH);
         test_counter!(LAST_VALIDATED_TIPSET_EPOCH);
+        test_counter!(CLOCK_DRIFT);
         test_counter!(NETWORK_HEAD_EVALUATION_ERRORS);
         test_counter!(BOOTSTRAP_ERRORS);
         test_counter!(FOLLOW_NETWORK_INTERRUPTIONS);
@@ -73,14 +73,15 @@ where
         self.peer_manager.as_ref()
     }
 
-    /// Send a `chain_exchange` request for only block headers (ignore messages).
+    /// Send a `chain_exchange` request for only block headers (ignore messages), returning them
+    /// along with the peer that sent them.
     /// If `peer_id` is `None`, requests will be sent to a set of shuffled peers.
     pub async fn chain_exchange_headers(
         &self,
         peer_id: Option<PeerId>,
         tsk: &TipsetKeys,
         count: u64,
-    ) -> Result<Vec<Arc<Tipset>>, String> {
+    ) -> Result<(PeerId, Vec<Arc<Tipset>>), String> {
         self.handle_chain_exchange_request(peer_id, tsk, count, HEADERS)
             .await
     }
@@ -92,8 +93,10 @@ where
         tsk: &TipsetKeys,
         count: u64,
     ) -> Result<Vec<CompactedMessages>, String> {
-        self.handle_chain_exchange_request(peer_id, tsk, count, MESSAGES)
-            .await
+        let (_, messages) = self
+            .handle_chain_exchange_request(peer_id, tsk, count, MESSAGES)
+            .await?;
+        Ok(messages)
     }
 
     /// Send a `chain_exchange` request for a single full tipset (includes messages)
@@ -103,7 +106,7 @@ where
         peer_id: Option<PeerId>,
         tsk: &TipsetKeys,
     ) -> Result<FullTipset, String> {
-        let mut fts = self
+        let (_, mut fts) = self
             .handle_chain_exchange_request(peer_id, tsk, 1, HEADERS | MESSAGES)
             .await?;
 
@@ -128,6 +131,41 @@ where
             return Ok(b);
         }
 
+        let success = self.bitswap_request(content).await?;
+
+        match self.db.get_obj(&content) {
+            Ok(Some(b)) => Ok(b),
+            Ok(None) => Err(format!(
+                "Not found in db, bitswap. success: {success} cid, {content:?}"
+            )),
+            Err(e) => Err(format!(
+                "Error retrieving from db. success: {success} cid, {content:?}, {e}"
+            )),
+        }
+    }
+
+    /// Same as [`Self::bitswap_get`], returning the raw block rather than deserializing it.
+    pub async fn bitswap_get_block(&self, content: Cid) -> Result<Vec<u8>, String> {
+        if let Some(b) = self.db.get(&content).map_err(|e| e.to_string())? {
+            return Ok(b);
+        }
+
+        let success = self.bitswap_request(content).await?;
+
+        match self.db.get(&content) {
+            Ok(Some(b)) => Ok(b),
+            Ok(None) => Err(format!(
+                "Not found in db, bitswap. success: {success} cid, {content:?}"
+            )),
+            Err(e) => Err(format!(
+                "Error retrieving from db. success: {success} cid, {content:?}, {e}"
+            )),
+        }
+    }
+
+    /// Sends a `Bitswap` request for `content`, which is written to the `BlockStore` once
+    /// received. Returns whether it was received before the timeout.
+    async fn bitswap_request(&self, content: Cid) -> Result<bool, String> {
         let (tx, rx) = flume::bounded(1);
 
         self.network_send
@@ -142,28 +180,20 @@ where
             rx.recv_timeout(BITSWAP_TIMEOUT).unwrap_or_default()
         })
         .await
-        .is_ok();
-
-        match self.db.get_obj(&content) {
-            Ok(Some(b)) => Ok(b),
-            Ok(None) => Err(format!(
-                "Not found in db, bitswap. success: {success} cid, {content:?}"
-            )),
-            Err(e) => Err(format!(
-                "Error retrieving from db. success: {success} cid, {content:?}, {e}"
-            )),
-        }
+        .unwrap_or_default();
+        Ok(success)
     }
 
     /// Helper function to handle the peer retrieval if no peer supplied as well as the logging
-    /// and updating of the peer info in the `PeerManager`.
+    /// and updating of the peer info in the `PeerManager`. Returns the peer that responded along
+    /// with the response.
     async fn handle_chain_exchange_request<T>(
         &self,
         peer_id: Option<PeerId>,
         tsk: &TipsetKeys,
         request_len: u64,
         options: u64,
-    ) -> Result<Vec<T>, String>
+    ) -> Result<(PeerId, Vec<T>), String>
     where
         T: TryFrom<TipsetBundle, Error = String> + Send + Sync + 'static,
     {
@@ -178,19 +208,22 @@ where
         let lookup_failures = Arc::new(AtomicU64::new(0));
         let chain_exchange_result = match peer_id {
             // Specific peer is given to send request, send specifically to that peer.
-            Some(id) => Self::chain_exchange_request(
-                self.peer_manager.clone(),
-                self.network_send.clone(),
+            Some(id) => (
                 id,
-                request,
-            )
-            .await?
-            .into_result()?,
+                Self::chain_exchange_request(
+                    self.peer_manager.clone(),
+                    self.network_send.clone(),
+                    id,
+                    request,
+                )
+                .await?
+                .into_result()?,
+            ),
             None => {
                 // Control max num of concurrent jobs
                 let (n_task_control_tx, n_task_control_rx) =
                     flume::bounded(MAX_CONCURRENT_CHAIN_EXCHANGE_REQUESTS);
-                let (result_tx, result_rx) = flume::bounded::<Vec<T>>(1);
+                let (result_tx, result_rx) = flume::bounded::<(PeerId, Vec<T>)>(1);
                 // No specific peer set, send requests to a shuffled set of top peers until
                 // a request succeeds.
                 let peers = self.peer_manager.top_peers_shuffled().await;
@@ -217,7 +250,7 @@ where
                                 Ok(chain_exchange_result) => {
                                     match chain_exchange_result.into_result() {
                                         Ok(r) => {
-                                            _ = result_tx.send_async(r).await;
+                                            _ = result_tx.send_async((peer_id, r)).await;
                                         }
                                         Err(e) => {
                                             lookup_failures.fetch_add(1, Ordering::Relaxed);
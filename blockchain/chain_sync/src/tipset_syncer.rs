@@ -90,6 +90,8 @@ pub enum TipsetRangeSyncerError<C: Consensus> {
     ChainForkLengthExceedsFinalityThreshold,
     #[error("Chain for block forked from local chain at genesis, refusing to sync block: {0}")]
     ForkAtGenesisBlock(String),
+    #[error("Chain does not include the checkpoint, refusing to sync tipset: {0:?}")]
+    ChainWithoutCheckpoint(TipsetKeys),
     #[error("Querying tipsets from the network failed: {0}")]
     NetworkTipsetQueryFailed(String),
     #[error("Query tipset messages from the network failed: {0}")]
@@ -252,7 +254,7 @@ pub(crate) struct TipsetProcessor<DB, C: Consensus> {
     state_manager: Arc<StateManager<DB>>,
     network: SyncNetworkContext<DB>,
     chain_store: Arc<ChainStore<DB>>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     genesis: Arc<Tipset>,
 }
 
@@ -269,7 +271,7 @@ where
         state_manager: Arc<StateManager<DB>>,
         network: SyncNetworkContext<DB>,
         chain_store: Arc<ChainStore<DB>>,
-        bad_block_cache: Arc<BadBlockCache>,
+        bad_block_cache: Arc<BadBlockCache<DB>>,
         genesis: Arc<Tipset>,
     ) -> Self {
         Self {
@@ -621,7 +623,7 @@ pub(crate) struct TipsetRangeSyncer<DB, C: Consensus> {
     state_manager: Arc<StateManager<DB>>,
     network: SyncNetworkContext<DB>,
     chain_store: Arc<ChainStore<DB>>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     genesis: Arc<Tipset>,
     consensus: Arc<C>,
 }
@@ -640,7 +642,7 @@ where
         state_manager: Arc<StateManager<DB>>,
         network: SyncNetworkContext<DB>,
         chain_store: Arc<ChainStore<DB>>,
-        bad_block_cache: Arc<BadBlockCache>,
+        bad_block_cache: Arc<BadBlockCache<DB>>,
         genesis: Arc<Tipset>,
     ) -> Result<Self, TipsetRangeSyncerError<C>> {
         let tipset_tasks = Box::pin(FuturesUnordered::new());
@@ -760,7 +762,7 @@ fn sync_tipset_range<DB: Blockstore + Store + Clone + Sync + Send + 'static, C:
     state_manager: Arc<StateManager<DB>>,
     chain_store: Arc<ChainStore<DB>>,
     network: SyncNetworkContext<DB>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     genesis: Arc<Tipset>,
 ) -> TipsetRangeSyncerFuture<C> {
     Box::pin(async move {
@@ -846,7 +848,7 @@ async fn sync_headers_in_reverse<
     tipset_range_length: u64,
     proposed_head: Arc<Tipset>,
     current_head: &Tipset,
-    bad_block_cache: &BadBlockCache,
+    bad_block_cache: &BadBlockCache<DB>,
     chain_store: &ChainStore<DB>,
     network: SyncNetworkContext<DB>,
 ) -> Result<Vec<Arc<Tipset>>, TipsetRangeSyncerError<C>> {
@@ -884,7 +886,7 @@ async fn sync_headers_in_reverse<
         // TODO: Tweak request window when socket frame is tested
         let epoch_diff = oldest_parent.epoch() - current_head.epoch();
         let window = min(epoch_diff, MAX_TIPSETS_TO_REQUEST as i64);
-        let network_tipsets = network
+        let (source, network_tipsets) = network
             .chain_exchange_headers(None, oldest_parent.parents(), window as u64)
             .await
             .map_err(TipsetRangeSyncerError::NetworkTipsetQueryFailed)?;
@@ -895,6 +897,8 @@ async fn sync_headers_in_reverse<
                 break 'sync;
             }
             validate_tipset_against_cache(bad_block_cache, tipset.key(), &parent_blocks)?;
+            // Remember where the blocks came from, in case they turn out to be bad
+            bad_block_cache.record_source(tipset.cids(), source);
             parent_blocks.extend_from_slice(tipset.cids());
             tracker.write().set_epoch(tipset.epoch());
             parent_tipsets.push(tipset);
@@ -911,10 +915,13 @@ async fn sync_headers_in_reverse<
     if oldest_tipset.parents() != current_head.parents() {
         info!("Fork detected, searching for a common ancestor between the local chain and the network chain");
         const FORK_LENGTH_THRESHOLD: u64 = 500;
-        let fork_tipsets = network
+        let (source, fork_tipsets) = network
             .chain_exchange_headers(None, oldest_tipset.parents(), FORK_LENGTH_THRESHOLD)
             .await
             .map_err(TipsetRangeSyncerError::NetworkTipsetQueryFailed)?;
+        for tipset in &fork_tipsets {
+            bad_block_cache.record_source(tipset.cids(), source);
+        }
         let mut potential_common_ancestor = chain_store.tipset_from_keys(current_head.parents())?;
         let mut i = 0;
         let mut fork_length = 1;
@@ -954,6 +961,11 @@ async fn sync_headers_in_reverse<
             }
         }
     }
+    if !chain_store.includes_checkpoint(&parent_tipsets)? {
+        return Err(TipsetRangeSyncerError::ChainWithoutCheckpoint(
+            proposed_head.key().clone(),
+        ));
+    }
     Ok(parent_tipsets)
 }
 
@@ -964,7 +976,7 @@ fn sync_tipset<DB: Blockstore + Store + Clone + Sync + Send + 'static, C: Consen
     state_manager: Arc<StateManager<DB>>,
     chain_store: Arc<ChainStore<DB>>,
     network: SyncNetworkContext<DB>,
-    bad_block_cache: Arc<BadBlockCache>,
+    bad_block_cache: Arc<BadBlockCache<DB>>,
     genesis: Arc<Tipset>,
 ) -> TipsetRangeSyncerFuture<C> {
     Box::pin(async move {
@@ -1028,8 +1040,8 @@ async fn fetch_batch<DB: Blockstore + Store + Clone + Send + Sync + 'static, C:
         for (messages, tipset) in compacted_messages.into_iter().rev().zip(batch.iter()) {
             // Construct full tipset from fetched messages
             let bundle = TipsetBundle {
-                blocks: tipset.blocks().to_vec(),
-                messages: Some(messages),
+                blocks: Arc::new(tipset.blocks().to_vec()),
+                messages: Some(Arc::new(messages)),
             };
 
             let full_tipset = FullTipset::try_from(&bundle)
@@ -1062,7 +1074,7 @@ async fn sync_messages_check_state<
     state_manager: Arc<StateManager<DB>>,
     network: SyncNetworkContext<DB>,
     chainstore: Arc<ChainStore<DB>>,
-    bad_block_cache: &BadBlockCache,
+    bad_block_cache: &BadBlockCache<DB>,
     tipsets: Vec<Arc<Tipset>>,
     genesis: &Tipset,
     invalid_block_strategy: InvalidBlockStrategy,
@@ -1133,7 +1145,7 @@ async fn validate_tipset<DB: Blockstore + Store + Clone + Send + Sync + 'static,
     consensus: Arc<C>,
     state_manager: Arc<StateManager<DB>>,
     chainstore: &ChainStore<DB>,
-    bad_block_cache: &BadBlockCache,
+    bad_block_cache: &BadBlockCache<DB>,
     full_tipset: FullTipset,
     genesis: &Tipset,
     invalid_block_strategy: InvalidBlockStrategy,
@@ -1352,6 +1364,13 @@ async fn validate_block<DB: Blockstore + Store + Clone + Sync + Send + 'static,
                 receipt_root
             )));
         }
+
+        // The state now matches the commitment of a child header, it is safe to persist it
+        if let Err(e) =
+            v_state_manager.save_validated_tipset_state(&v_base_tipset, &(state_root, receipt_root))
+        {
+            warn!("Failed to persist tipset state: {e}");
+        }
         Ok(())
     }));
 
@@ -1598,8 +1617,8 @@ fn block_timestamp_checks<C: Consensus>(
 
 /// Check if any CID in `tipset` is a known bad block.
 /// If so, add all their descendants to the bad block cache and return an error.
-fn validate_tipset_against_cache<C: Consensus>(
-    bad_block_cache: &BadBlockCache,
+fn validate_tipset_against_cache<DB: Store, C: Consensus>(
+    bad_block_cache: &BadBlockCache<DB>,
     tipset: &TipsetKeys,
     descendant_blocks: &[Cid],
 ) -> Result<(), TipsetRangeSyncerError<C>> {
@@ -1618,14 +1637,46 @@ fn validate_tipset_against_cache<C: Consensus>(
 
 #[cfg(test)]
 mod test {
+    use async_trait::async_trait;
     use cid::Cid;
     use forest_blocks::{BlockHeader, ElectionProof, Ticket, Tipset};
+    use forest_chain::test_utils::{child, setup};
+    use forest_chain::{Scale, Weight};
     use forest_crypto::VRFProof;
+    use forest_libp2p::PeerManager;
     use fvm_shared::address::Address;
     use num_bigint::BigInt;
 
     use super::*;
 
+    #[derive(Debug)]
+    struct NoConsensus;
+
+    impl Scale for NoConsensus {
+        fn weight<DB>(_: &DB, ts: &Tipset) -> Result<Weight, anyhow::Error>
+        where
+            DB: Blockstore,
+        {
+            Ok(ts.epoch().into())
+        }
+    }
+
+    #[async_trait]
+    impl Consensus for NoConsensus {
+        type Error = String;
+
+        async fn validate_block<DB>(
+            &self,
+            _: Arc<StateManager<DB>>,
+            _: Arc<Block>,
+        ) -> Result<(), NonEmpty<Self::Error>>
+        where
+            DB: Blockstore + Store + Clone + Sync + Send + 'static,
+        {
+            Ok(())
+        }
+    }
+
     pub fn mock_block(id: u64, weight: u64, ticket_sequence: u64) -> BlockHeader {
         let addr = Address::new_id(id);
         let cid =
@@ -1671,4 +1722,42 @@ mod test {
         assert_eq!(index, 2);
         assert_eq!(weight, &BigInt::from(10));
     }
+
+    #[tokio::test]
+    async fn headers_without_checkpoint_are_rejected() {
+        let (cs, genesis) = setup();
+        let db = cs.blockstore().clone();
+        let a1 = child(&db, &genesis, 1, &[], &[]);
+        let a2 = child(&db, &a1, 1, &[], &[]);
+        let b1 = child(&db, &genesis, 2, &[], &[]);
+        let b2 = child(&db, &b1, 2, &[], &[]);
+        cs.set_checkpoint(b1).unwrap();
+
+        let peer_manager = Arc::new(PeerManager::default());
+        let bad_block_cache = BadBlockCache::new(db.clone(), peer_manager.clone(), None);
+        // The headers are all in the store, the network is never queried
+        let (network_send, _network_rx) = flume::bounded(1);
+        let network = SyncNetworkContext::new(network_send, peer_manager, db);
+        let (genesis, bad_block_cache, cs) = (&*genesis, &bad_block_cache, &cs);
+        let sync_headers = move |proposed_head| {
+            sync_headers_in_reverse::<_, NoConsensus>(
+                Default::default(),
+                MAX_TIPSETS_TO_REQUEST,
+                proposed_head,
+                genesis,
+                bad_block_cache,
+                cs,
+                network.clone(),
+            )
+        };
+
+        assert!(matches!(
+            sync_headers(a2).await,
+            Err(TipsetRangeSyncerError::ChainWithoutCheckpoint(_))
+        ));
+        assert_eq!(
+            sync_headers(b2).await.map(|tipsets| tipsets.len()).ok(),
+            Some(3)
+        );
+    }
 }
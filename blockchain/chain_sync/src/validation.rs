@@ -6,6 +6,7 @@ use crate::bad_block_cache::BadBlockCache;
 use cid::{multihash::Code::Blake2b256, Cid};
 use forest_blocks::{Block, FullTipset, Tipset, TxMeta};
 use forest_chain::ChainStore;
+use forest_db::Store;
 use forest_legacy_ipld_amt::{Amt, Error as IpldAmtError};
 use forest_message::SignedMessage;
 use forest_utils::db::BlockstoreExt;
@@ -55,10 +56,10 @@ impl From<IpldAmtError> for Box<TipsetValidationError> {
 pub struct TipsetValidator<'a>(pub &'a FullTipset);
 
 impl<'a> TipsetValidator<'a> {
-    pub fn validate<DB: Blockstore>(
+    pub fn validate<DB: Blockstore + Store>(
         &self,
         chainstore: Arc<ChainStore<DB>>,
-        bad_block_cache: Arc<BadBlockCache>,
+        bad_block_cache: Arc<BadBlockCache<DB>>,
         genesis_tipset: Arc<Tipset>,
         block_delay: u64,
     ) -> Result<(), Box<TipsetValidationError>> {
@@ -4,6 +4,7 @@
 pub mod chain_rand;
 mod errors;
 mod metrics;
+mod tipset_state_index;
 mod utils;
 mod vm_circ_supply;
 
@@ -18,7 +19,9 @@ use forest_beacon::{BeaconSchedule, DrandBeacon};
 use forest_blocks::{BlockHeader, Tipset, TipsetKeys};
 use forest_chain::{ChainStore, HeadChange};
 use forest_db::Store;
-use forest_interpreter::{resolve_to_key_addr, BlockMessages, RewardCalc, VM};
+use forest_interpreter::{
+    resolve_to_key_addr, BlockMessages, ExecutionTrace, RewardCalc, VMTrace, VM,
+};
 use forest_json::message_receipt;
 use forest_legacy_ipld_amt::Amt;
 use forest_message::{ChainMessage, Message as MessageTrait};
@@ -178,6 +181,31 @@ pub struct InvocResult {
     #[serde(with = "message_receipt::json::opt")]
     pub msg_rct: Option<Receipt>,
     pub error: Option<String>,
+    pub execution_trace: Option<ExecutionTrace>,
+}
+
+impl InvocResult {
+    /// Creates an invocation result of an applied message, including its execution trace if the
+    /// VM was traced.
+    pub fn from_apply_ret(msg: Message, ret: &ApplyRet) -> Self {
+        let execution_trace =
+            (!ret.exec_trace.is_empty()).then(|| ExecutionTrace::from_apply_ret(&msg, ret));
+        InvocResult {
+            msg,
+            msg_rct: Some(ret.msg_receipt.clone()),
+            error: ret.failure_info.as_ref().map(|e| e.to_string()),
+            execution_trace,
+        }
+    }
+}
+
+/// Output of a tipset state computation with every applied message traced.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ComputeStateOutput {
+    #[serde(with = "forest_json::cid")]
+    pub root: Cid,
+    pub trace: Vec<InvocResult>,
 }
 
 /// An alias Result that represents an `InvocResult` and an Error.
@@ -217,7 +245,9 @@ where
         reward_calc: Arc<dyn RewardCalc>,
     ) -> Result<Self, anyhow::Error> {
         let genesis = cs.genesis()?.context("genesis header missing")?;
-        let beacon = Arc::new(chain_config.get_beacon_schedule(genesis.timestamp())?);
+        let beacon = Arc::new(
+            chain_config.get_beacon_schedule(genesis.timestamp(), cs.blockstore().clone())?,
+        );
 
         Ok(Self {
             cs,
@@ -355,6 +385,7 @@ where
         base_fee: TokenAmount,
         mut callback: Option<CB>,
         tipset: &Arc<Tipset>,
+        enable_tracing: VMTrace,
     ) -> Result<CidPair, anyhow::Error>
     where
         R: Rand + Clone + 'static,
@@ -378,6 +409,7 @@ where
                 chain_epoch_root(Arc::clone(self), Arc::clone(tipset)),
                 &self.engine,
                 Arc::clone(self.chain_config()),
+                enable_tracing,
             )
         };
 
@@ -413,14 +445,20 @@ where
         Ok((state_root, receipt_root))
     }
 
-    /// Returns the pair of (parent state root, message receipt root). This will either be cached
-    /// or will be calculated and fill the cache. Tipset state for a given tipset is guaranteed
-    /// not to be computed twice.
+    /// Returns the pair of (parent state root, message receipt root). This will either be cached,
+    /// loaded from the persisted tipset state index or will be calculated and fill the cache.
+    /// Tipset state for a given tipset is guaranteed not to be computed twice.
     #[instrument(skip(self))]
     pub async fn tipset_state(self: &Arc<Self>, tipset: &Arc<Tipset>) -> anyhow::Result<CidPair> {
         let key = tipset.key();
         self.cache
             .get_or_else(key, || async move {
+                if let Some(cid_pair) =
+                    tipset_state_index::load_tipset_state(self.blockstore(), key)?
+                {
+                    return Ok(cid_pair);
+                }
+
                 let cid_pair = if tipset.epoch() == 0 {
                     // NB: This is here because the process that executes blocks requires that the
                     // block miner reference a valid miner in the state tree. Unless we create some
@@ -435,7 +473,9 @@ where
                     // generic constants are not implemented yet this is a lowcost method for now
                     let no_func =
                         None::<fn(&Cid, &ChainMessage, &ApplyRet) -> Result<(), anyhow::Error>>;
-                    let ts_state = self.compute_tipset_state(tipset, no_func).await?;
+                    let ts_state = self
+                        .compute_tipset_state(tipset, no_func, VMTrace::NotTraced)
+                        .await?;
                     debug!("Completed tipset state calculation {:?}", tipset.cids());
                     ts_state
                 };
@@ -445,36 +485,113 @@ where
             .await
     }
 
+    /// Records the state of a tipset in the persisted tipset state index. This must only be
+    /// called once the state has been validated against the header of a child block, which
+    /// commits to the parent state root and receipt root.
+    pub fn save_validated_tipset_state(
+        &self,
+        tipset: &Tipset,
+        cid_pair: &CidPair,
+    ) -> anyhow::Result<()> {
+        tipset_state_index::save_tipset_state(self.blockstore(), tipset.key(), cid_pair)
+    }
+
+    /// Re-executes every tipset of the heaviest chain with an epoch within `from..=to`,
+    /// bypassing the cached and persisted tipset states. Each computed state is checked against
+    /// the parent state and receipt roots of the child tipset header before being persisted;
+    /// the heaviest tipset, having no child yet, is only cached.
+    ///
+    /// Returns the number of recomputed tipsets.
+    pub async fn recompute_tipset_states(
+        self: &Arc<Self>,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> anyhow::Result<usize> {
+        let mut recomputed = 0;
+        let head = self.cs.heaviest_tipset();
+        let (mut child, mut tipset) = if to < head.epoch() {
+            // Start from the first tipset after the range, without loading the ones above it
+            let child = self.cs.tipset_by_height(to + 1, head, false)?;
+            let tipset = self.cs.tipset_from_keys(child.parents())?;
+            (Some(child), tipset)
+        } else {
+            (None, head)
+        };
+        while tipset.epoch() >= from {
+            if tipset.epoch() > 0 {
+                let no_func =
+                    None::<fn(&Cid, &ChainMessage, &ApplyRet) -> Result<(), anyhow::Error>>;
+                let cid_pair = self
+                    .compute_tipset_state(&tipset, no_func, VMTrace::NotTraced)
+                    .await?;
+                if let Some(child) = &child {
+                    let (state_root, receipt_root) = &cid_pair;
+                    let header = child.min_ticket_block();
+                    anyhow::ensure!(
+                        state_root == header.state_root(),
+                        "Parent state root of epoch {} did not match computed state: {} (header), {} (computed)",
+                        child.epoch(),
+                        header.state_root(),
+                        state_root
+                    );
+                    anyhow::ensure!(
+                        receipt_root == header.message_receipts(),
+                        "Parent receipt root of epoch {} did not match computed root: {} (header), {} (computed)",
+                        child.epoch(),
+                        header.message_receipts(),
+                        receipt_root
+                    );
+                    self.save_validated_tipset_state(&tipset, &cid_pair)?;
+                }
+                self.cache.insert(tipset.key().clone(), cid_pair);
+                recomputed += 1;
+            }
+            if tipset.epoch() == 0 {
+                break;
+            }
+            let parent = self.cs.tipset_from_keys(tipset.parents())?;
+            child = Some(tipset);
+            tipset = parent;
+        }
+        Ok(recomputed)
+    }
+
     #[instrument(skip(self, rand))]
-    fn call_raw(
+    fn call_raw<S>(
         self: &Arc<Self>,
         msg: &mut Message,
         rand: ChainRand<DB>,
         tipset: &Arc<Tipset>,
-    ) -> StateCallResult {
+        store: S,
+    ) -> StateCallResult
+    where
+        S: Blockstore + Store + Clone + Send + Sync + 'static,
+    {
         let bstate = tipset.parent_state();
         let bheight = tipset.epoch();
-        let store = self.blockstore().clone();
+        let circ_supply = self
+            .genesis_info
+            .get_circulating_supply(bheight, &store, bstate)?;
         let mut vm = VM::new(
             *bstate,
-            store,
+            store.clone(),
             bheight,
             rand,
             TokenAmount::zero(),
-            self.genesis_info
-                .get_circulating_supply(bheight, self.blockstore(), bstate)?,
+            circ_supply,
             self.reward_calc.clone(),
             chain_epoch_root(Arc::clone(self), Arc::clone(tipset)),
             &self.engine,
             Arc::clone(self.chain_config()),
+            VMTrace::Traced,
         )?;
 
         if msg.gas_limit == 0 {
             msg.gas_limit = 10000000000;
         }
 
-        let actor = self
-            .get_actor(&msg.from, *bstate)?
+        let actor = StateTree::new_from_root(store, bstate)?
+            .get_actor(&msg.from)?
             .ok_or_else(|| Error::Other("Could not get actor".to_string()))?;
         msg.sequence = actor.sequence;
         let apply_ret = vm.apply_implicit_message(msg)?;
@@ -488,11 +605,7 @@ where
             warn!("chain call failed: {:?}", err);
         }
 
-        Ok(InvocResult {
-            msg: msg.clone(),
-            msg_rct: Some(apply_ret.msg_receipt.clone()),
-            error: apply_ret.failure_info.map(|e| e.to_string()),
-        })
+        Ok(InvocResult::from_apply_ret(msg.clone(), &apply_ret))
     }
 
     /// runs the given message and returns its result without any persisted changes.
@@ -503,7 +616,23 @@ where
     ) -> StateCallResult {
         let ts = tipset.unwrap_or_else(|| self.cs.heaviest_tipset());
         let chain_rand = self.chain_rand(ts.key().to_owned());
-        self.call_raw(message, chain_rand, &ts)
+        self.call_raw(message, chain_rand, &ts, self.blockstore().clone())
+    }
+
+    /// Same as [`Self::call`], loading the state from `store` rather than the store of the
+    /// state manager, e.g. a store fetching missing blocks from the network.
+    pub fn call_with_store<S>(
+        self: &Arc<Self>,
+        message: &mut Message,
+        tipset: Option<Arc<Tipset>>,
+        store: S,
+    ) -> StateCallResult
+    where
+        S: Blockstore + Store + Clone + Send + Sync + 'static,
+    {
+        let ts = tipset.unwrap_or_else(|| self.cs.heaviest_tipset());
+        let chain_rand = self.chain_rand(ts.key().to_owned());
+        self.call_raw(message, chain_rand, &ts, store)
     }
 
     /// Computes message on the given [Tipset] state, after applying other messages and returns
@@ -536,6 +665,7 @@ where
             chain_epoch_root(Arc::clone(self), Arc::clone(&ts)),
             &self.engine,
             Arc::clone(self.chain_config()),
+            VMTrace::NotTraced,
         )?;
 
         for msg in prior_messages {
@@ -553,15 +683,18 @@ where
             msg: message.message().clone(),
             msg_rct: Some(ret.msg_receipt.clone()),
             error: ret.failure_info.map(|e| e.to_string()),
+            execution_trace: None,
         })
     }
 
     /// Replays the given message and returns the result of executing the indicated message,
-    /// assuming it was executed in the indicated tipset.
+    /// assuming it was executed in the indicated tipset. The execution events of the messages are
+    /// only recorded with [`VMTrace::Traced`].
     pub async fn replay(
         self: &Arc<Self>,
         ts: &Arc<Tipset>,
         mcid: Cid,
+        enable_tracing: VMTrace,
     ) -> Result<(Message, ApplyRet), Error> {
         const ERROR_MSG: &str = "replay_halt";
 
@@ -577,7 +710,9 @@ where
             }
             Ok(())
         };
-        let result = self.compute_tipset_state(ts, Some(callback)).await;
+        let result = self
+            .compute_tipset_state(ts, Some(callback), enable_tracing)
+            .await;
 
         if let Err(error_message) = result {
             if error_message.to_string() != ERROR_MSG {
@@ -597,6 +732,32 @@ where
         Ok((out_mes, out_ret))
     }
 
+    /// Recomputes the state of the given tipset, returning the resulting state root along
+    /// with the invocation result and execution trace of every applied message, including
+    /// implicit reward and cron messages.
+    pub async fn compute_state_traced(
+        self: &Arc<Self>,
+        ts: &Arc<Tipset>,
+    ) -> Result<ComputeStateOutput, Error> {
+        // Callback execution is synchronous, see `replay`
+        let (tx, rx) = std::sync::mpsc::channel();
+        let callback = move |_: &Cid, msg: &ChainMessage, apply_ret: &ApplyRet| {
+            tx.send(InvocResult::from_apply_ret(
+                msg.message().clone(),
+                apply_ret,
+            ))?;
+            Ok(())
+        };
+        let (root, _) = self
+            .compute_tipset_state(ts, Some(callback), VMTrace::Traced)
+            .await?;
+
+        Ok(ComputeStateOutput {
+            root,
+            trace: rx.try_iter().collect(),
+        })
+    }
+
     /// Gets look-back tipset for block validations.
     ///
     /// The look-back tipset for a round is the tipset with epoch `round - chain_finality`.
@@ -706,6 +867,7 @@ where
         self: &Arc<Self>,
         tipset: &Arc<Tipset>,
         callback: Option<CB>,
+        enable_tracing: VMTrace,
     ) -> Result<CidPair, Error>
     where
         CB: FnMut(&Cid, &ChainMessage, &ApplyRet) -> Result<(), anyhow::Error> + Send,
@@ -765,6 +927,7 @@ where
                 base_fee.into(),
                 callback,
                 &ts_cloned,
+                enable_tracing,
             )?)
         })
         .await
@@ -878,6 +1041,13 @@ where
     }
     /// Returns a message receipt from a given tipset and message CID.
     pub fn get_receipt(&self, tipset: Arc<Tipset>, msg: Cid) -> Result<Receipt, Error> {
+        if let Some((_, receipt)) = self
+            .cs
+            .search_msg_index(tipset.clone(), &msg)
+            .map_err(|e| Error::Other(e.to_string()))?
+        {
+            return Ok(receipt);
+        }
         let m = forest_chain::get_chain_message(self.blockstore(), &msg)
             .map_err(|e| Error::Other(e.to_string()))?;
         let message_var = (m.from(), &m.sequence());
@@ -899,6 +1069,33 @@ where
         Ok(message_receipt)
     }
 
+    /// Searches the current chain for a message in the message index. Returns the tipset in which
+    /// the message was executed along with its receipt.
+    ///
+    /// The chain is not walked back on an index miss: only the head tipset is checked, so
+    /// messages of tipsets that were never indexed are not found until they are backfilled with
+    /// `ChainStore::backfill_msg_index`.
+    pub fn search_for_message(
+        &self,
+        msg_cid: Cid,
+    ) -> Result<Option<(Arc<Tipset>, Receipt)>, Error> {
+        let current_tipset = self.cs.heaviest_tipset();
+        if let Some(found) = self
+            .cs
+            .search_msg_index(current_tipset.clone(), &msg_cid)
+            .map_err(|e| Error::Other(e.to_string()))?
+        {
+            return Ok(Some(found));
+        }
+
+        let message = forest_chain::get_chain_message(self.blockstore(), &msg_cid)
+            .map_err(|err| Error::Other(format!("failed to load message {err:}")))?;
+        let message_var = (message.from(), &message.sequence());
+        Ok(self
+            .tipset_executed_message(&current_tipset, msg_cid, message_var)?
+            .map(|receipt| (current_tipset, receipt)))
+    }
+
     /// `WaitForMessage` blocks until a message appears on chain. It looks backwards in the
     /// chain to see if this has already happened. It guarantees that the message has been on chain
     /// for at least confidence epochs without being reverted before returning.
@@ -915,8 +1112,18 @@ where
         let message = forest_chain::get_chain_message(self.blockstore(), &msg_cid)
             .map_err(|err| Error::Other(format!("failed to load message {err:}")))?;
 
-        let message_var = (message.from(), &message.sequence());
         let current_tipset = self.cs.heaviest_tipset();
+        if let Some((tipset, receipt)) = self
+            .cs
+            .search_msg_index(current_tipset.clone(), &msg_cid)
+            .map_err(|e| Error::Other(e.to_string()))?
+        {
+            if current_tipset.epoch() >= tipset.epoch() + confidence {
+                return Ok((Some(tipset), Some(receipt)));
+            }
+        }
+
+        let message_var = (message.from(), &message.sequence());
         let maybe_message_reciept =
             self.tipset_executed_message(&current_tipset, msg_cid, message_var)?;
         if let Some(r) = maybe_message_reciept {
@@ -1150,6 +1357,9 @@ where
         ps.miner_nominal_power_meets_consensus_minimum(policy, self.blockstore(), addr)
     }
 
+    /// Re-computes the states of the tipsets from `ts` down to `height`, checking them against
+    /// the headers of their children. If `height` is a null round, the chain is validated down to
+    /// the last tipset before it.
     pub async fn validate_chain(
         self: &Arc<Self>,
         mut ts: Arc<Tipset>,
@@ -1162,7 +1372,7 @@ where
             );
         }
         let mut ts_chain = Vec::<Arc<Tipset>>::new();
-        while ts.epoch() != height {
+        while ts.epoch() > height {
             let next = self.cs.tipset_from_keys(ts.parents())?;
             ts_chain.push(std::mem::replace(&mut ts, next));
         }
@@ -1221,3 +1431,61 @@ where
         Ok(st)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cid::multihash::{Code::Blake2b256, MultihashDigest};
+    use forest_db::MemoryDB;
+    use fvm_ipld_encoding::DAG_CBOR;
+
+    fn cid(i: u8) -> Cid {
+        Cid::new_v1(DAG_CBOR, Blake2b256.digest(&[i]))
+    }
+
+    #[tokio::test]
+    async fn validate_chain_down_to_null_round() {
+        let db = MemoryDB::default();
+        let chain_config = Arc::new(ChainConfig::default());
+        let genesis = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap();
+        let cs = Arc::new(ChainStore::new(db, chain_config.clone(), &genesis).unwrap());
+        cs.set_genesis(&genesis).unwrap();
+        let sm = Arc::new(
+            StateManager::new(
+                cs.clone(),
+                chain_config,
+                Arc::new(forest_interpreter::RewardActorMessageCalc),
+            )
+            .unwrap(),
+        );
+
+        // Epoch 2 is a null round
+        let parent = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .parents(TipsetKeys::new(vec![*genesis.cid()]))
+            .epoch(1)
+            .build()
+            .unwrap();
+        let head = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .parents(TipsetKeys::new(vec![*parent.cid()]))
+            .state_root(cid(1))
+            .message_receipts(cid(2))
+            .epoch(3)
+            .build()
+            .unwrap();
+        forest_chain::persist_objects(cs.blockstore(), &[parent.clone(), head.clone()]).unwrap();
+        let parent = Arc::new(Tipset::from(parent));
+        let head = Arc::new(Tipset::from(head));
+
+        // The states are not computed, the persisted ones are checked against the headers.
+        sm.save_validated_tipset_state(&parent, &(cid(1), cid(2)))
+            .unwrap();
+        sm.save_validated_tipset_state(&head, &(cid(3), cid(4)))
+            .unwrap();
+        sm.validate_chain(head, 2).await.unwrap();
+    }
+}
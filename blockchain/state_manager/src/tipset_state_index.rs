@@ -0,0 +1,119 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Persistent index of computed tipset states, so that tipsets near the head do not have to
+//! be re-executed after a restart.
+
+use crate::CidPair;
+use forest_blocks::TipsetKeys;
+use forest_db::Store;
+use fvm_ipld_encoding::{from_slice, to_vec, Cbor};
+
+const TIPSET_STATE_PREFIX: &[u8] = b"tipset_state/";
+
+fn tipset_state_key(tsk: &TipsetKeys) -> anyhow::Result<Vec<u8>> {
+    let mut key = Vec::new();
+    key.extend_from_slice(TIPSET_STATE_PREFIX);
+    key.extend(tsk.marshal_cbor()?);
+    Ok(key)
+}
+
+/// Returns the persisted `(state root, receipt root)` pair of the tipset, if any.
+pub(crate) fn load_tipset_state<DB: Store>(
+    db: &DB,
+    tsk: &TipsetKeys,
+) -> anyhow::Result<Option<CidPair>> {
+    match db.read(tipset_state_key(tsk)?)? {
+        Some(bytes) => Ok(Some(from_slice(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+/// Persists the `(state root, receipt root)` pair of the tipset.
+pub(crate) fn save_tipset_state<DB: Store>(
+    db: &DB,
+    tsk: &TipsetKeys,
+    cid_pair: &CidPair,
+) -> anyhow::Result<()> {
+    Ok(db.write(tipset_state_key(tsk)?, to_vec(cid_pair)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StateManager;
+    use cid::multihash::{Code::Blake2b256, MultihashDigest};
+    use cid::Cid;
+    use forest_blocks::{BlockHeader, Tipset};
+    use forest_chain::ChainStore;
+    use forest_db::MemoryDB;
+    use forest_networks::ChainConfig;
+    use fvm_ipld_encoding::DAG_CBOR;
+    use fvm_shared::address::Address;
+    use std::sync::Arc;
+
+    fn cid(i: u8) -> Cid {
+        Cid::new_v1(DAG_CBOR, Blake2b256.digest(&[i]))
+    }
+
+    #[test]
+    fn tipset_state_roundtrip() {
+        let db = MemoryDB::default();
+        let tsk = TipsetKeys::new(vec![cid(0), cid(1)]);
+        assert_eq!(load_tipset_state(&db, &tsk).unwrap(), None);
+
+        save_tipset_state(&db, &tsk, &(cid(2), cid(3))).unwrap();
+        assert_eq!(
+            load_tipset_state(&db, &tsk).unwrap(),
+            Some((cid(2), cid(3)))
+        );
+        assert_eq!(
+            load_tipset_state(&db, &TipsetKeys::new(vec![cid(0)])).unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn validated_tipset_state_is_not_recomputed() {
+        let db = MemoryDB::default();
+        let chain_config = Arc::new(ChainConfig::default());
+        let genesis = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap();
+        let cs = Arc::new(ChainStore::new(db.clone(), chain_config.clone(), &genesis).unwrap());
+        cs.set_genesis(&genesis).unwrap();
+
+        // The state root of this tipset doesn't exist, it can't be computed.
+        let header = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .parents(TipsetKeys::new(vec![*genesis.cid()]))
+            .state_root(cid(4))
+            .epoch(1)
+            .build()
+            .unwrap();
+        let ts = Arc::new(Tipset::from(header));
+
+        let sm = Arc::new(
+            StateManager::new(
+                cs.clone(),
+                chain_config.clone(),
+                Arc::new(forest_interpreter::RewardActorMessageCalc),
+            )
+            .unwrap(),
+        );
+        sm.save_validated_tipset_state(&ts, &(cid(5), cid(6)))
+            .unwrap();
+
+        // The persisted state is used after a restart.
+        let sm = Arc::new(
+            StateManager::new(
+                cs,
+                chain_config,
+                Arc::new(forest_interpreter::RewardActorMessageCalc),
+            )
+            .unwrap(),
+        );
+        assert_eq!(sm.tipset_state(&ts).await.unwrap(), (cid(5), cid(6)));
+    }
+}
@@ -3,10 +3,11 @@
 
 use super::{handle_rpc_err, print_rpc_res_bytes, Config};
 use forest_libp2p::{Multiaddr, Protocol};
-use forest_rpc_api::auth_api::AuthNewParams;
-use forest_rpc_client::auth_new;
+use forest_rpc_api::auth_api::{AuthNewParams, AuthRotateSecretParams};
+use forest_rpc_client::{auth_list, auth_new, auth_revoke, auth_rotate_secret};
 use jsonrpc_v2::Error as JsonRpcError;
 use structopt::StructOpt;
+use time::OffsetDateTime;
 
 use forest_auth::*;
 
@@ -17,13 +18,29 @@ pub enum AuthCommands {
         /// permission to assign to the token, one of: read, write, sign, admin
         #[structopt(short, long)]
         perm: String,
+        /// human readable label to identify the token with
+        #[structopt(short, long)]
+        label: Option<String>,
     },
     /// Get RPC API Information
     ApiInfo {
         /// permission to assign the token, one of: read, write, sign, admin
         #[structopt(short, long)]
         perm: String,
+        /// human readable label to identify the token with
+        #[structopt(short, long)]
+        label: Option<String>,
+    },
+    /// List the tokens issued by the node that have not expired yet
+    List,
+    /// Revoke a token, rejecting any further request made with it
+    Revoke {
+        /// identifier of the token to revoke, as shown by `auth list`
+        id: String,
     },
+    /// Replace the secret used to sign tokens, invalidating every issued token.
+    /// Prints a new admin token.
+    RotateSecret,
 }
 
 fn process_perms(perm: String) -> Result<Vec<String>, JsonRpcError> {
@@ -39,18 +56,26 @@ fn process_perms(perm: String) -> Result<Vec<String>, JsonRpcError> {
 impl AuthCommands {
     pub async fn run(&self, config: Config) -> anyhow::Result<()> {
         match self {
-            Self::CreateToken { perm } => {
+            Self::CreateToken { perm, label } => {
                 let perm: String = perm.parse()?;
                 let perms = process_perms(perm).map_err(handle_rpc_err)?;
                 let token_exp = config.client.token_exp;
-                let auth_params = AuthNewParams { perms, token_exp };
+                let auth_params = AuthNewParams {
+                    perms,
+                    token_exp,
+                    label: label.clone(),
+                };
                 print_rpc_res_bytes(auth_new(auth_params, &config.client.rpc_token).await)
             }
-            Self::ApiInfo { perm } => {
+            Self::ApiInfo { perm, label } => {
                 let perm: String = perm.parse()?;
                 let perms = process_perms(perm).map_err(handle_rpc_err)?;
                 let token_exp = config.client.token_exp;
-                let auth_params = AuthNewParams { perms, token_exp };
+                let auth_params = AuthNewParams {
+                    perms,
+                    token_exp,
+                    label: label.clone(),
+                };
                 let token = auth_new(auth_params, &config.client.rpc_token)
                     .await
                     .map_err(handle_rpc_err)?;
@@ -65,6 +90,51 @@ impl AuthCommands {
                 );
                 Ok(())
             }
+            Self::List => {
+                let tokens = auth_list(&config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+
+                let (title_id, title_label, title_perms, title_expires, title_revoked) =
+                    ("ID", "Label", "Permissions", "Expires", "Revoked");
+                println!(
+                    "{title_id:32}  {title_label:20}  {title_perms:24}  {title_expires:25}  {title_revoked}"
+                );
+                for token in tokens {
+                    let label = token.label.unwrap_or_default();
+                    let perms = token.perms.join(",");
+                    let expires = format_timestamp(token.expires_at);
+                    let revoked = if token.revoked { "X" } else { "" };
+                    println!(
+                        "{:32}  {label:20}  {perms:24}  {expires:25}  {revoked}",
+                        token.id
+                    );
+                }
+                Ok(())
+            }
+            Self::Revoke { id } => {
+                auth_revoke((id.clone(),), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                println!("Token {id} revoked");
+                Ok(())
+            }
+            Self::RotateSecret => {
+                let token_exp = config.client.token_exp;
+                print_rpc_res_bytes(
+                    auth_rotate_secret(
+                        AuthRotateSecretParams { token_exp },
+                        &config.client.rpc_token,
+                    )
+                    .await,
+                )
+            }
         }
     }
 }
+
+fn format_timestamp(timestamp: usize) -> String {
+    OffsetDateTime::from_unix_timestamp(timestamp as i64)
+        .map(|time| time.to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
@@ -3,9 +3,12 @@
 
 use super::*;
 use cid::Cid;
-use forest_blocks::TipsetKeys;
+use forest_blocks::{tipset_keys_json::TipsetKeysJson, TipsetKeys};
+use forest_chain::headchange_json::HeadChangeJson;
 use forest_json::cid::CidJson;
 use forest_rpc_client::chain_ops::*;
+use forest_rpc_client::state_ops::state_search_msg;
+use fvm_shared::clock::ChainEpoch;
 use std::str::FromStr;
 use structopt::StructOpt;
 
@@ -36,6 +39,9 @@ pub enum ChainCommands {
         /// Input a valid CID
         #[structopt(short)]
         cid: String,
+        /// Also print the receipt of the message, looked up in the message index
+        #[structopt(long)]
+        receipt: bool,
     },
 
     /// Reads and prints out IPLD nodes referenced by the specified CID from chain
@@ -45,6 +51,76 @@ pub enum ChainCommands {
         #[structopt(short)]
         cid: String,
     },
+
+    /// Re-executes the tipsets of the canonical chain within the given epoch range, replacing
+    /// their cached and persisted states. Useful to repair a corrupted tipset state index.
+    /// At most 2880 epochs can be recomputed at once
+    RecomputeState {
+        /// First epoch to recompute
+        #[structopt(long)]
+        from: ChainEpoch,
+        /// Last epoch to recompute
+        #[structopt(long)]
+        to: ChainEpoch,
+    },
+
+    /// Indexes the messages of the canonical chain within the given epoch range, so that they
+    /// can be looked up without walking back through tipsets
+    BackfillMsgIndex {
+        /// First epoch to index
+        #[structopt(long)]
+        from: ChainEpoch,
+        /// Last epoch to index
+        #[structopt(long)]
+        to: ChainEpoch,
+    },
+
+    /// Prints the messages executed in the parent tipset of the block specified by the given CID
+    ParentMessages {
+        /// Input a valid CID
+        #[structopt(short)]
+        cid: String,
+    },
+
+    /// Prints the receipts of the messages executed in the parent tipset of the block specified
+    /// by the given CID
+    ParentReceipts {
+        /// Input a valid CID
+        #[structopt(short)]
+        cid: String,
+    },
+
+    /// Prints the weight of the tipset given its keys
+    TipsetWeight { cids: Vec<String> },
+
+    /// Prints the tipsets to revert and to apply to go from one tipset to another
+    Path {
+        /// Keys of the tipset to start from
+        #[structopt(long, required = true)]
+        from: Vec<String>,
+        /// Keys of the tipset to go to
+        #[structopt(long, required = true)]
+        to: Vec<String>,
+    },
+
+    /// Prints the number of unique blocks and their total size in the DAG referenced by the
+    /// specified CID, excluding the blocks also referenced by the base CID if given
+    StatObj {
+        /// Input a valid CID
+        #[structopt(short)]
+        cid: String,
+        /// CID of a DAG whose blocks are excluded
+        #[structopt(long)]
+        base: Option<String>,
+    },
+}
+
+fn parse_tipset_keys(cids: &[String]) -> anyhow::Result<TipsetKeysJson> {
+    let cids = cids
+        .iter()
+        .map(|s| Cid::from_str(s))
+        .collect::<Result<_, _>>()?;
+    Ok(TipsetKeysJson(TipsetKeys::new(cids)))
 }
 
 impl ChainCommands {
@@ -61,8 +137,6 @@ impl ChainCommands {
             }
             Self::Head => print_rpc_res_cids(chain_head(&config.client.rpc_token).await),
             Self::TipsetHash { cids } => {
-                use forest_blocks::tipset_keys_json::TipsetKeysJson;
-
                 let tipset_keys = TipsetKeys::new(
                     cids.iter()
                         .map(|s| Cid::from_str(s).expect("invalid cid"))
@@ -80,16 +154,80 @@ impl ChainCommands {
                 let result = chain_validate_tipset_checkpoints((), &config.client.rpc_token).await;
                 print_rpc_res(result)
             }
-            Self::Message { cid } => {
+            Self::Message { cid, receipt } => {
                 let cid: Cid = cid.parse()?;
                 print_rpc_res_pretty(
                     chain_get_message((CidJson(cid),), &config.client.rpc_token).await,
-                )
+                )?;
+                if *receipt {
+                    match state_search_msg((CidJson(cid),), &config.client.rpc_token)
+                        .await
+                        .map_err(handle_rpc_err)?
+                    {
+                        Some(lookup) => {
+                            println!("{}", serde_json::to_string_pretty(&lookup)?)
+                        }
+                        None => println!("No receipt, the message wasn't executed on chain"),
+                    }
+                }
+                Ok(())
             }
             Self::ReadObj { cid } => {
                 let cid: Cid = cid.parse()?;
                 print_rpc_res(chain_read_obj((CidJson(cid),), &config.client.rpc_token).await)
             }
+            Self::RecomputeState { from, to } => print_rpc_res(
+                chain_recompute_state((*from, *to), &config.client.rpc_token)
+                    .await
+                    .map(|count| format!("Recomputed {count} tipset states")),
+            ),
+            Self::BackfillMsgIndex { from, to } => print_rpc_res(
+                chain_backfill_message_index((*from, *to), &config.client.rpc_token)
+                    .await
+                    .map(|count| format!("Indexed messages of {count} tipsets")),
+            ),
+            Self::ParentMessages { cid } => {
+                let cid: Cid = cid.parse()?;
+                print_rpc_res_pretty(
+                    chain_get_parent_messages((CidJson(cid),), &config.client.rpc_token).await,
+                )
+            }
+            Self::ParentReceipts { cid } => {
+                let cid: Cid = cid.parse()?;
+                print_rpc_res_pretty(
+                    chain_get_parent_receipts((CidJson(cid),), &config.client.rpc_token).await,
+                )
+            }
+            Self::TipsetWeight { cids } => print_rpc_res(
+                chain_tipset_weight((parse_tipset_keys(cids)?,), &config.client.rpc_token).await,
+            ),
+            Self::Path { from, to } => {
+                let path = chain_get_path(
+                    (parse_tipset_keys(from)?, parse_tipset_keys(to)?),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                for change in path {
+                    let (kind, ts) = match change {
+                        HeadChangeJson::Current(ts) => ("current", ts),
+                        HeadChangeJson::Apply(ts) => ("apply", ts),
+                        HeadChangeJson::Revert(ts) => ("revert", ts),
+                    };
+                    let cids: Vec<_> = ts.0.cids().iter().map(Cid::to_string).collect();
+                    println!("{kind} {} {}", ts.0.epoch(), cids.join(","));
+                }
+                Ok(())
+            }
+            Self::StatObj { cid, base } => {
+                let cid: Cid = cid.parse()?;
+                let base = base.as_deref().map(Cid::from_str).transpose()?.map(CidJson);
+                print_rpc_res(
+                    chain_stat_obj((CidJson(cid), base), &config.client.rpc_token)
+                        .await
+                        .map(|stat| format!("Links: {}\nSize: {}", stat.links, stat.size)),
+                )
+            }
         }
     }
 }
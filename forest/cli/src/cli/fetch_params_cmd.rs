@@ -1,10 +1,12 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::path::PathBuf;
+
 use crate::cli::Config;
 use structopt::StructOpt;
 
-use forest_paramfetch::{get_params_default, SectorSizeOpt};
+use forest_paramfetch::{get_params, load_manifest, verify_params, ParamSource, SectorSizeOpt};
 use fvm_shared::sector::SectorSize;
 
 use super::cli_error_and_die;
@@ -21,6 +23,17 @@ pub struct FetchCommands {
     /// Size in bytes
     #[structopt(required_ifs(&[("all", "false"), ("keys", "false")]))]
     params_size: Option<String>,
+    /// Only check the parameter files already downloaded against the digests of the manifest
+    #[structopt(long)]
+    verify_only: bool,
+    /// Gateway URL, `file://` URL or local directory to fetch the parameters from (otherwise
+    /// `IPFS_GATEWAY` or the Filecoin proofs gateway is used)
+    #[structopt(long)]
+    source: Option<String>,
+    /// Parameter manifest extending the built-in one (otherwise
+    /// `FOREST_PROOFS_PARAMETER_MANIFEST` is used if set)
+    #[structopt(long)]
+    manifest: Option<PathBuf>,
 }
 
 impl FetchCommands {
@@ -39,7 +52,33 @@ impl FetchCommands {
             );
         };
 
-        get_params_default(&config.client.data_dir, sizes).await
+        let params = load_manifest(self.manifest.as_deref())?;
+
+        if self.verify_only {
+            let results = verify_params(&config.client.data_dir, params, sizes).await;
+            let mut failed = 0;
+            for (name, result) in results {
+                match result {
+                    Ok(()) => println!("{name}: ok"),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        failed += 1;
+                        println!("{name}: missing");
+                    }
+                    Err(e) => {
+                        failed += 1;
+                        println!("{name}: {e}");
+                    }
+                }
+            }
+            anyhow::ensure!(failed == 0, "{failed} parameter files failed verification");
+            return Ok(());
+        }
+
+        let source = match &self.source {
+            Some(source) => ParamSource::parse(source),
+            None => ParamSource::from_env(),
+        };
+        get_params(&config.client.data_dir, params, sizes, source).await
     }
 }
 
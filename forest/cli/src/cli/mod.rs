@@ -12,6 +12,7 @@ mod config_cmd;
 mod db_cmd;
 mod fetch_params_cmd;
 mod mpool_cmd;
+mod msig_cmd;
 mod net_cmd;
 mod send_cmd;
 mod snapshot_cmd;
@@ -24,12 +25,13 @@ pub(super) use self::chain_cmd::ChainCommands;
 pub(super) use self::db_cmd::DBCommands;
 pub(super) use self::fetch_params_cmd::FetchCommands;
 pub(super) use self::mpool_cmd::MpoolCommands;
+pub(super) use self::msig_cmd::MsigCommands;
 pub(super) use self::net_cmd::NetCommands;
 pub(super) use self::send_cmd::SendCommand;
 pub(super) use self::snapshot_cmd::SnapshotCommands;
 pub(super) use self::state_cmd::StateCommands;
 pub(super) use self::sync_cmd::SyncCommands;
-pub(super) use self::wallet_cmd::WalletCommands;
+pub(super) use self::wallet_cmd::WalletCommand;
 pub(crate) use forest_cli_shared::cli::{Config, FOREST_VERSION_STRING};
 
 use crate::cli::config_cmd::ConfigCommands;
@@ -75,7 +77,10 @@ pub enum Subcommand {
     Net(NetCommands),
 
     /// Manage wallet
-    Wallet(WalletCommands),
+    Wallet(WalletCommand),
+
+    /// Interact with multisig wallets
+    Msig(MsigCommands),
 
     /// Inspect or interact with the chain synchronizer
     Sync(SyncCommands),
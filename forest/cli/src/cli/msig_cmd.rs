@@ -0,0 +1,261 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::{handle_rpc_err, Config};
+use anyhow::Context;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use forest_blocks::tipset_keys_json::TipsetKeysJson;
+use forest_json::address::json::AddressJson;
+use forest_rpc_client::{
+    chain_get_genesis, chain_head, msig_approve, msig_cancel, msig_create, msig_get_pending,
+    msig_get_vested, msig_propose, wallet_default_address,
+};
+use forest_shim::econ::{TokenAmount, FIL};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::MethodNum;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum MsigCommands {
+    /// Create a new multisig wallet
+    Create {
+        /// Addresses of the signers
+        #[structopt(required = true)]
+        signers: Vec<Address>,
+        /// Number of approvals required to execute a transaction (defaults to all signers)
+        #[structopt(long)]
+        required: Option<u64>,
+        /// Number of epochs over which the initial balance vests
+        #[structopt(long, default_value = "0")]
+        duration: ChainEpoch,
+        /// Initial balance, e.g. `1.5 FIL` or `200 nanoFIL`. Amounts without a denomination are
+        /// in attoFIL
+        #[structopt(long, default_value = "0")]
+        value: FIL,
+        /// The account to send the creation message from (otherwise the default one will be used)
+        #[structopt(long)]
+        from: Option<Address>,
+    },
+    /// Propose a transaction to a multisig wallet
+    Propose {
+        /// The multisig wallet
+        multisig: Address,
+        /// The recipient of the transaction
+        destination: Address,
+        /// Token amount, e.g. `1.5 FIL` or `200 nanoFIL`. Amounts without a denomination are in
+        /// attoFIL
+        value: FIL,
+        /// Method number to invoke on the recipient
+        #[structopt(long, default_value = "0")]
+        method: MethodNum,
+        /// Hex encoded parameters of the method
+        #[structopt(long)]
+        params: Option<String>,
+        /// The signer proposing the transaction (otherwise the default one will be used)
+        #[structopt(long)]
+        from: Option<Address>,
+    },
+    /// Approve a pending transaction of a multisig wallet
+    Approve {
+        /// The multisig wallet
+        multisig: Address,
+        /// ID of the pending transaction
+        txn_id: i64,
+        /// The signer approving the transaction (otherwise the default one will be used)
+        #[structopt(long)]
+        from: Option<Address>,
+    },
+    /// Cancel a pending transaction of a multisig wallet
+    Cancel {
+        /// The multisig wallet
+        multisig: Address,
+        /// ID of the pending transaction
+        txn_id: i64,
+        /// The proposer of the transaction (otherwise the default one will be used)
+        #[structopt(long)]
+        from: Option<Address>,
+    },
+    /// Print the pending transactions of a multisig wallet, and the funds vested so far
+    Inspect {
+        /// The multisig wallet
+        multisig: Address,
+        /// Print amounts rounded in the most readable FIL denomination
+        #[structopt(long)]
+        human: bool,
+    },
+}
+
+impl MsigCommands {
+    pub async fn run(&self, config: Config) -> anyhow::Result<()> {
+        match self {
+            Self::Create {
+                signers,
+                required,
+                duration,
+                value,
+                from,
+            } => {
+                let from = resolve_from(from, &config).await?;
+                let required = required.unwrap_or(signers.len() as u64);
+                let signers = signers.iter().copied().map(AddressJson).collect();
+
+                let cid = msig_create(
+                    (
+                        required,
+                        signers,
+                        *duration,
+                        value.0.atto().to_string(),
+                        AddressJson(from),
+                        "0".to_owned(),
+                    ),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                println!("{}", cid.0);
+                Ok(())
+            }
+            Self::Propose {
+                multisig,
+                destination,
+                value,
+                method,
+                params,
+                from,
+            } => {
+                let from = resolve_from(from, &config).await?;
+                let params = match params {
+                    Some(params) => BASE64_STANDARD
+                        .encode(hex::decode(params).context("Params have to be a hex string")?),
+                    None => String::new(),
+                };
+
+                let cid = msig_propose(
+                    (
+                        AddressJson(*multisig),
+                        AddressJson(*destination),
+                        value.0.atto().to_string(),
+                        AddressJson(from),
+                        *method,
+                        params,
+                    ),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                println!("{}", cid.0);
+                Ok(())
+            }
+            Self::Approve {
+                multisig,
+                txn_id,
+                from,
+            } => {
+                let from = resolve_from(from, &config).await?;
+                let cid = msig_approve(
+                    (AddressJson(*multisig), *txn_id, AddressJson(from)),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                println!("{}", cid.0);
+                Ok(())
+            }
+            Self::Cancel {
+                multisig,
+                txn_id,
+                from,
+            } => {
+                let from = resolve_from(from, &config).await?;
+                let cid = msig_cancel(
+                    (AddressJson(*multisig), *txn_id, AddressJson(from)),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                println!("{}", cid.0);
+                Ok(())
+            }
+            Self::Inspect { multisig, human } => {
+                let display = |amount: FIL| {
+                    if *human {
+                        amount.format_human()
+                    } else {
+                        amount.to_string()
+                    }
+                };
+                let head = chain_head(&config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?
+                    .0;
+                let genesis = chain_get_genesis(&config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?
+                    .context("Genesis tipset not found")?
+                    .0;
+
+                let vested = msig_get_vested(
+                    (
+                        AddressJson(*multisig),
+                        TipsetKeysJson(genesis.key().clone()),
+                        TipsetKeysJson(head.key().clone()),
+                    ),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                let vested = FIL(TokenAmount::from_atto(BigInt::from_str(&vested)?));
+                println!("Vested: {}", display(vested));
+
+                let pending = msig_get_pending(
+                    (AddressJson(*multisig), TipsetKeysJson(head.key().clone())),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+
+                println!("Pending transactions: {}", pending.len());
+                if !pending.is_empty() {
+                    println!(
+                        "{:4} {:41} {:24} {:6} {:12} Approved",
+                        "ID", "To", "Value", "Method", "Params"
+                    );
+                }
+                for txn in pending {
+                    let value = display(FIL::from(txn.value));
+                    let params = hex::encode(BASE64_STANDARD.decode(&txn.params)?);
+                    let approved = txn
+                        .approved
+                        .iter()
+                        .map(|addr| addr.0.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    println!(
+                        "{:4} {:41} {value:24} {:6} {:12} {approved}",
+                        txn.id,
+                        txn.to.0.to_string(),
+                        txn.method,
+                        if params.is_empty() { "-" } else { &params },
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns the given address, or the default wallet address of the node.
+async fn resolve_from(from: &Option<Address>, config: &Config) -> anyhow::Result<Address> {
+    match from {
+        Some(from) => Ok(*from),
+        None => Ok(Address::from_str(
+            &wallet_default_address(&config.client.rpc_token)
+                .await
+                .map_err(handle_rpc_err)?,
+        )?),
+    }
+}
@@ -25,6 +25,8 @@ pub enum NetCommands {
         /// Peer ID to disconnect from
         id: String,
     },
+    /// Prints the `bitswap` activity and the ledgers of the connected peers
+    BitswapStats,
 }
 
 impl NetCommands {
@@ -110,6 +112,40 @@ impl NetCommands {
                 println!("disconnect {id}: success");
                 Ok(())
             }
+            Self::BitswapStats => {
+                use human_repr::HumanCount;
+
+                let stats = net_bitswap_stat((), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                println!("Serving: {}", stats.serving);
+                println!("Blocks received: {}", stats.blocks_received);
+                println!("Data received: {}", stats.data_received.human_count_bytes());
+                println!("Duplicate blocks received: {}", stats.dup_blks_received);
+                println!(
+                    "Duplicate data received: {}",
+                    stats.dup_data_received.human_count_bytes()
+                );
+                println!("Blocks sent: {}", stats.blocks_sent);
+                println!("Data sent: {}", stats.data_sent.human_count_bytes());
+                println!("Queued wants: {}", stats.queued_wants);
+                println!("Wantlist: {} blocks", stats.wantlist.len());
+                println!("Peers: {}", stats.peers.len());
+
+                let mut ledgers = stats.ledgers;
+                ledgers.sort_by(|a, b| b.value.total_cmp(&a.value));
+                for ledger in ledgers {
+                    println!(
+                        "{}: debt ratio {:.2}, sent {}, received {}, exchanged {}",
+                        ledger.peer,
+                        ledger.value,
+                        ledger.sent.human_count_bytes(),
+                        ledger.recv.human_count_bytes(),
+                        ledger.exchanged
+                    );
+                }
+                Ok(())
+            }
         }
     }
 }
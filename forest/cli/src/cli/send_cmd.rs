@@ -1,33 +1,74 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use anyhow::Context;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use cid::Cid;
+use forest_blocks::{tipset_keys_json::TipsetKeysJson, TipsetKeys};
+use forest_ipld::json::IpldJson;
+use forest_json::actor_state::json::ActorStateJson;
+use forest_json::address::json::AddressJson;
+use forest_json::cid::CidJson;
 use forest_json::message::json::MessageJson;
-use forest_rpc_client::{mpool_push_message, wallet_default_address};
-use fvm_shared::{
-    address::Address, bigint::BigInt, econ::TokenAmount, message::Message, METHOD_SEND,
+use forest_json::signed_message::json::SignedMessageJson;
+use forest_message::SignedMessage;
+use forest_rpc_client::{
+    gas_estimate_message_gas, mpool_push, mpool_push_message, state_call, state_encode_params,
+    state_get_actor, state_wait_msg, wallet_default_address, wallet_sign,
 };
+use forest_shim::econ::{Unit, FIL};
+use fvm_ipld_encoding::{Cbor, RawBytes};
+use fvm_shared::{address::Address, econ::TokenAmount, message::Message, MethodNum};
 use std::str::FromStr;
 use structopt::StructOpt;
 
 use super::{handle_rpc_err, Config};
 
+/// Number of epochs a message has to be on chain for `--wait` to return.
+const WAIT_CONFIDENCE: i64 = 5;
+
 #[derive(Debug, StructOpt)]
 pub struct SendCommand {
     /// optionally specify the account to send funds from (otherwise the default one will be used)
     #[structopt(long)]
     from: Option<Address>,
     target_address: Address,
-    /// token amount in attoFIL
-    amount: BigInt,
-    /// specify gas fee cap to use in attoFIL
+    /// token amount, e.g. `1.5 FIL` or `200 nanoFIL`. Amounts without a denomination are in attoFIL
+    amount: FIL,
+    /// specify gas fee cap to use, e.g. `100 nanoFIL`. Amounts without a denomination are in attoFIL
     #[structopt(long)]
-    gas_feecap: Option<BigInt>,
-    /// specify gas limit in attoFIL
+    gas_feecap: Option<FIL>,
+    /// specify gas limit
     #[structopt(long)]
     gas_limit: Option<i64>,
-    /// specify gas price to use in attoFIL
+    /// specify gas premium to use, e.g. `100 nanoFIL`. Amounts without a denomination are in attoFIL
+    #[structopt(long)]
+    gas_premium: Option<FIL>,
+    /// method number to invoke on the target actor
+    #[structopt(long, default_value = "0")]
+    method: MethodNum,
+    /// hex encoded CBOR parameters of the method
+    #[structopt(long, conflicts_with = "params_json")]
+    params_hex: Option<String>,
+    /// parameters of the method as JSON, encoded to CBOR before sending. The parameters of
+    /// built-in actor methods use the Lotus JSON layout, others are read as DAG-JSON
+    #[structopt(long)]
+    params_json: Option<String>,
+    /// specify the nonce to use, instead of the next nonce of the sender
+    #[structopt(long)]
+    nonce: Option<u64>,
+    /// execute the message against the current head without sending it, and print the
+    /// estimated gas and exit code
     #[structopt(long)]
-    gas_premium: Option<BigInt>,
+    dry_run: bool,
+    /// wait for the message to be executed on chain and print its receipt
+    #[structopt(long)]
+    wait: bool,
+    /// print the estimated gas amounts of `--dry-run` rounded in the most readable FIL
+    /// denomination
+    #[structopt(long)]
+    human: bool,
 }
 
 impl SendCommand {
@@ -45,18 +86,163 @@ impl SendCommand {
         let message = Message {
             from,
             to: self.target_address,
-            value: TokenAmount::from_atto(self.amount.clone()),
-            method_num: METHOD_SEND,
+            value: self.amount.clone().into(),
+            method_num: self.method,
+            params: self.params(&config).await?,
             gas_limit: self.gas_limit.unwrap_or_default(),
-            gas_fee_cap: TokenAmount::from_atto(self.gas_feecap.clone().unwrap_or_default()),
-            gas_premium: TokenAmount::from_atto(self.gas_premium.clone().unwrap_or_default()),
+            gas_fee_cap: self.gas_feecap.clone().unwrap_or_default().into(),
+            gas_premium: self.gas_premium.clone().unwrap_or_default().into(),
             ..Default::default()
         };
 
-        mpool_push_message((MessageJson(message), None), &config.client.rpc_token)
+        if self.dry_run {
+            return dry_run(message, self.human, &config).await;
+        }
+
+        let cid = match self.nonce {
+            Some(nonce) => push_with_nonce(message, nonce, &config).await?,
+            None => mpool_push_message((MessageJson(message), None), &config.client.rpc_token)
+                .await
+                .map_err(handle_rpc_err)?
+                .0
+                .cid()?,
+        };
+        println!("{cid}");
+
+        if self.wait {
+            let lookup = state_wait_msg((CidJson(cid), WAIT_CONFIDENCE), &config.client.rpc_token)
+                .await
+                .map_err(handle_rpc_err)?;
+            let receipt = lookup.receipt.0;
+            println!(
+                "Executed at epoch {}: exit code {}, gas used {}",
+                lookup.height,
+                receipt.exit_code.value(),
+                receipt.gas_used
+            );
+            if !receipt.return_data.bytes().is_empty() {
+                println!("Return: {}", hex::encode(receipt.return_data.bytes()));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn params(&self, config: &Config) -> anyhow::Result<RawBytes> {
+        if let Some(params) = &self.params_hex {
+            return Ok(RawBytes::new(
+                hex::decode(params).context("Params have to be a hex string")?,
+            ));
+        }
+        if let Some(params) = &self.params_json {
+            let params: serde_json::Value =
+                serde_json::from_str(params).context("Params have to be valid JSON")?;
+            let actor = state_get_actor(
+                (
+                    AddressJson(self.target_address),
+                    TipsetKeysJson(TipsetKeys::default()),
+                ),
+                &config.client.rpc_token,
+            )
             .await
             .map_err(handle_rpc_err)?;
+            // The node knows the parameters of the methods of built-in actors, the parameters
+            // sent to actors that don't exist yet can only be DAG-JSON
+            return match actor {
+                Some(ActorStateJson(actor)) => {
+                    let encoded = state_encode_params(
+                        (CidJson(actor.code), self.method, params),
+                        &config.client.rpc_token,
+                    )
+                    .await
+                    .map_err(handle_rpc_err)?;
+                    Ok(RawBytes::new(BASE64_STANDARD.decode(encoded)?))
+                }
+                None => {
+                    let IpldJson(ipld) = serde_json::from_value(params)
+                        .context("Params have to be valid DAG-JSON")?;
+                    Ok(RawBytes::serialize(ipld)?)
+                }
+            };
+        }
+        Ok(RawBytes::default())
+    }
+}
 
-        Ok(())
+/// Executes the message against the current head and prints the outcome along with the
+/// estimated gas parameters.
+async fn dry_run(message: Message, human: bool, config: &Config) -> anyhow::Result<()> {
+    let head = TipsetKeysJson(TipsetKeys::default());
+    let res = state_call(
+        (MessageJson(message.clone()), head.clone()),
+        &config.client.rpc_token,
+    )
+    .await
+    .map_err(handle_rpc_err)?;
+
+    match res.msg_rct {
+        Some(receipt) => {
+            println!("Exit code: {}", receipt.exit_code.value());
+            println!("Gas used: {}", receipt.gas_used);
+            if !receipt.return_data.bytes().is_empty() {
+                println!("Return: {}", hex::encode(receipt.return_data.bytes()));
+            }
+        }
+        None => println!("No receipt"),
+    }
+    if let Some(error) = res.error {
+        println!("Error: {error}");
     }
+
+    let estimated =
+        gas_estimate_message_gas((MessageJson(message), None, head), &config.client.rpc_token)
+            .await
+            .map_err(handle_rpc_err)?
+            .0;
+    let display = |amount: TokenAmount| {
+        let amount = FIL::from(amount);
+        if human {
+            amount.format_human()
+        } else {
+            amount.format_in(Unit::Atto, None)
+        }
+    };
+    println!("Estimated gas limit: {}", estimated.gas_limit);
+    println!("Estimated gas fee cap: {}", display(estimated.gas_fee_cap));
+    println!("Estimated gas premium: {}", display(estimated.gas_premium));
+    Ok(())
+}
+
+/// Estimates gas, signs and pushes a message with the given nonce, returning its CID.
+async fn push_with_nonce(mut message: Message, nonce: u64, config: &Config) -> anyhow::Result<Cid> {
+    message.sequence = nonce;
+    let message = gas_estimate_message_gas(
+        (
+            MessageJson(message),
+            None,
+            TipsetKeysJson(TipsetKeys::default()),
+        ),
+        &config.client.rpc_token,
+    )
+    .await
+    .map_err(handle_rpc_err)?
+    .0;
+
+    let to_sign = BASE64_STANDARD.encode(message.cid()?.to_bytes());
+    let signature = wallet_sign(
+        (AddressJson(message.from), to_sign.into_bytes()),
+        &config.client.rpc_token,
+    )
+    .await
+    .map_err(handle_rpc_err)?
+    .0;
+
+    let signed_message = SignedMessage::new_from_parts(message, signature)?;
+    Ok(mpool_push(
+        (SignedMessageJson(signed_message),),
+        &config.client.rpc_token,
+    )
+    .await
+    .map_err(handle_rpc_err)?
+    .0)
 }
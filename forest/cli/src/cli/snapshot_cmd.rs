@@ -5,6 +5,7 @@ use super::*;
 use crate::cli::{cli_error_and_die, handle_rpc_err};
 use ahash::{HashSet, HashSetExt};
 use anyhow::bail;
+use async_compression::tokio::bufread::ZstdDecoder;
 use dialoguer::{theme::ColorfulTheme, Confirm};
 use forest_blocks::{tipset_keys_json::TipsetKeysJson, Tipset, TipsetKeys};
 use forest_chain::ChainStore;
@@ -13,8 +14,10 @@ use forest_cli_shared::cli::{
 };
 use forest_db::{db_engine::open_db, Store};
 use forest_genesis::read_genesis_header;
-use forest_ipld::recurse_links;
+use forest_ipld::{recurse_links, verify_dag, DagVerification};
+use forest_paramfetch::{get_params_default, set_proofs_parameter_cache_dir_env, SectorSizeOpt};
 use forest_rpc_client::chain_ops::*;
+use forest_state_manager::StateManager;
 use forest_utils::net::FetchProgress;
 use fvm_ipld_car::load_car;
 use fvm_shared::clock::ChainEpoch;
@@ -51,6 +54,10 @@ pub enum SnapshotCommands {
         /// Skip creating the checksum file.
         #[structopt(long)]
         skip_checksum: bool,
+        /// Compress the snapshot, adding a `.zst` extension to the output path. The checksum is
+        /// computed over the compressed file.
+        #[structopt(long, possible_values = &["zstd"])]
+        compress: Option<String>,
     },
 
     /// Fetches the most recent snapshot from a trusted, pre-defined location.
@@ -59,12 +66,9 @@ pub enum SnapshotCommands {
         /// in default Forest data location.
         #[structopt(short, long)]
         snapshot_dir: Option<PathBuf>,
-        /// Snapshot trusted source
-        #[structopt(
-            short,
-            long,
-            possible_values = &["forest", "filecoin"],
-        )]
+        /// Snapshot trusted source: `forest`, `filecoin`, the name of a provider of the
+        /// `snapshot_fetch.providers` configuration, or the HTTP URL of a snapshot
+        #[structopt(short, long)]
         provider: Option<SnapshotServer>,
         /// Use [`aria2`](https://aria2.github.io/) for downloading, default is false. Requires `aria2c` in PATH.
         #[structopt(long)]
@@ -132,6 +136,17 @@ pub enum SnapshotCommands {
         /// Force validation and answers yes to all prompts.
         #[structopt(long)]
         force: bool,
+        /// Walk the full state and message DAGs of the recent tipsets, verifying the hash of every
+        /// block and collecting all missing or corrupt blocks instead of stopping at the first one.
+        #[structopt(long)]
+        deep: bool,
+        /// Number of tipsets to re-execute from the tip, comparing the computed state roots and
+        /// receipts with the ones in the snapshot. Must be smaller than `recent-stateroots`.
+        #[structopt(long, default_value = "0")]
+        reexecute: i64,
+        /// Write a JSON report of the validation to this path.
+        #[structopt(long)]
+        report: Option<PathBuf>,
     },
 }
 
@@ -143,6 +158,7 @@ impl SnapshotCommands {
                 recent_stateroots,
                 output_path,
                 skip_checksum,
+                compress,
             } => {
                 let chain_head = match chain_head(&config.client.rpc_token).await {
                     Ok(head) => head.0,
@@ -175,13 +191,21 @@ impl SnapshotCommands {
                     output_path.clone()
                 };
 
-                let output_path = match strfmt(&output_path.display().to_string(), &vars) {
+                let output_path: PathBuf = match strfmt(&output_path.display().to_string(), &vars) {
                     Ok(path) => path.into(),
                     Err(e) => {
                         cli_error_and_die(format!("Unparsable string error: {e}"), 1);
                     }
                 };
 
+                // The node compresses snapshots written to `.zst` files
+                let output_path =
+                    if compress.is_some() && output_path.extension().unwrap_or_default() != "zst" {
+                        format!("{}.zst", output_path.display()).into()
+                    } else {
+                        output_path
+                    };
+
                 let params = (
                     epoch,
                     *recent_stateroots,
@@ -242,7 +266,21 @@ impl SnapshotCommands {
                 recent_stateroots,
                 snapshot,
                 force,
-            } => validate(&config, recent_stateroots, snapshot, *force).await,
+                deep,
+                reexecute,
+                report,
+            } => {
+                validate(
+                    &config,
+                    recent_stateroots,
+                    snapshot,
+                    *force,
+                    *deep,
+                    *reexecute,
+                    report,
+                )
+                .await
+            }
         }
     }
 }
@@ -351,12 +389,51 @@ fn clean(config: &Config, snapshot_dir: &Option<PathBuf>, force: bool) -> anyhow
     Ok(())
 }
 
+/// Machine-readable outcome of `forest-cli snapshot validate`.
+#[derive(Debug, Default, Serialize)]
+struct ValidationReport {
+    snapshot: PathBuf,
+    head_epoch: ChainEpoch,
+    valid: bool,
+    verified_blocks: u64,
+    missing: Vec<BrokenBlock>,
+    corrupt: Vec<BrokenBlock>,
+    reexecuted_tipsets: i64,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BrokenBlock {
+    cid: String,
+    /// Epoch of the tipset referencing the block
+    epoch: ChainEpoch,
+}
+
+impl ValidationReport {
+    fn add(&mut self, dag: DagVerification, epoch: ChainEpoch) {
+        self.verified_blocks += dag.verified;
+        let broken = |cid: Cid| BrokenBlock {
+            cid: cid.to_string(),
+            epoch,
+        };
+        self.missing.extend(dag.missing.into_iter().map(broken));
+        self.corrupt.extend(dag.corrupt.into_iter().map(broken));
+    }
+}
+
 async fn validate(
     config: &Config,
     recent_stateroots: &i64,
     snapshot: &PathBuf,
     force: bool,
+    deep: bool,
+    reexecute: i64,
+    report_path: &Option<PathBuf>,
 ) -> anyhow::Result<()> {
+    if reexecute < 0 || reexecute >= *recent_stateroots {
+        bail!("reexecute must be between 0 and recent-stateroots ({recent_stateroots}), exclusive of the latter");
+    }
+
     let confirm = force
         || atty::is(atty::Stream::Stdin)
             && Confirm::with_theme(&ColorfulTheme::default())
@@ -385,25 +462,86 @@ async fn validate(
         let cids = {
             let file = tokio::fs::File::open(&snapshot).await?;
             let reader = FetchProgress::fetch_from_file(file).await?;
-            load_car(chain_store.blockstore(), reader.compat()).await?
+            if snapshot.extension().unwrap_or_default() == "zst" {
+                let reader = ZstdDecoder::new(tokio::io::BufReader::new(reader));
+                load_car(chain_store.blockstore(), reader.compat()).await?
+            } else {
+                load_car(chain_store.blockstore(), reader.compat()).await?
+            }
         };
 
         let ts = chain_store.tipset_from_keys(&TipsetKeys::new(cids))?;
 
-        validate_links_and_genesis_traversal(
+        let mut report = ValidationReport {
+            snapshot: snapshot.clone(),
+            head_epoch: ts.epoch(),
+            ..Default::default()
+        };
+
+        let mut result = validate_links_and_genesis_traversal(
             &chain_store,
-            ts,
+            ts.clone(),
             chain_store.blockstore(),
             *recent_stateroots,
             &Tipset::from(genesis),
             &config.chain.name,
+            deep.then_some(&mut report),
         )
-        .await?;
+        .await;
+
+        let broken = report.missing.len() + report.corrupt.len();
+        if result.is_ok() && broken > 0 {
+            result = Err(anyhow::anyhow!(
+                "{} missing and {} corrupt blocks",
+                report.missing.len(),
+                report.corrupt.len()
+            ));
+        }
+
+        if result.is_ok() && reexecute > 0 {
+            result = reexecute_tipsets(config, chain_store, ts, reexecute).await;
+            if result.is_ok() {
+                report.reexecuted_tipsets = reexecute;
+            }
+        }
+
+        report.valid = result.is_ok();
+        report.error = result.as_ref().err().map(|e| e.to_string());
+        if let Some(report_path) = report_path {
+            fs::write(report_path, serde_json::to_vec_pretty(&report)?)?;
+            println!("Validation report saved to {}", report_path.display());
+        }
+
+        result?;
+        println!("Snapshot is valid");
     }
 
     Ok(())
 }
 
+/// Re-executes the `count` tipsets below `ts` and checks that the resulting state roots and
+/// receipts match the ones in the snapshot.
+async fn reexecute_tipsets<DB>(
+    config: &Config,
+    chain_store: Arc<ChainStore<DB>>,
+    ts: Arc<Tipset>,
+    count: i64,
+) -> anyhow::Result<()>
+where
+    DB: fvm_ipld_blockstore::Blockstore + Store + Clone + Send + Sync + 'static,
+{
+    set_proofs_parameter_cache_dir_env(&config.client.data_dir);
+    get_params_default(&config.client.data_dir, SectorSizeOpt::Keys).await?;
+
+    let state_manager = Arc::new(StateManager::new(
+        chain_store,
+        config.chain.clone(),
+        Arc::new(forest_interpreter::RewardActorMessageCalc),
+    )?);
+    let height = ts.epoch() - count;
+    state_manager.validate_chain(ts, height).await
+}
+
 async fn validate_links_and_genesis_traversal<DB>(
     chain_store: &ChainStore<DB>,
     ts: Arc<Tipset>,
@@ -411,6 +549,7 @@ async fn validate_links_and_genesis_traversal<DB>(
     recent_stateroots: ChainEpoch,
     genesis_tipset: &Tipset,
     network: &str,
+    mut report: Option<&mut ValidationReport>,
 ) -> anyhow::Result<()>
 where
     DB: fvm_ipld_blockstore::Blockstore + Store + Send + Sync,
@@ -418,6 +557,11 @@ where
     let mut seen = HashSet::<Cid>::new();
     let upto = ts.epoch() - recent_stateroots;
 
+    // The state and messages of the head tipset are part of the export as well.
+    if let Some(report) = report.as_deref_mut() {
+        verify_tipset_dags(db, &ts, &mut seen, report)?;
+    }
+
     let mut tsk = ts.parents().clone();
 
     let total_size = ts.epoch();
@@ -450,14 +594,18 @@ where
         }
         // check for ipld links backwards till `upto`
         if height > upto {
-            let mut assert_cid_exists = |cid: Cid| async move {
-                let data = db.get(&cid)?;
-                data.ok_or_else(|| anyhow::anyhow!("Broken IPLD link at epoch: {height}"))
-            };
-
-            for h in tipset.blocks() {
-                recurse_links(&mut seen, *h.state_root(), &mut assert_cid_exists).await?;
-                recurse_links(&mut seen, *h.messages(), &mut assert_cid_exists).await?;
+            if let Some(report) = report.as_deref_mut() {
+                verify_tipset_dags(db, &tipset, &mut seen, report)?;
+            } else {
+                let mut assert_cid_exists = |cid: Cid| async move {
+                    let data = db.get(&cid)?;
+                    data.ok_or_else(|| anyhow::anyhow!("Broken IPLD link at epoch: {height}"))
+                };
+
+                for h in tipset.blocks() {
+                    recurse_links(&mut seen, *h.state_root(), &mut assert_cid_exists).await?;
+                    recurse_links(&mut seen, *h.messages(), &mut assert_cid_exists).await?;
+                }
             }
         }
 
@@ -467,11 +615,30 @@ where
     }
 
     pb.finish();
-    println!("Snapshot is valid");
 
     Ok(())
 }
 
+/// Verifies every block of the state and message DAGs referenced by the tipset headers.
+fn verify_tipset_dags<DB>(
+    db: &DB,
+    tipset: &Tipset,
+    seen: &mut HashSet<Cid>,
+    report: &mut ValidationReport,
+) -> anyhow::Result<()>
+where
+    DB: fvm_ipld_blockstore::Blockstore,
+{
+    let mut dag = DagVerification::default();
+    for h in tipset.blocks() {
+        for root in [*h.state_root(), *h.messages()] {
+            verify_dag(seen, root, &mut |cid| db.get(cid), &mut dag)?;
+        }
+    }
+    report.add(dag, tipset.epoch());
+    Ok(())
+}
+
 fn delete_snapshot(snapshot_path: &PathBuf) {
     let checksum_path = snapshot_path.with_extension("sha256sum");
     for path in [snapshot_path, &checksum_path] {
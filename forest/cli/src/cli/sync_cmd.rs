@@ -3,6 +3,7 @@
 
 use super::Config;
 use cid::Cid;
+use forest_blocks::{tipset_keys_json::TipsetKeysJson, TipsetKeys};
 use forest_chain_sync::SyncStage;
 use forest_json::cid::CidJson;
 use forest_rpc_client::*;
@@ -37,6 +38,22 @@ pub enum SyncCommands {
         #[structopt(short)]
         cid: String,
     },
+    /// Unmark a given block as bad, so that it can be synced again
+    UnmarkBad {
+        /// The block CID to unmark as a bad block
+        #[structopt(short)]
+        cid: String,
+    },
+    /// Unmark all the blocks marked as bad
+    UnmarkAllBad,
+    /// Pin the node to the chain of a tipset given its keys. Chains that don't include it are
+    /// rejected, and the checkpoint is kept across restarts
+    Checkpoint {
+        cids: Vec<String>,
+        /// Remove the checkpoint, so that the node follows the heaviest chain again
+        #[structopt(long, conflicts_with = "cids")]
+        clear: bool,
+    },
 }
 
 impl SyncCommands {
@@ -126,6 +143,9 @@ impl SyncCommands {
                 if let Some(duration) = elapsed_time {
                     println!("Elapsed time:\t{}s", duration.whole_seconds());
                 }
+                if let Some(drift) = response.clock_drift {
+                    println!("Clock drift:\t{drift}ms");
+                }
                 Ok(())
             }
             Self::CheckBad { cid } => {
@@ -149,6 +169,42 @@ impl SyncCommands {
                 println!("OK");
                 Ok(())
             }
+            Self::UnmarkBad { cid } => {
+                let cid: Cid = cid.parse()?;
+                sync_unmark_bad((CidJson(cid),), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                println!("OK");
+                Ok(())
+            }
+            Self::UnmarkAllBad => {
+                sync_unmark_all_bad(&config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                println!("OK");
+                Ok(())
+            }
+            Self::Checkpoint { clear: true, .. } => {
+                sync_clear_checkpoint(&config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                println!("OK");
+                Ok(())
+            }
+            Self::Checkpoint { cids, .. } => {
+                if cids.is_empty() {
+                    anyhow::bail!("The keys of the checkpoint tipset, or --clear, are required");
+                }
+                let cids = cids.iter().map(|s| s.parse()).collect::<Result<_, _>>()?;
+                sync_checkpoint(
+                    (TipsetKeysJson(TipsetKeys::new(cids)),),
+                    &config.client.rpc_token,
+                )
+                .await
+                .map_err(handle_rpc_err)?;
+                println!("OK");
+                Ok(())
+            }
         }
     }
 }
@@ -5,15 +5,23 @@ use super::Config;
 use anyhow::Context;
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
+use forest_cli_shared::cli::open_keystore;
 use forest_json::address::json::AddressJson;
 use forest_json::signature::json::{signature_type::SignatureTypeJson, SignatureJson};
+use forest_json::signed_message::json::SignedMessageJson;
 use forest_key_management::json::KeyInfoJson;
+use forest_key_management::{generate_mnemonic, KeyInfo, Wallet};
+use forest_message::SignedMessage;
 use forest_rpc_client::wallet_ops::*;
+use forest_shim::econ::FIL;
 use forest_utils::io::read_file_to_string;
+use fvm_ipld_encoding::{Cbor, RawBytes};
 use fvm_shared::address::{Address, Protocol};
 use fvm_shared::bigint::BigInt;
 use fvm_shared::crypto::signature::{Signature, SignatureType};
 use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::MethodNum;
 use rpassword::read_password;
 use std::{
     path::PathBuf,
@@ -23,6 +31,26 @@ use structopt::StructOpt;
 
 use super::handle_rpc_err;
 
+#[derive(Debug, StructOpt)]
+pub struct WalletCommand {
+    /// Operate directly on the keystore of the data directory instead of going through the
+    /// daemon. The daemon must not be running, as it would overwrite the changes.
+    #[structopt(long)]
+    local: bool,
+    #[structopt(subcommand)]
+    cmd: WalletCommands,
+}
+
+impl WalletCommand {
+    pub async fn run(&self, config: Config) -> anyhow::Result<()> {
+        if self.local {
+            self.cmd.run_local(&config)
+        } else {
+            self.cmd.run(config).await
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 pub enum WalletCommands {
     /// Create a new wallet
@@ -30,11 +58,28 @@ pub enum WalletCommands {
         /// The signature type to use. One of SECP256k1, or BLS
         #[structopt(default_value = "secp256k1")]
         signature_type: String,
+        /// Derive the wallet keys from a BIP39 mnemonic phrase, which is prompted for. A new
+        /// phrase is generated when none is entered. The first address of the signature type is
+        /// created. Only available with `--local`
+        #[structopt(long)]
+        from_mnemonic: bool,
+    },
+    /// Derive the address at the given index from the mnemonic phrase of the wallet. Only
+    /// available with `--local`
+    Derive {
+        /// Index of the address to derive
+        index: u32,
+        /// The signature type to use. One of SECP256k1, or BLS
+        #[structopt(long, default_value = "secp256k1")]
+        signature_type: String,
     },
     /// Get account balance
     Balance {
         /// The address of the account to check
         address: String,
+        /// Print the balance in the most readable FIL denomination instead of attoFIL
+        #[structopt(long)]
+        human: bool,
     },
     /// Get the default address of the wallet
     Default,
@@ -42,6 +87,22 @@ pub enum WalletCommands {
     Export {
         /// The address that contains the keys to export
         address: String,
+        /// Output format. `hex` is the hex encoded `KeyInfo` used by Lotus, `json` is a plain
+        /// JSON key file
+        #[structopt(long, default_value = "hex")]
+        format: KeyFormat,
+    },
+    /// Delete a key from the wallet
+    Delete {
+        /// The address of the key to delete
+        address: String,
+    },
+    /// Set the label of an address, or print it when no label is given
+    Label {
+        /// The address to label
+        address: String,
+        /// The new label, an empty label removes it
+        label: Option<String>,
     },
     /// Check if the wallet has a key
     Has {
@@ -50,11 +111,16 @@ pub enum WalletCommands {
     },
     /// Import keys from existing wallet
     Import {
-        /// The path to the private key
+        /// The path to the private key, either a hex encoded `KeyInfo` as exported by Lotus or a
+        /// JSON key file
         path: Option<String>,
     },
     /// List addresses of the wallet
-    List,
+    List {
+        /// Print the balances rounded in the most readable FIL denomination instead of attoFIL
+        #[structopt(long)]
+        human: bool,
+    },
     /// Set the default wallet address
     SetDefault {
         /// The given key to set to the default address
@@ -81,12 +147,51 @@ pub enum WalletCommands {
         #[structopt(short)]
         signature: String,
     },
+    /// Sign a message without a node and print it as JSON, ready to be pushed to the message pool
+    /// of a node. Only available with `--local`
+    SignMessage {
+        /// The address to send the message from (otherwise the default one will be used)
+        #[structopt(long)]
+        from: Option<Address>,
+        /// The recipient of the message
+        #[structopt(long)]
+        to: Address,
+        /// Token amount, e.g. `1.5 FIL` or `200 nanoFIL`. Amounts without a denomination are
+        /// in attoFIL
+        #[structopt(long, default_value = "0")]
+        value: FIL,
+        /// Nonce of the message, i.e. the current nonce of the sender
+        #[structopt(long)]
+        nonce: u64,
+        /// Gas limit of the message
+        #[structopt(long)]
+        gas_limit: i64,
+        /// Gas fee cap, e.g. `100 nanoFIL`. Amounts without a denomination are in attoFIL
+        #[structopt(long)]
+        gas_feecap: FIL,
+        /// Gas premium, e.g. `100 nanoFIL`. Amounts without a denomination are in attoFIL
+        #[structopt(long)]
+        gas_premium: FIL,
+        /// Method number to invoke
+        #[structopt(long, default_value = "0")]
+        method: MethodNum,
+        /// Hex encoded parameters of the method
+        #[structopt(long)]
+        params: Option<String>,
+    },
 }
 
 impl WalletCommands {
     pub async fn run(&self, config: Config) -> anyhow::Result<()> {
         match self {
-            Self::New { signature_type } => {
+            Self::New {
+                from_mnemonic: true,
+                ..
+            }
+            | Self::Derive { .. } => {
+                anyhow::bail!("Mnemonic wallets are only available with `--local`")
+            }
+            Self::New { signature_type, .. } => {
                 let signature_type = match signature_type.to_lowercase().as_str() {
                     "secp256k1" => SignatureType::Secp256k1,
                     _ => SignatureType::BLS,
@@ -100,11 +205,16 @@ impl WalletCommands {
                 println!("{response}");
                 Ok(())
             }
-            Self::Balance { address } => {
+            Self::Balance { address, human } => {
                 let response = wallet_balance((address.to_string(),), &config.client.rpc_token)
                     .await
                     .map_err(handle_rpc_err)?;
-                println!("{response}");
+                if *human {
+                    let balance = FIL::from(TokenAmount::from_atto(response.parse::<BigInt>()?));
+                    println!("{}", balance.format_human());
+                } else {
+                    println!("{response}");
+                }
                 Ok(())
             }
             Self::Default => {
@@ -114,13 +224,40 @@ impl WalletCommands {
                 println!("{response}");
                 Ok(())
             }
-            Self::Export { address } => {
+            Self::Export { address, format } => {
                 let response = wallet_export((address.to_string(),), &config.client.rpc_token)
                     .await
                     .map_err(handle_rpc_err)?;
 
-                let encoded_key = serde_json::to_string(&response)?;
-                println!("{}", hex::encode(encoded_key));
+                println!("{}", format.encode(response.0)?);
+                Ok(())
+            }
+            Self::Delete { address } => {
+                let address = Address::from_str(address)
+                    .with_context(|| format!("Invalid address: {address}"))?;
+                wallet_delete((AddressJson(address),), &config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+                Ok(())
+            }
+            Self::Label { address, label } => {
+                let address = Address::from_str(address)
+                    .with_context(|| format!("Invalid address: {address}"))?;
+                match label {
+                    Some(label) => wallet_set_label(
+                        (AddressJson(address), label.clone()),
+                        &config.client.rpc_token,
+                    )
+                    .await
+                    .map_err(handle_rpc_err)?,
+                    None => {
+                        let label =
+                            wallet_get_label((AddressJson(address),), &config.client.rpc_token)
+                                .await
+                                .map_err(handle_rpc_err)?;
+                        println!("{}", label.unwrap_or_default());
+                    }
+                }
                 Ok(())
             }
             Self::Has { key } => {
@@ -131,31 +268,16 @@ impl WalletCommands {
                 Ok(())
             }
             Self::Import { path } => {
-                let key = match path {
-                    Some(path) => read_file_to_string(&PathBuf::from(path))?,
-                    _ => {
-                        println!("Enter the private key: ");
-                        read_password()?
-                    }
-                };
-
-                let key = key.trim();
-
-                let decoded_key = hex::decode(key).context("Key must be hex encoded")?;
+                let key_info = read_key_info(path)?;
 
-                let key_str = str::from_utf8(&decoded_key)?;
-
-                let key: KeyInfoJson =
-                    serde_json::from_str(key_str).context("invalid key format")?;
-
-                let key = wallet_import(vec![KeyInfoJson(key.0)], &config.client.rpc_token)
+                let key = wallet_import(vec![KeyInfoJson(key_info)], &config.client.rpc_token)
                     .await
                     .map_err(handle_rpc_err)?;
 
                 println!("{key}");
                 Ok(())
             }
-            Self::List => {
+            Self::List { human } => {
                 let response = wallet_list(&config.client.rpc_token)
                     .await
                     .map_err(handle_rpc_err)?;
@@ -164,9 +286,15 @@ impl WalletCommands {
                     .await
                     .map_err(handle_rpc_err)?;
 
-                let (title_address, title_default_mark, title_balance) =
-                    ("Address", "Default", "Balance");
-                println!("{title_address:41} {title_default_mark:7} {title_balance}");
+                let labels = wallet_labels(&config.client.rpc_token)
+                    .await
+                    .map_err(handle_rpc_err)?;
+
+                let (title_address, title_default_mark, title_balance, title_label) =
+                    ("Address", "Default", "Balance", "Label");
+                println!(
+                    "{title_address:41} {title_default_mark:7} {title_balance:24} {title_label}"
+                );
 
                 for address in response {
                     let addr = address.0.to_string();
@@ -176,9 +304,17 @@ impl WalletCommands {
                         .await
                         .map_err(handle_rpc_err)?;
 
-                    let balance_int = TokenAmount::from_atto(balance_string.parse::<BigInt>()?);
+                    // In attoFIL as printed by `wallet balance`, unless `--human` is set
+                    let balance = if *human {
+                        FIL::from(TokenAmount::from_atto(balance_string.parse::<BigInt>()?))
+                            .format_human()
+                    } else {
+                        balance_string
+                    };
 
-                    println!("{addr:41}  {default_address_mark:7}  {balance_int:.6} FIL");
+                    let label = labels.get(&addr).map(String::as_str).unwrap_or_default();
+
+                    println!("{addr:41}  {default_address_mark:7}  {balance:24} {label}");
                 }
                 Ok(())
             }
@@ -234,6 +370,253 @@ impl WalletCommands {
                 println!("{response}");
                 Ok(())
             }
+            Self::SignMessage { .. } => {
+                anyhow::bail!("Signing messages is only available with `--local`")
+            }
         }
     }
+
+    pub fn run_local(&self, config: &Config) -> anyhow::Result<()> {
+        let mut wallet = Wallet::new(open_keystore(config)?);
+        match self {
+            Self::New {
+                signature_type,
+                from_mnemonic,
+            } => {
+                let signature_type = match signature_type.to_lowercase().as_str() {
+                    "secp256k1" => SignatureType::Secp256k1,
+                    _ => SignatureType::BLS,
+                };
+                if !from_mnemonic {
+                    println!("{}", wallet.generate_addr(signature_type)?);
+                    return Ok(());
+                }
+
+                if wallet.has_seed() {
+                    anyhow::bail!(
+                        "The wallet already has a mnemonic phrase, use `wallet derive` instead"
+                    );
+                }
+                println!("Enter the mnemonic phrase (leave empty to generate a new one): ");
+                let mut phrase = read_password()?;
+                if phrase.trim().is_empty() {
+                    phrase = generate_mnemonic()?;
+                    println!("Write down this phrase, it is the only way to recover the wallet:");
+                    println!("{phrase}");
+                }
+                wallet.set_seed_from_mnemonic(&phrase)?;
+                println!("{}", wallet.derive_addr(signature_type, 0)?);
+                Ok(())
+            }
+            Self::Derive {
+                index,
+                signature_type,
+            } => {
+                let signature_type = match signature_type.to_lowercase().as_str() {
+                    "secp256k1" => SignatureType::Secp256k1,
+                    _ => SignatureType::BLS,
+                };
+                println!("{}", wallet.derive_addr(signature_type, *index)?);
+                Ok(())
+            }
+            Self::Balance { .. } => {
+                anyhow::bail!(
+                    "Querying the balance requires a node, it is not available with `--local`"
+                )
+            }
+            Self::Default => {
+                println!("{}", wallet.get_default()?);
+                Ok(())
+            }
+            Self::Export { address, format } => {
+                let address = Address::from_str(address)
+                    .with_context(|| format!("Invalid address: {address}"))?;
+                let key_info = wallet.export(&address)?;
+
+                println!("{}", format.encode(key_info)?);
+                Ok(())
+            }
+            Self::Delete { address } => {
+                let address = Address::from_str(address)
+                    .with_context(|| format!("Invalid address: {address}"))?;
+                wallet.delete(&address)
+            }
+            Self::Label { address, label } => {
+                let address = Address::from_str(address)
+                    .with_context(|| format!("Invalid address: {address}"))?;
+                match label {
+                    Some(label) => wallet.set_label(&address, label)?,
+                    None => println!("{}", wallet.get_label(&address).unwrap_or_default()),
+                }
+                Ok(())
+            }
+            Self::Has { key } => {
+                let address =
+                    Address::from_str(key).with_context(|| format!("Invalid address: {key}"))?;
+                println!("{}", wallet.has_key(&address));
+                Ok(())
+            }
+            Self::Import { path } => {
+                let key_info = read_key_info(path)?;
+                println!("{}", wallet.import(key_info)?);
+                Ok(())
+            }
+            Self::List { .. } => {
+                let default = wallet.get_default().ok();
+
+                let (title_address, title_default_mark, title_label) =
+                    ("Address", "Default", "Label");
+                println!("{title_address:41} {title_default_mark:7} {title_label}");
+
+                for address in wallet.list_addrs()? {
+                    let default_address_mark = if Some(address) == default { "X" } else { "" };
+                    let label = wallet.get_label(&address).unwrap_or_default();
+                    println!(
+                        "{:41}  {default_address_mark:7}  {label}",
+                        address.to_string()
+                    );
+                }
+                Ok(())
+            }
+            Self::SetDefault { key } => {
+                let key =
+                    Address::from_str(key).with_context(|| format!("Invalid address: {key}"))?;
+                wallet.set_default(key)
+            }
+            Self::Sign { address, message } => {
+                let address = Address::from_str(address)
+                    .with_context(|| format!("Invalid address: {address}"))?;
+                let message = hex::decode(message).context("Message has to be a hex string")?;
+
+                let signature = wallet.sign(&address, &message)?;
+                println!("{}", hex::encode(signature.bytes()));
+                Ok(())
+            }
+            Self::Verify {
+                message,
+                address,
+                signature,
+            } => {
+                let sig_bytes =
+                    hex::decode(signature).context("Signature has to be a hex string")?;
+                let address = Address::from_str(address)
+                    .with_context(|| format!("Invalid address: {address}"))?;
+                let signature = match address.protocol() {
+                    Protocol::Secp256k1 => Signature::new_secp256k1(sig_bytes),
+                    Protocol::BLS => Signature::new_bls(sig_bytes),
+                    _ => anyhow::bail!("Invalid signature (must be bls or secp256k1)"),
+                };
+                let msg = hex::decode(message).context("Message has to be a hex string")?;
+
+                println!("{}", signature.verify(&msg, &address).is_ok());
+                Ok(())
+            }
+            Self::SignMessage {
+                from,
+                to,
+                value,
+                nonce,
+                gas_limit,
+                gas_feecap,
+                gas_premium,
+                method,
+                params,
+            } => {
+                let from = match from {
+                    Some(from) => *from,
+                    None => wallet.get_default()?,
+                };
+                let params = match params {
+                    Some(params) => RawBytes::new(
+                        hex::decode(params).context("Params have to be a hex string")?,
+                    ),
+                    None => RawBytes::default(),
+                };
+
+                let message = Message {
+                    version: 0,
+                    from,
+                    to: *to,
+                    sequence: *nonce,
+                    value: value.clone().into(),
+                    method_num: *method,
+                    params,
+                    gas_limit: *gas_limit,
+                    gas_fee_cap: gas_feecap.clone().into(),
+                    gas_premium: gas_premium.clone().into(),
+                };
+
+                let signature = wallet.sign(&from, &message.cid()?.to_bytes())?;
+                let signed_message = SignedMessage::new_from_parts(message, signature)?;
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&SignedMessageJson(signed_message))?
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Reads a `KeyInfo` from the given file, or from the terminal. Both the hex encoded format of
+/// Lotus and plain JSON key files are accepted.
+fn read_key_info(path: &Option<String>) -> anyhow::Result<KeyInfo> {
+    let key = match path {
+        Some(path) => read_file_to_string(&PathBuf::from(path))?,
+        _ => {
+            println!("Enter the private key: ");
+            read_password()?
+        }
+    };
+
+    let key = key.trim();
+    let format = if key.starts_with('{') {
+        KeyFormat::Json
+    } else {
+        KeyFormat::Hex
+    };
+    format.decode(key)
+}
+
+/// Encoding of exported and imported keys.
+#[derive(Debug, Clone, Copy)]
+pub enum KeyFormat {
+    /// Hex encoded JSON `KeyInfo`, as used by Lotus
+    Hex,
+    /// JSON key file, i.e. `{"Type": "bls", "PrivateKey": "<base64>"}`
+    Json,
+}
+
+impl FromStr for KeyFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hex" | "lotus" => Ok(Self::Hex),
+            "json" => Ok(Self::Json),
+            _ => anyhow::bail!("Unknown key format {s}, expected hex or json"),
+        }
+    }
+}
+
+impl KeyFormat {
+    fn encode(self, key_info: KeyInfo) -> anyhow::Result<String> {
+        let key_json = KeyInfoJson(key_info);
+        Ok(match self {
+            Self::Hex => hex::encode(serde_json::to_string(&key_json)?),
+            Self::Json => serde_json::to_string_pretty(&key_json)?,
+        })
+    }
+
+    fn decode(self, key: &str) -> anyhow::Result<KeyInfo> {
+        let key_json = match self {
+            Self::Hex => {
+                let decoded_key = hex::decode(key).context("Key must be hex encoded")?;
+                str::from_utf8(&decoded_key)?.to_owned()
+            }
+            Self::Json => key.to_owned(),
+        };
+        let key: KeyInfoJson = serde_json::from_str(&key_json).context("invalid key format")?;
+        Ok(key.0)
+    }
 }
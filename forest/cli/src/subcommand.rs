@@ -12,6 +12,7 @@ pub(super) async fn process(command: Subcommand, config: Config) -> anyhow::Resu
         Subcommand::Auth(cmd) => cmd.run(config).await,
         Subcommand::Net(cmd) => cmd.run(config).await,
         Subcommand::Wallet(cmd) => cmd.run(config).await,
+        Subcommand::Msig(cmd) => cmd.run(config).await,
         Subcommand::Sync(cmd) => cmd.run(config).await,
         Subcommand::Mpool(cmd) => cmd.run(config),
         Subcommand::State(cmd) => cmd.run(config),
@@ -4,22 +4,21 @@
 use super::cli::set_sigint_handler;
 use anyhow::Context;
 use dialoguer::{theme::ColorfulTheme, Confirm};
-use forest_auth::{create_token, generate_priv_key, ADMIN, JWT_IDENTIFIER};
+use forest_auth::{create_labeled_token, generate_priv_key, TokenRegistry, ADMIN, JWT_IDENTIFIER};
 use forest_blocks::Tipset;
 use forest_chain::ChainStore;
 use forest_chain_sync::consensus::SyncGossipSubmitter;
-use forest_chain_sync::ChainMuxer;
+use forest_chain_sync::{ChainMuxer, FetchOnMissBlockstore};
 use forest_cli_shared::chain_path;
 use forest_cli_shared::cli::{
-    default_snapshot_dir, is_aria2_installed, snapshot_fetch, Client, Config, FOREST_VERSION_STRING,
+    default_snapshot_dir, is_aria2_installed, open_keystore, snapshot_fetch, Client, Config,
+    FOREST_VERSION_STRING,
 };
 use forest_db::{
     db_engine::{db_path, open_db, Db},
     Store,
 };
 use forest_genesis::{get_network_name_from_genesis, import_chain, read_genesis_header};
-use forest_key_management::ENCRYPTED_KEYSTORE_NAME;
-use forest_key_management::{KeyStore, KeyStoreConfig};
 use forest_libp2p::{ed25519, get_keypair, Keypair, Libp2pConfig, Libp2pService};
 use forest_libp2p::{PeerId, PeerManager};
 use forest_message_pool::{MessagePool, MpoolConfig, MpoolRpcProvider};
@@ -32,13 +31,11 @@ use futures::{select, FutureExt};
 use fvm_ipld_blockstore::Blockstore;
 use log::{debug, error, info, warn};
 use raw_sync::events::{Event, EventInit, EventState};
-use rpassword::read_password;
 use std::net::TcpListener;
 use std::time::Duration;
 use tokio::sync::RwLock;
 use tokio::task::JoinSet;
 
-use std::io::prelude::*;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time;
@@ -91,56 +88,33 @@ pub(super) async fn start(config: Config, detached: bool) -> anyhow::Result<Db>
     // Useful if others want to use this node to bootstrap from.
     info!("PeerId: {}", PeerId::from(net_keypair.public()));
 
-    let mut ks = if config.client.encrypt_keystore {
-        loop {
-            print!("Enter the keystore passphrase: ");
-            std::io::stdout().flush()?;
-
-            let passphrase = read_password()?;
-
-            let data_dir = PathBuf::from(&config.client.data_dir).join(ENCRYPTED_KEYSTORE_NAME);
-            if !data_dir.exists() {
-                print!("Confirm passphrase: ");
-                std::io::stdout().flush()?;
-
-                if passphrase != read_password()? {
-                    error!("Passphrases do not match. Please retry.");
-                    continue;
-                }
-            }
-
-            let key_store_init_result = KeyStore::new(KeyStoreConfig::Encrypted(
-                PathBuf::from(&config.client.data_dir),
-                passphrase,
-            ));
-
-            match key_store_init_result {
-                Ok(ks) => break ks,
-                Err(_) => {
-                    error!("Incorrect passphrase entered. Please try again.")
-                }
-            };
-        }
-    } else {
+    if !config.client.encrypt_keystore {
         warn!("Warning: Keystore encryption disabled!");
-        KeyStore::new(KeyStoreConfig::Persistent(PathBuf::from(
-            &config.client.data_dir,
-        )))?
-    };
+    }
+    let mut ks = open_keystore(&config)?;
 
     if ks.get(JWT_IDENTIFIER).is_err() {
         ks.put(JWT_IDENTIFIER.to_owned(), generate_priv_key())?;
     }
 
+    let db = open_db(&db_path(&chain_path(&config)), config.db_config())?;
+
     // Print admin token
     let ki = ks.get(JWT_IDENTIFIER)?;
     let token_exp = config.client.token_exp;
-    let token = create_token(ADMIN.to_owned(), ki.private_key(), token_exp)?;
+    let (token, token_info) = create_labeled_token(
+        ADMIN.to_owned(),
+        ki.private_key(),
+        token_exp,
+        Some("admin (startup)".to_owned()),
+    )?;
+    let mut token_registry = TokenRegistry::load(&db)?;
+    token_registry.register(token_info);
+    token_registry.flush(&db)?;
     info!("Admin token: {}", token);
 
     let keystore = Arc::new(RwLock::new(ks));
-
-    let db = open_db(&db_path(&chain_path(&config)), config.db_config())?;
+    let token_registry = Arc::new(RwLock::new(token_registry));
 
     let mut services = JoinSet::new();
 
@@ -232,6 +206,9 @@ pub(super) async fn start(config: Config, detached: bool) -> anyhow::Result<Db>
         config
     };
 
+    // Keep the message index up to date with the head of the chain
+    services.spawn(Arc::clone(&chain_store).index_head_changes());
+
     let peer_manager = Arc::new(PeerManager::default());
     services.spawn(peer_manager.clone().peer_operation_event_loop_task());
     let genesis_cid = *genesis_header.cid();
@@ -277,7 +254,7 @@ pub(super) async fn start(config: Config, detached: bool) -> anyhow::Result<Db>
     let chain_muxer = ChainMuxer::new(
         Arc::new(consensus),
         Arc::clone(&state_manager),
-        peer_manager,
+        peer_manager.clone(),
         mpool.clone(),
         network_send.clone(),
         network_rx,
@@ -288,6 +265,7 @@ pub(super) async fn start(config: Config, detached: bool) -> anyhow::Result<Db>
     )?;
     let bad_blocks = chain_muxer.bad_blocks_cloned();
     let sync_state = chain_muxer.sync_state_cloned();
+    let clock_drift = chain_muxer.clock_drift_cloned();
     services.spawn(async { Err(anyhow::anyhow!("{}", chain_muxer.await)) });
 
     // Start services
@@ -301,6 +279,13 @@ pub(super) async fn start(config: Config, detached: bool) -> anyhow::Result<Db>
 
         let rpc_state_manager = Arc::clone(&state_manager);
         let rpc_chain_store = Arc::clone(&chain_store);
+        let fetch_on_miss_store = config.client.fetch_missing_state.then(|| {
+            FetchOnMissBlockstore::new(
+                chain_store.blockstore().clone(),
+                network_send.clone(),
+                Arc::clone(&peer_manager),
+            )
+        });
 
         services.spawn(async move {
             info!("JSON-RPC endpoint started at {}", config.client.rpc_address);
@@ -309,14 +294,17 @@ pub(super) async fn start(config: Config, detached: bool) -> anyhow::Result<Db>
                 Arc::new(RPCState {
                     state_manager: Arc::clone(&rpc_state_manager),
                     keystore: keystore_rpc,
+                    token_registry,
                     mpool,
                     bad_blocks,
                     sync_state,
+                    clock_drift,
                     network_send,
                     network_name,
                     beacon: rpc_state_manager.beacon_schedule(), // TODO: the RPCState can fetch this itself from the StateManager
                     chain_store: rpc_chain_store,
                     new_mined_block_tx: tipset_sink,
+                    fetch_on_miss_store,
                 }),
                 rpc_listen,
                 FOREST_VERSION_STRING.as_str(),
@@ -499,6 +487,25 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn import_compressed_snapshot_from_file_valid() -> anyhow::Result<()> {
+        use async_compression::tokio::write::ZstdEncoder;
+        use tokio::io::AsyncWriteExt;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("chain4.car.zst");
+        let mut encoder = ZstdEncoder::new(tokio::fs::File::create(&path).await?);
+        encoder
+            .write_all(&std::fs::read("test_files/chain4.car")?)
+            .await?;
+        encoder.shutdown().await?;
+
+        anyhow::ensure!(import_snapshot_from_file(path.to_str().unwrap())
+            .await
+            .is_ok());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn import_snapshot_from_file_invalid() -> anyhow::Result<()> {
         anyhow::ensure!(import_snapshot_from_file("Cargo.toml").await.is_err());
@@ -43,6 +43,9 @@ pub struct Client {
     pub token_exp: Duration,
     /// Display progress bars mode. Auto will display if TTY.
     pub show_progress_bars: ProgressBarVisibility,
+    /// Fetch the state missing from the database over `Bitswap` when answering `StateCall`,
+    /// e.g. on nodes synced from a minimal snapshot.
+    pub fetch_missing_state: bool,
 }
 
 impl Default for Client {
@@ -65,6 +68,7 @@ impl Default for Client {
             auto_download_snapshot: false,
             token_exp: Duration::seconds(5184000), // 60 Days = 5184000 Seconds
             show_progress_bars: Default::default(),
+            fetch_missing_state: false,
         }
     }
 }
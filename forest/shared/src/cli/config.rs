@@ -1,6 +1,7 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use ahash::HashMap;
 use forest_chain_sync::SyncConfig;
 use forest_db::db_engine::DbConfig;
 use forest_libp2p::Libp2pConfig;
@@ -64,6 +65,33 @@ impl LogValue {
 pub struct SnapshotFetchConfig {
     pub forest: ForestSnapshotFetchConfig,
     pub filecoin: FilecoinSnapshotFetchConfig,
+    /// Additional snapshot providers, selected by name with `--provider`
+    #[serde(default)]
+    pub providers: HashMap<String, SnapshotProviderConfig>,
+}
+
+/// Snapshot provider configured by URL.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SnapshotProviderConfig {
+    /// Snapshot served at a URL, which may redirect once to the actual file. A `sha256sum` file
+    /// is expected at the same URL (but different extension).
+    Http { url: Url },
+    /// Most recent `.car` file of a bucket of an S3-compatible endpoint, e.g. MinIO. A
+    /// `sha256sum` file is expected next to it.
+    S3 {
+        endpoint: Url,
+        bucket: String,
+        #[serde(default = "default_s3_region")]
+        region: String,
+        /// Key prefix of the snapshots in the bucket, e.g. `calibnet/`
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq)]
@@ -241,6 +269,7 @@ mod test {
                     auto_download_snapshot: bool::arbitrary(g),
                     token_exp: Duration::milliseconds(i64::arbitrary(g)),
                     show_progress_bars: ProgressBarVisibility::arbitrary(g),
+                    fetch_missing_state: bool::arbitrary(g),
                 },
                 rocks_db: forest_db::rocks_config::RocksDbConfig {
                     create_if_missing: bool::arbitrary(g),
@@ -266,10 +295,24 @@ mod test {
                     mdns: bool::arbitrary(g),
                     kademlia: bool::arbitrary(g),
                     target_peer_count: u32::arbitrary(g),
+                    bitswap: forest_libp2p::BitswapServerConfig {
+                        enabled: bool::arbitrary(g),
+                        max_outbound_bytes_per_sec: u32::arbitrary(g).into(),
+                        max_concurrent_responses_per_peer: u32::arbitrary(g) as _,
+                        max_queued_wants_per_peer: u32::arbitrary(g) as _,
+                    },
+                    chain_exchange: forest_libp2p::chain_exchange::ChainExchangeServerConfig {
+                        max_request_len: u32::arbitrary(g).into(),
+                        max_requests_per_peer_per_minute: u32::arbitrary(g),
+                        max_response_bytes: u32::arbitrary(g) as _,
+                        max_cache_bytes: u32::arbitrary(g) as _,
+                    },
                 },
                 sync: SyncConfig {
                     req_window: i64::arbitrary(g),
                     tipset_sample_size: usize::arbitrary(g),
+                    clock_drift_warn_threshold_ms: u32::arbitrary(g).into(),
+                    bad_block_ttl_secs: u32::arbitrary(g).into(),
                 },
             }
         }
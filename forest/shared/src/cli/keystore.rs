@@ -0,0 +1,48 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::Config;
+use forest_key_management::{KeyStore, KeyStoreConfig, ENCRYPTED_KEYSTORE_NAME};
+use log::error;
+use rpassword::read_password;
+use std::io::Write;
+
+/// Environment variable holding the passphrase of the encrypted keystore, to open it without
+/// prompting.
+pub const FOREST_KEYSTORE_PHRASE_ENV: &str = "FOREST_KEYSTORE_PHRASE";
+
+/// Opens the keystore of the data directory. The passphrase of an encrypted keystore is taken
+/// from [`FOREST_KEYSTORE_PHRASE_ENV`] when set, and prompted for otherwise.
+pub fn open_keystore(config: &Config) -> anyhow::Result<KeyStore> {
+    let data_dir = config.client.data_dir.clone();
+    if !config.client.encrypt_keystore {
+        return Ok(KeyStore::new(KeyStoreConfig::Persistent(data_dir))?);
+    }
+
+    if let Ok(passphrase) = std::env::var(FOREST_KEYSTORE_PHRASE_ENV) {
+        return KeyStore::new(KeyStoreConfig::Encrypted(data_dir, passphrase)).map_err(|e| {
+            anyhow::anyhow!("Failed to open the keystore with {FOREST_KEYSTORE_PHRASE_ENV}: {e}")
+        });
+    }
+
+    loop {
+        print!("Enter the keystore passphrase: ");
+        std::io::stdout().flush()?;
+        let passphrase = read_password()?;
+
+        if !data_dir.join(ENCRYPTED_KEYSTORE_NAME).exists() {
+            print!("Confirm passphrase: ");
+            std::io::stdout().flush()?;
+
+            if passphrase != read_password()? {
+                error!("Passphrases do not match. Please retry.");
+                continue;
+            }
+        }
+
+        match KeyStore::new(KeyStoreConfig::Encrypted(data_dir.clone(), passphrase)) {
+            Ok(keystore) => return Ok(keystore),
+            Err(_) => error!("Incorrect passphrase entered. Please try again."),
+        }
+    }
+}
@@ -3,9 +3,10 @@
 
 mod client;
 mod config;
+mod keystore;
 mod snapshot_fetch;
 
-pub use self::{client::*, config::*, snapshot_fetch::*};
+pub use self::{client::*, config::*, keystore::*, snapshot_fetch::*};
 use crate::logger::LoggingColor;
 
 use ahash::HashSet;
@@ -101,6 +102,9 @@ pub struct CliOpts {
     /// Display progress bars mode [always, never, auto]. Auto will display if TTY.
     #[structopt(long, default_value = "auto")]
     pub show_progress_bars: ProgressBarVisibility,
+    /// Fetch the state missing from the database over `Bitswap` when answering `StateCall`
+    #[structopt(long)]
+    pub fetch_missing_state: bool,
     /// Turn on tokio-console support for debugging
     #[structopt(long)]
     pub tokio_console: bool,
@@ -172,6 +176,9 @@ impl CliOpts {
         cfg.client.halt_after_import = self.halt_after_import;
         cfg.client.auto_download_snapshot = self.auto_download_snapshot;
         cfg.client.show_progress_bars = self.show_progress_bars;
+        if self.fetch_missing_state {
+            cfg.client.fetch_missing_state = true;
+        }
 
         cfg.network.kademlia = self.kademlia.unwrap_or(cfg.network.kademlia);
         cfg.network.mdns = self.mdns.unwrap_or(cfg.network.mdns);
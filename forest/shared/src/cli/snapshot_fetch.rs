@@ -1,20 +1,21 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
-use super::Config;
+use super::{Config, SnapshotProviderConfig};
 use crate::cli::to_size_string;
 use anyhow::bail;
+use async_trait::async_trait;
 use chrono::DateTime;
 use forest_utils::{
-    io::{progress_bar::Units, ProgressBar, TempFile},
+    io::{progress_bar::Units, ProgressBar},
     net::{
         https_client,
-        hyper::{self, client::connect::Connect, Body, Response},
+        hyper::{self, client::connect::Connect, Body, Response, StatusCode},
     },
 };
 use hex::{FromHex, ToHex};
-use log::info;
+use log::{info, warn};
 use regex::Regex;
-use s3::Bucket;
+use s3::{Bucket, Region};
 use sha2::{Digest, Sha256};
 use std::str::FromStr;
 use std::{
@@ -24,16 +25,23 @@ use std::{
 };
 use time::{format_description, format_description::well_known::Iso8601, Date};
 use tokio::{
-    fs::{create_dir_all, File},
-    io::{AsyncWriteExt, BufWriter},
+    fs::{create_dir_all, File, OpenOptions},
+    io::{AsyncReadExt, AsyncWriteExt, BufWriter},
 };
 use url::Url;
 
+/// Number of times an interrupted snapshot download is resumed before giving up.
+const DOWNLOAD_RETRIES: u32 = 5;
+
 /// Snapshot fetch service provider
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SnapshotServer {
     Forest,
     Filecoin,
+    /// Provider configured in the `snapshot_fetch.providers` section of the configuration
+    Custom(String),
+    /// Snapshot served at the given HTTP URL
+    Url(Url),
 }
 
 impl FromStr for SnapshotServer {
@@ -43,13 +51,199 @@ impl FromStr for SnapshotServer {
         match provider.to_lowercase().as_str() {
             "forest" => Ok(SnapshotServer::Forest),
             "filecoin" => Ok(SnapshotServer::Filecoin),
-            _ => bail!(
-                "Failed to fetch snapshot from: {provider}, Must be one of `forest`|`filecoin`."
-            ),
+            _ if provider.starts_with("http://") || provider.starts_with("https://") => {
+                Ok(SnapshotServer::Url(Url::parse(provider)?))
+            }
+            "" => bail!("Snapshot provider can't be empty"),
+            _ => Ok(SnapshotServer::Custom(provider.to_owned())),
         }
     }
 }
 
+/// Location of a snapshot to download.
+#[derive(Debug, Clone)]
+pub struct RemoteSnapshot {
+    pub url: Url,
+    /// Name under which the snapshot is saved
+    pub filename: String,
+}
+
+/// Source of snapshots, resolving the location of the most recent snapshot it serves.
+#[async_trait]
+pub trait SnapshotProvider: Send + Sync {
+    async fn latest(&self) -> anyhow::Result<RemoteSnapshot>;
+}
+
+/// Serves the most recent `.car` file of an S3-compatible bucket.
+pub struct S3Provider {
+    bucket: Bucket,
+    /// URL the keys of the bucket are relative to
+    base_url: Url,
+    prefix: String,
+}
+
+impl S3Provider {
+    /// Provider for a bucket of an S3-compatible endpoint such as MinIO, using path-style URLs.
+    pub fn new(endpoint: &Url, bucket: &str, region: &str, prefix: &str) -> anyhow::Result<Self> {
+        let region = Region::Custom {
+            region: region.to_owned(),
+            endpoint: endpoint.as_str().trim_end_matches('/').to_owned(),
+        };
+        Ok(Self {
+            bucket: Bucket::new_public(bucket, region)?.with_path_style(),
+            base_url: endpoint.join(&format!("{bucket}/"))?,
+            prefix: prefix.to_owned(),
+        })
+    }
+
+    /// Provider for a bucket served with virtual-hosted-style URLs, e.g. `DigitalOcean` spaces.
+    fn virtual_hosted(
+        bucket_url: &Url,
+        bucket: &str,
+        region: &str,
+        prefix: &str,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            bucket: Bucket::new_public(bucket, region.parse()?)?,
+            base_url: bucket_url.clone(),
+            prefix: prefix.to_owned(),
+        })
+    }
+}
+
+#[async_trait]
+impl SnapshotProvider for S3Provider {
+    async fn latest(&self) -> anyhow::Result<RemoteSnapshot> {
+        // Grab contents of the bucket
+        let bucket_contents = self
+            .bucket
+            .list(self.prefix.clone(), Some("/".to_string()))?;
+
+        // Find the the last modified file that is not a directory or empty file
+        let last_modified = bucket_contents
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Couldn't list bucket"))?
+            .contents
+            .iter()
+            .filter(|obj| {
+                obj.size > 0
+                    && matches!(
+                        obj.key.rsplit_once('.').unwrap_or_default().1,
+                        "car" | "zst"
+                    )
+            })
+            .max_by_key(|obj| DateTime::parse_from_rfc3339(&obj.last_modified).unwrap_or_default())
+            .ok_or_else(|| anyhow::anyhow!("Couldn't retrieve bucket contents"))?;
+
+        let filename = last_modified
+            .key
+            .rsplit_once('/')
+            .map(|(_, name)| name)
+            .unwrap_or(&last_modified.key)
+            .to_owned();
+        Ok(RemoteSnapshot {
+            url: self.base_url.join(&last_modified.key)?,
+            filename,
+        })
+    }
+}
+
+/// Serves the snapshot at a URL, which may redirect once to the actual file.
+pub struct HttpProvider {
+    url: Url,
+}
+
+impl HttpProvider {
+    pub fn new(url: Url) -> Self {
+        Self { url }
+    }
+}
+
+#[async_trait]
+impl SnapshotProvider for HttpProvider {
+    async fn latest(&self) -> anyhow::Result<RemoteSnapshot> {
+        let client = https_client();
+        let head_response = client
+            .request(hyper::Request::head(self.url.as_str()).body("".into())?)
+            .await?;
+
+        // Use the redirect if available.
+        let url: Url = match head_response.headers().get("location") {
+            Some(url) => url.to_str()?.try_into()?,
+            None => self.url.clone(),
+        };
+        let filename = filename_from_url(&url)?;
+        Ok(RemoteSnapshot { url, filename })
+    }
+}
+
+/// Serves the latest snapshot of the Filecoin snapshot service, normalizing its name.
+struct FilecoinProvider {
+    http: HttpProvider,
+    network: String,
+}
+
+#[async_trait]
+impl SnapshotProvider for FilecoinProvider {
+    async fn latest(&self) -> anyhow::Result<RemoteSnapshot> {
+        let snapshot = self.http.latest().await?;
+        let filename = normalize_filecoin_snapshot_name(&self.network, &snapshot.filename)?;
+        Ok(RemoteSnapshot {
+            url: snapshot.url,
+            filename,
+        })
+    }
+}
+
+/// Returns the provider of the given server, for the chain of the configuration.
+pub fn snapshot_provider(
+    server: &SnapshotServer,
+    config: &Config,
+) -> anyhow::Result<Box<dyn SnapshotProvider>> {
+    let network = config.chain.name.to_lowercase();
+    Ok(match server {
+        SnapshotServer::Forest => {
+            let snapshot_fetch_config = match network.as_str() {
+                "mainnet" => bail!(
+                    "Mainnet snapshot fetch service not provided by Forest yet. Suggestion: use `--provider=filecoin` to fetch from Filecoin server."
+                ),
+                "calibnet" => &config.snapshot_fetch.forest.calibnet,
+                _ => bail!("Fetch not supported for chain {}", config.chain.name,),
+            };
+            Box::new(S3Provider::virtual_hosted(
+                &snapshot_fetch_config.snapshot_spaces_url,
+                &snapshot_fetch_config.bucket_name,
+                &snapshot_fetch_config.region,
+                &snapshot_fetch_config.path,
+            )?)
+        }
+        SnapshotServer::Filecoin => {
+            let service_url = match network.as_str() {
+                "mainnet" => config.snapshot_fetch.filecoin.mainnet.clone(),
+                "calibnet" => config.snapshot_fetch.filecoin.calibnet.clone(),
+                _ => bail!("Fetch not supported for chain {}", config.chain.name,),
+            };
+            Box::new(FilecoinProvider {
+                http: HttpProvider::new(service_url),
+                network: config.chain.name.clone(),
+            })
+        }
+        SnapshotServer::Custom(name) => match config.snapshot_fetch.providers.get(name) {
+            Some(SnapshotProviderConfig::Http { url }) => Box::new(HttpProvider::new(url.clone())),
+            Some(SnapshotProviderConfig::S3 {
+                endpoint,
+                bucket,
+                region,
+                prefix,
+            }) => Box::new(S3Provider::new(endpoint, bucket, region, prefix)?),
+            None => bail!(
+                "Unknown snapshot provider: {name}. Must be one of `forest`|`filecoin`, a URL or a provider of the `snapshot_fetch.providers` configuration."
+            ),
+        },
+        SnapshotServer::Url(url) => Box::new(HttpProvider::new(url.clone())),
+    })
+}
+
 /// Snapshot attributes
 pub struct SnapshotInfo {
     pub network: String,
@@ -67,7 +261,7 @@ impl SnapshotStore {
     pub fn new(config: &Config, snapshot_dir: &PathBuf) -> SnapshotStore {
         let mut snapshots = Vec::new();
         let pattern = Regex::new(
-            r"^([^_]+?)_snapshot_(?P<network>[^_]+?)_(?P<date>\d{4}-\d{2}-\d{2})_height_(?P<height>\d+).car(.tmp|.aria2)?$",
+            r"^([^_]+?)_snapshot_(?P<network>[^_]+?)_(?P<date>\d{4}-\d{2}-\d{2})_height_(?P<height>\d+).car(.zst)?(.tmp|.aria2)?$",
         ).unwrap();
         if let Ok(dir) = std::fs::read_dir(snapshot_dir) {
             dir.flatten()
@@ -113,11 +307,19 @@ impl SnapshotStore {
 
 pub fn is_car_or_tmp(path: &Path) -> bool {
     let ext = path.extension().unwrap_or_default();
-    ext == "car" || ext == "tmp" || ext == "aria2"
+    ext == "car" || ext == "zst" || ext == "tmp" || ext == "aria2"
+}
+
+/// Path of the partial download of a snapshot, e.g. `snapshot.car.tmp` or `snapshot.car.zst.tmp`.
+fn tmp_path(snapshot_path: &Path) -> PathBuf {
+    let mut path = snapshot_path.as_os_str().to_owned();
+    path.push(".tmp");
+    path.into()
 }
 
 /// Fetches snapshot from a trusted location and saves it to the given directory. Chain is inferred
-/// from configuration.
+/// from configuration. In case of failure (e.g. connection interrupted) the partial download is
+/// kept, and resumed by the next fetch of the same snapshot.
 pub async fn snapshot_fetch(
     snapshot_out_dir: &Path,
     config: &Config,
@@ -132,193 +334,224 @@ pub async fn snapshot_fetch(
             _ => anyhow::bail!("Fetch not supported for chain {}", config.chain.name),
         },
     };
-    match server {
-        SnapshotServer::Forest => snapshot_fetch_forest(snapshot_out_dir, config, use_aria2).await,
-        SnapshotServer::Filecoin => {
-            snapshot_fetch_filecoin(snapshot_out_dir, config, use_aria2).await
-        }
-    }
-}
-
-/// Checks whether `aria2c` is available in PATH
-pub fn is_aria2_installed() -> bool {
-    which::which("aria2c").is_ok()
-}
-
-/// Fetches snapshot for `calibnet` from a default, trusted location. On success, the snapshot will be
-/// saved in the given directory. In case of failure (e.g. connection interrupted) it will not be
-/// removed.
-async fn snapshot_fetch_forest(
-    snapshot_out_dir: &Path,
-    config: &Config,
-    use_aria2: bool,
-) -> anyhow::Result<PathBuf> {
-    let snapshot_fetch_config = match config.chain.name.to_lowercase().as_str() {
-        "mainnet" => bail!(
-            "Mainnet snapshot fetch service not provided by Forest yet. Suggestion: use `--provider=filecoin` to fetch from Filecoin server."
-        ),
-        "calibnet" => &config.snapshot_fetch.forest.calibnet,
-        _ => bail!("Fetch not supported for chain {}", config.chain.name,),
-    };
-    let name = &snapshot_fetch_config.bucket_name;
-    let region = &snapshot_fetch_config.region;
-    let bucket = Bucket::new_public(name, region.parse()?)?;
-
-    // Grab contents of the bucket
-    let bucket_contents = bucket.list(snapshot_fetch_config.path.clone(), Some("/".to_string()))?;
-
-    // Find the the last modified file that is not a directory or empty file
-    let last_modified = bucket_contents
-        .first()
-        .ok_or_else(|| anyhow::anyhow!("Couldn't list bucket"))?
-        .contents
-        .iter()
-        .filter(|obj| obj.size > 0 && obj.key.rsplit_once('.').unwrap_or_default().1 == "car")
-        .max_by_key(|obj| DateTime::parse_from_rfc3339(&obj.last_modified).unwrap_or_default())
-        .ok_or_else(|| anyhow::anyhow!("Couldn't retrieve bucket contents"))?
-        .to_owned();
+    let snapshot = snapshot_provider(server, config)?.latest().await?;
 
-    // Grab the snapshot name and create requested directory tree.
-    let filename = last_modified.key.rsplit_once('/').unwrap().1;
-    let snapshot_path = snapshot_out_dir.join(filename);
+    // Create requested directory tree to store the snapshot
     create_dir_all(snapshot_out_dir).await?;
+    let snapshot_path = snapshot_out_dir.join(&snapshot.filename);
 
-    // Download the file
-    // It'd be better to use the bucket directly with `get_object_stream`, but at the time
-    // of writing this code the Stream API is a bit lacking, making adding a progress bar a pain.
-    // https://github.com/durch/rust-s3/issues/275
     let client = https_client();
-    let snapshot_spaces_url = &snapshot_fetch_config.snapshot_spaces_url;
-    let path = &snapshot_fetch_config.path;
-    let url = snapshot_spaces_url.join(path)?.join(filename)?;
-
-    let snapshot_response = client.get(url.as_str().try_into()?).await?;
     if use_aria2 {
-        download_snapshot_and_validate_checksum_with_aria2(client, url, &snapshot_path).await?
+        download_snapshot_and_validate_checksum_with_aria2(client, snapshot.url, &snapshot_path)
+            .await?
     } else {
-        let total_size = last_modified.size;
-        download_snapshot_and_validate_checksum(
-            client,
-            url,
-            &snapshot_path,
-            snapshot_response,
-            total_size,
-        )
-        .await?;
+        download_snapshot_and_validate_checksum(client, snapshot.url, &snapshot_path).await?;
     }
-
     Ok(snapshot_path)
 }
 
-/// Fetches snapshot for `mainnet` from a default, trusted location. On success, the snapshot will be
-/// saved in the given directory. In case of failure (e.g. checksum verification fiasco) it will
-/// not be removed.
-async fn snapshot_fetch_filecoin(
-    snapshot_out_dir: &Path,
-    config: &Config,
-    use_aria2: bool,
-) -> anyhow::Result<PathBuf> {
-    let service_url = match config.chain.name.to_lowercase().as_ref() {
-        "mainnet" => config.snapshot_fetch.filecoin.mainnet.clone(),
-        "calibnet" => config.snapshot_fetch.filecoin.calibnet.clone(),
-        _ => bail!("Fetch not supported for chain {}", config.chain.name,),
-    };
-    let client = https_client();
-
-    let snapshot_url = {
-        let head_response = client
-            .request(hyper::Request::head(service_url.as_str()).body("".into())?)
-            .await?;
-
-        // Use the redirect if available.
-        match head_response.headers().get("location") {
-            Some(url) => url.to_str()?.try_into()?,
-            None => service_url,
-        }
-    };
-
-    let snapshot_response = client.get(snapshot_url.as_str().try_into()?).await?;
-
-    // Grab the snapshot file name
-    let filename = filename_from_url(&snapshot_url)?;
-    // Create requested directory tree to store the snapshot
-    create_dir_all(snapshot_out_dir).await?;
-    let snapshot_name = normalize_filecoin_snapshot_name(&config.chain.name, &filename)?;
-    let snapshot_path = snapshot_out_dir.join(&snapshot_name);
-    // Download the file
-    if use_aria2 {
-        download_snapshot_and_validate_checksum_with_aria2(client, snapshot_url, &snapshot_path)
-            .await?
-    } else {
-        let total_size = snapshot_response
-            .headers()
-            .get("content-length")
-            .and_then(|ct_len| ct_len.to_str().ok())
-            .and_then(|ct_len| ct_len.parse::<u64>().ok())
-            .ok_or_else(|| anyhow::anyhow!("Couldn't retrieve content length"))?;
-
-        download_snapshot_and_validate_checksum(
-            client,
-            snapshot_url,
-            &snapshot_path,
-            snapshot_response,
-            total_size,
-        )
-        .await?;
-    }
-    Ok(snapshot_path)
+/// Checks whether `aria2c` is available in PATH
+pub fn is_aria2_installed() -> bool {
+    which::which("aria2c").is_ok()
 }
 
-/// Downloads snapshot to a file with a progress bar. Returns the digest of the downloaded file.
+/// Downloads snapshot to a file with a progress bar, and validates its checksum. The snapshot is
+/// downloaded to a `.car.tmp` file first, which is resumed with HTTP range requests when the
+/// connection is interrupted or when a previous download did not complete.
 async fn download_snapshot_and_validate_checksum<C>(
     client: hyper::Client<C>,
     url: Url,
     snapshot_path: &Path,
-    snapshot_response: Response<Body>,
-    total_size: u64,
 ) -> anyhow::Result<()>
 where
     C: Connect + Clone + Send + Sync + 'static,
 {
     info!("Snapshot url: {url}");
+    info!("Snapshot will be downloaded to {}", snapshot_path.display());
+
+    let checksum_expected = fetch_checksum(&client, url.clone()).await?;
+
+    let snapshot_file_tmp = tmp_path(snapshot_path);
+    let mut hasher = Sha256::new();
+    let mut downloaded = hash_partial_download(&snapshot_file_tmp, &mut hasher).await?;
+    if downloaded > 0 {
+        info!("Resuming the download from {downloaded} bytes");
+    }
+
+    let mut progress_bar = None;
+    let mut attempt = 0;
+    loop {
+        match download_remaining(
+            &client,
+            &url,
+            &snapshot_file_tmp,
+            &mut downloaded,
+            &mut hasher,
+            &mut progress_bar,
+        )
+        .await
+        {
+            Ok(()) => break,
+            Err(e) if attempt < DOWNLOAD_RETRIES => {
+                attempt += 1;
+                warn!(
+                    "Snapshot download interrupted ({e}), resuming ({attempt}/{DOWNLOAD_RETRIES})"
+                );
+                // Part of the last chunk may not have been written, hash what is on disk again.
+                let written = tokio::fs::metadata(&snapshot_file_tmp)
+                    .await
+                    .map(|m| m.len())
+                    .unwrap_or_default();
+                if written != downloaded {
+                    downloaded = hash_partial_download(&snapshot_file_tmp, &mut hasher).await?;
+                }
+                tokio::time::sleep(Duration::from_secs(attempt as u64)).await;
+            }
+            Err(e) => {
+                return Err(
+                    e.context("Failed to download the snapshot, run the fetch again to resume it")
+                )
+            }
+        }
+    }
+    if let Some(progress_bar) = progress_bar {
+        progress_bar.finish_println("Finished downloading the snapshot.");
+    }
+
+    info!("Validating checksum...");
+    let checksum_actual = hasher.finalize();
+    if let Err(e) = validate_checksum(&checksum_expected, &checksum_actual) {
+        // The download is corrupted, resuming it would fail again.
+        std::fs::remove_file(&snapshot_file_tmp)?;
+        return Err(e);
+    }
     info!(
-        "Snapshot will be downloaded to {} ({})",
-        snapshot_path.display(),
-        to_size_string(&total_size.into())?
+        "Snapshot checksum correct. {}",
+        checksum_actual.encode_hex::<String>()
     );
+    std::fs::rename(&snapshot_file_tmp, snapshot_path)?;
 
-    let progress_bar = ProgressBar::new(total_size);
-    progress_bar.message("Downloading snapshot ");
-    progress_bar.set_max_refresh_rate(Some(Duration::from_millis(500)));
-    progress_bar.set_units(Units::Bytes);
+    Ok(())
+}
+
+/// Downloads the part of the snapshot after the `downloaded` bytes already in `path`, appending
+/// it to the file and updating the hasher as chunks are written.
+async fn download_remaining<C>(
+    client: &hyper::Client<C>,
+    url: &Url,
+    path: &Path,
+    downloaded: &mut u64,
+    hasher: &mut Sha256,
+    progress_bar: &mut Option<ProgressBar>,
+) -> anyhow::Result<()>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let mut request = hyper::Request::get(url.as_str());
+    if *downloaded > 0 {
+        request = request.header(hyper::header::RANGE, format!("bytes={downloaded}-"));
+    }
+    let response = client.request(request.body(Body::empty())?).await?;
+
+    let total_size = match response.status() {
+        StatusCode::PARTIAL_CONTENT => content_range_total(&response)?,
+        StatusCode::OK => {
+            if *downloaded > 0 {
+                info!("Server doesn't support resuming downloads, restarting from scratch");
+                *downloaded = 0;
+                *hasher = Sha256::new();
+            }
+            content_length(&response)?
+        }
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // The previous download may have completed without being validated.
+            if content_range_total(&response).ok() == Some(*downloaded) {
+                return Ok(());
+            }
+            *downloaded = 0;
+            *hasher = Sha256::new();
+            bail!("Partial download is larger than the snapshot");
+        }
+        status => bail!("Unexpected response status: {status}"),
+    };
 
-    let snapshot_file_tmp = TempFile::new(snapshot_path.with_extension("car.tmp"));
-    let file = File::create(snapshot_file_tmp.path()).await?;
+    let progress_bar = progress_bar.get_or_insert_with(|| {
+        info!(
+            "Snapshot size: {}",
+            to_size_string(&total_size.into()).unwrap_or_default()
+        );
+        let progress_bar = ProgressBar::new(total_size);
+        progress_bar.message("Downloading snapshot ");
+        progress_bar.set_max_refresh_rate(Some(Duration::from_millis(500)));
+        progress_bar.set_units(Units::Bytes);
+        progress_bar
+    });
+    progress_bar.set(*downloaded);
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.set_len(*downloaded).await?;
     let mut writer = BufWriter::new(file);
-    let mut downloaded: u64 = 0;
-    let mut stream = snapshot_response.into_body();
+    let mut stream = response.into_body();
 
-    let mut snapshot_hasher = Sha256::new();
     while let Some(item) = futures::StreamExt::next(&mut stream).await {
         let chunk = item?;
         writer.write_all(&chunk).await?;
-        downloaded = total_size.min(downloaded + chunk.len() as u64);
-        progress_bar.set(downloaded);
-        snapshot_hasher.update(chunk);
+        hasher.update(&chunk);
+        *downloaded += chunk.len() as u64;
+        progress_bar.set(total_size.min(*downloaded));
     }
     writer.flush().await?;
 
-    let file_size = std::fs::metadata(snapshot_file_tmp.path())?.len();
-    if file_size != total_size {
-        bail!("Didn't manage to download the entire file. {file_size}/{total_size} [B]");
+    if *downloaded != total_size {
+        bail!("Didn't manage to download the entire file. {downloaded}/{total_size} [B]");
     }
+    Ok(())
+}
 
-    progress_bar.finish_println("Finished downloading the snapshot.");
+/// Hashes the partially downloaded snapshot at `path` with a fresh hasher, returning its size.
+async fn hash_partial_download(path: &Path, hasher: &mut Sha256) -> anyhow::Result<u64> {
+    *hasher = Sha256::new();
+    let mut file = match File::open(path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
 
-    fetch_checksum_and_validate(client, url, &snapshot_hasher.finalize()).await?;
-    std::fs::rename(snapshot_file_tmp.path(), snapshot_path)?;
+    let mut buf = vec![0; 1 << 20];
+    let mut size = 0;
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok(size)
+}
 
-    Ok(())
+fn content_length(response: &Response<Body>) -> anyhow::Result<u64> {
+    response
+        .headers()
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|ct_len| ct_len.to_str().ok())
+        .and_then(|ct_len| ct_len.parse::<u64>().ok())
+        .ok_or_else(|| anyhow::anyhow!("Couldn't retrieve content length"))
+}
+
+/// Returns the complete size from a `Content-Range: bytes <range>/<size>` header.
+fn content_range_total(response: &Response<Body>) -> anyhow::Result<u64> {
+    response
+        .headers()
+        .get(hyper::header::CONTENT_RANGE)
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.rsplit_once('/'))
+        .and_then(|(_, size)| size.parse::<u64>().ok())
+        .ok_or_else(|| anyhow::anyhow!("Couldn't retrieve content range"))
 }
 
 async fn download_snapshot_and_validate_checksum_with_aria2<C>(
@@ -336,15 +569,9 @@ where
         bail!("Command aria2c is not in PATH. To install aria2, refer to instructions on https://aria2.github.io/");
     }
 
-    let checksum_url = replace_extension_url(url.clone(), "sha256sum")?;
-    let checksum_response = client.get(checksum_url.as_str().try_into()?).await?;
-    if !checksum_response.status().is_success() {
-        bail!("Unable to get the checksum file. Url: {checksum_url}");
-    }
-    let checksum_bytes = hyper::body::to_bytes(checksum_response.into_body()).await?
-        [..Sha256::output_size() * 2]
-        .to_vec();
-    let checksum_expected = String::from_utf8(checksum_bytes)?;
+    let checksum_expected = fetch_checksum(&client, url.clone())
+        .await?
+        .encode_hex::<String>();
     info!("Expected sha256 checksum: {checksum_expected}");
     download_with_aria2(
         url.as_str(),
@@ -457,36 +684,22 @@ fn replace_extension_url(mut url: Url, extension: &str) -> anyhow::Result<Url> {
     Ok(url)
 }
 
-/// Fetches the relevant checksum for the snapshot, compares it with the result one. Fails if they
-/// don't match. The checksum is expected to be located in the same location as the snapshot but
-/// with a `.sha256sum` extension.
-async fn fetch_checksum_and_validate<C>(
-    client: hyper::Client<C>,
-    url: Url,
-    snapshot_checksum: &[u8],
-) -> anyhow::Result<()>
+/// Fetches the relevant checksum for the snapshot. The checksum is expected to be located in the
+/// same location as the snapshot but with a `.sha256sum` extension.
+async fn fetch_checksum<C>(client: &hyper::Client<C>, url: Url) -> anyhow::Result<Vec<u8>>
 where
     C: Connect + Clone + Send + Sync + 'static,
 {
-    info!("Validating checksum...");
     let checksum_url = replace_extension_url(url, "sha256sum")?;
     let checksum_expected_file = client.get(checksum_url.as_str().try_into()?).await?;
     if !checksum_expected_file.status().is_success() {
-        bail!("Unable to get the checksum file. Snapshot downloaded but not verified.");
+        bail!("Unable to get the checksum file. Url: {checksum_url}");
     }
 
     let checksum_bytes = hyper::body::to_bytes(checksum_expected_file.into_body()).await?;
     // checksum file is hex-encoded with optionally trailing `- ` at the end. Take only what's needed, i.e.
     // encoded digest, for SHA256 it's 32 bytes.
-    let checksum_expected = checksum_from_file(&checksum_bytes, Sha256::output_size())?;
-
-    validate_checksum(&checksum_expected, snapshot_checksum)?;
-    info!(
-        "Snapshot checksum correct. {}",
-        snapshot_checksum.encode_hex::<String>()
-    );
-
-    Ok(())
+    checksum_from_file(&checksum_bytes, Sha256::output_size())
 }
 
 /// Creates regular checksum (raw bytes) from a checksum file with format:
@@ -662,11 +875,16 @@ mod test {
     }
 
     fn serve_forest_logo() -> Result<(String, tokio::sync::oneshot::Sender<()>)> {
+        let (url, shutdown_tx) = serve_dir("../../.github")?;
+        Ok((format!("{url}/forest_logo.png"), shutdown_tx))
+    }
+
+    fn serve_dir(dir: impl AsRef<Path>) -> Result<(String, tokio::sync::oneshot::Sender<()>)> {
         let listener = TcpListener::bind("127.0.0.1:0")?;
         let addr = listener.local_addr()?;
-        let url = format!("http://{}:{}/forest_logo.png", addr.ip(), addr.port());
+        let url = format!("http://{}:{}", addr.ip(), addr.port());
         let app = {
-            let serve_dir = get_service(ServeDir::new("../../.github")).handle_error(|_| async {
+            let serve_dir = get_service(ServeDir::new(dir)).handle_error(|_| async {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong...")
             });
             Router::new().nest_service("/", serve_dir)
@@ -681,6 +899,78 @@ mod test {
         Ok((url, shutdown_tx))
     }
 
+    /// Serves a snapshot and its checksum file, returning the snapshot content and URL.
+    fn serve_snapshot(dir: &Path) -> Result<(Vec<u8>, Url, tokio::sync::oneshot::Sender<()>)> {
+        let content: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+        std::fs::write(dir.join("snapshot.car"), &content)?;
+        std::fs::write(
+            dir.join("snapshot.sha256sum"),
+            format!("{} -", Sha256::digest(&content).encode_hex::<String>()),
+        )?;
+        let (url, shutdown_tx) = serve_dir(dir)?;
+        Ok((
+            content,
+            Url::parse(&format!("{url}/snapshot.car"))?,
+            shutdown_tx,
+        ))
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn download_resumes_partial_snapshot() -> Result<()> {
+        let served = tempfile::tempdir()?;
+        let (content, url, shutdown_tx) = serve_snapshot(served.path())?;
+
+        let out = tempfile::tempdir()?;
+        let snapshot_path = out.path().join("snapshot.car");
+        std::fs::write(tmp_path(&snapshot_path), &content[..40_000])?;
+
+        download_snapshot_and_validate_checksum(https_client(), url, &snapshot_path).await?;
+        ensure!(std::fs::read(&snapshot_path)? == content);
+        ensure!(!tmp_path(&snapshot_path).exists());
+        shutdown_tx.send(()).unwrap();
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn download_discards_corrupted_partial_snapshot() -> Result<()> {
+        let served = tempfile::tempdir()?;
+        let (_, url, shutdown_tx) = serve_snapshot(served.path())?;
+
+        let out = tempfile::tempdir()?;
+        let snapshot_path = out.path().join("snapshot.car");
+        std::fs::write(tmp_path(&snapshot_path), [0xff; 1000])?;
+
+        let r = download_snapshot_and_validate_checksum(https_client(), url, &snapshot_path).await;
+        ensure!(r.is_err());
+        ensure!(!tmp_path(&snapshot_path).exists());
+        ensure!(!snapshot_path.exists());
+        shutdown_tx.send(()).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_server_from_str() {
+        assert!(matches!(
+            "Forest".parse::<SnapshotServer>().unwrap(),
+            SnapshotServer::Forest
+        ));
+        assert!(matches!(
+            "filecoin".parse::<SnapshotServer>().unwrap(),
+            SnapshotServer::Filecoin
+        ));
+        assert!(matches!(
+            "https://cthulhu.org/latest"
+                .parse::<SnapshotServer>()
+                .unwrap(),
+            SnapshotServer::Url(_)
+        ));
+        assert!(matches!(
+            "internal".parse::<SnapshotServer>().unwrap(),
+            SnapshotServer::Custom(name) if name == "internal"
+        ));
+        assert!("".parse::<SnapshotServer>().is_err());
+    }
+
     fn is_github_action() -> bool {
         // https://docs.github.com/en/actions/learn-github-actions/environment-variables#default-environment-variables
         std::env::var("GITHUB_ACTION").is_ok()
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use ahash::HashSet;
+use cid::multihash::{Code, MultihashDigest};
 use cid::Cid;
 use fvm_ipld_encoding::from_slice;
 use std::future::Future;
@@ -78,3 +79,83 @@ where
 
     Ok(())
 }
+
+/// Outcome of walking a DAG with [`verify_dag`].
+#[derive(Debug, Default, Clone)]
+pub struct DagVerification {
+    /// Number of blocks that were loaded and matched their CID
+    pub verified: u64,
+    /// Blocks that couldn't be loaded
+    pub missing: Vec<Cid>,
+    /// Blocks whose content doesn't match the multihash of their CID, or can't be decoded
+    pub corrupt: Vec<Cid>,
+}
+
+/// Walks the DAG under `root`, skipping the CIDs in `walked`, and checks that every block can be
+/// loaded and matches the multihash of its CID. Unlike [`recurse_links`], the walk doesn't stop at
+/// the first missing or corrupt block, they are collected in the report instead. As in
+/// [`recurse_links`], only `DAG_CBOR` and raw blocks are followed.
+pub fn verify_dag<F>(
+    walked: &mut HashSet<Cid>,
+    root: Cid,
+    load_block: &mut F,
+    report: &mut DagVerification,
+) -> Result<(), anyhow::Error>
+where
+    F: FnMut(&Cid) -> Result<Option<Vec<u8>>, anyhow::Error>,
+{
+    let mut to_walk = vec![root];
+    while let Some(cid) = to_walk.pop() {
+        if !is_walkable(&cid) || !walked.insert(cid) {
+            continue;
+        }
+
+        // Identity CIDs inline their content, which is trivially valid.
+        let data = if cid.hash().code() == IDENTITY_HASH {
+            cid.hash().digest().to_vec()
+        } else {
+            let data = match load_block(&cid)? {
+                Some(data) => data,
+                None => {
+                    report.missing.push(cid);
+                    continue;
+                }
+            };
+            // Blocks hashed with an unsupported function can't be verified.
+            if let Ok(code) = Code::try_from(cid.hash().code()) {
+                if code.digest(&data) != *cid.hash() {
+                    report.corrupt.push(cid);
+                    continue;
+                }
+            }
+            data
+        };
+
+        if cid.codec() == fvm_ipld_encoding::DAG_CBOR {
+            match from_slice::<Ipld>(&data) {
+                Ok(ipld) => collect_links(&ipld, &mut to_walk),
+                Err(_) => {
+                    report.corrupt.push(cid);
+                    continue;
+                }
+            }
+        }
+        report.verified += 1;
+    }
+    Ok(())
+}
+
+const IDENTITY_HASH: u64 = 0x00;
+
+fn is_walkable(cid: &Cid) -> bool {
+    cid.codec() == fvm_ipld_encoding::DAG_CBOR || cid.codec() == fvm_shared::IPLD_RAW
+}
+
+fn collect_links(ipld: &Ipld, links: &mut Vec<Cid>) {
+    match ipld {
+        Ipld::Map(m) => m.values().for_each(|v| collect_links(v, links)),
+        Ipld::List(list) => list.iter().for_each(|v| collect_links(v, links)),
+        Ipld::Link(cid) => links.push(*cid),
+        _ => (),
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use ahash::{HashMap, HashSet};
+use cid::multihash::{Code::Blake2b256, MultihashDigest};
+use cid::Cid;
+use forest_ipld::{verify_dag, DagVerification, Ipld};
+use fvm_ipld_encoding::{to_vec, DAG_CBOR};
+use libipld_macro::ipld;
+
+fn put(store: &mut HashMap<Cid, Vec<u8>>, ipld: &Ipld) -> Cid {
+    let bytes = to_vec(ipld).unwrap();
+    let cid = Cid::new_v1(DAG_CBOR, Blake2b256.digest(&bytes));
+    store.insert(cid, bytes);
+    cid
+}
+
+fn verify(store: &HashMap<Cid, Vec<u8>>, root: Cid) -> DagVerification {
+    let mut report = DagVerification::default();
+    verify_dag(
+        &mut HashSet::default(),
+        root,
+        &mut |cid: &Cid| Ok(store.get(cid).cloned()),
+        &mut report,
+    )
+    .unwrap();
+    report
+}
+
+#[test]
+fn verify_complete_dag() {
+    let mut store = HashMap::default();
+    let leaf = put(&mut store, &ipld!("leaf"));
+    let node = put(&mut store, &ipld!([leaf, leaf]));
+    let root = put(&mut store, &ipld!({ "node": node, "leaf": leaf }));
+
+    let report = verify(&store, root);
+    assert_eq!(report.verified, 3);
+    assert!(report.missing.is_empty());
+    assert!(report.corrupt.is_empty());
+}
+
+#[test]
+fn verify_collects_missing_and_corrupt_blocks() {
+    let mut store = HashMap::default();
+    let missing = put(&mut store, &ipld!("missing"));
+    let corrupt = put(&mut store, &ipld!("corrupt"));
+    let leaf = put(&mut store, &ipld!("leaf"));
+    let root = put(&mut store, &ipld!([missing, corrupt, leaf]));
+
+    store.remove(&missing);
+    store.insert(corrupt, to_vec(&ipld!("tampered")).unwrap());
+
+    let report = verify(&store, root);
+    assert_eq!(report.verified, 2);
+    assert_eq!(report.missing, vec![missing]);
+    assert_eq!(report.corrupt, vec![corrupt]);
+}
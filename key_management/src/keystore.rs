@@ -118,10 +118,20 @@ pub mod json {
     }
 }
 
+/// Persisted contents of a `KeyStore` that has labels. A `KeyStore` without labels is persisted
+/// as the bare map of keys, which is also how keystores written before labels are read.
+#[derive(Serialize, Deserialize)]
+struct LabelledKeys<K, L> {
+    keys: K,
+    labels: L,
+}
+
 /// `KeyStore` structure, this contains a set of `KeyInfos` indexed by address.
 #[derive(Clone, PartialEq, Debug, Eq)]
 pub struct KeyStore {
     key_info: HashMap<String, KeyInfo>,
+    /// Labels indexed by address, persisted along with the keys
+    labels: HashMap<String, String>,
     persistence: Option<PersistentKeyStore>,
     encryption: Option<EncryptedKeyStore>,
 }
@@ -166,6 +176,7 @@ impl KeyStore {
         match config {
             KeyStoreConfig::Memory => Ok(Self {
                 key_info: HashMap::new(),
+                labels: HashMap::new(),
                 persistence: None,
                 encryption: None,
             }),
@@ -174,11 +185,17 @@ impl KeyStore {
 
                 match File::open(&file_path) {
                     Ok(file) => {
-                        let reader = BufReader::new(file);
+                        let mut reader = BufReader::new(file);
+                        let mut buf = vec![];
+                        reader.read_to_end(&mut buf)?;
 
                         // Existing cleartext JSON keystore
-                        let persisted_key_info: HashMap<String, PersistentKeyInfo> =
-                            serde_json::from_reader(reader)
+                        let (persisted_key_info, labels): (HashMap<String, PersistentKeyInfo>, _) =
+                            serde_json::from_slice::<LabelledKeys<_, _>>(&buf)
+                                .map(|persisted| (persisted.keys, persisted.labels))
+                                .or_else(|_| {
+                                    serde_json::from_slice(&buf).map(|keys| (keys, HashMap::new()))
+                                })
                                 .map_err(|e| {
                                     error!(
                                 "failed to deserialize keyfile, initializing new keystore at: {:?}",
@@ -203,6 +220,7 @@ impl KeyStore {
 
                         Ok(Self {
                             key_info,
+                            labels,
                             persistence: Some(PersistentKeyStore { file_path }),
                             encryption: None,
                         })
@@ -215,6 +233,7 @@ impl KeyStore {
                             );
                             Ok(Self {
                                 key_info: HashMap::new(),
+                                labels: HashMap::new(),
                                 persistence: Some(PersistentKeyStore { file_path }),
                                 encryption: None,
                             })
@@ -257,6 +276,7 @@ impl KeyStore {
                                 )?;
                             Ok(Self {
                                 key_info: HashMap::new(),
+                                labels: HashMap::new(),
                                 persistence: Some(PersistentKeyStore { file_path }),
                                 encryption: Some(EncryptedKeyStore {
                                     salt,
@@ -279,15 +299,23 @@ impl KeyStore {
                             let decrypted_data = EncryptedKeyStore::decrypt(&encryption_key, &data)
                                 .map_err(|error| Error::Other(error.to_string()))?;
 
-                            let key_info = serde_ipld_dagcbor::from_slice(&decrypted_data)
-                                .map_err(|e| {
-                                    error!("Failed to deserialize keyfile, initializing new");
-                                    e
-                                })
-                                .unwrap_or_default();
+                            let (key_info, labels) = serde_ipld_dagcbor::from_slice::<
+                                LabelledKeys<_, _>,
+                            >(&decrypted_data)
+                            .map(|persisted| (persisted.keys, persisted.labels))
+                            .or_else(|_| {
+                                serde_ipld_dagcbor::from_slice(&decrypted_data)
+                                    .map(|keys| (keys, HashMap::new()))
+                            })
+                            .map_err(|e| {
+                                error!("Failed to deserialize keyfile, initializing new");
+                                e
+                            })
+                            .unwrap_or_default();
 
                             Ok(Self {
                                 key_info,
+                                labels,
                                 persistence: Some(PersistentKeyStore { file_path }),
                                 encryption: Some(EncryptedKeyStore {
                                     salt,
@@ -307,6 +335,7 @@ impl KeyStore {
 
                         Ok(Self {
                             key_info: HashMap::new(),
+                            labels: HashMap::new(),
                             persistence: Some(PersistentKeyStore { file_path }),
                             encryption: Some(EncryptedKeyStore {
                                 salt,
@@ -338,7 +367,15 @@ impl KeyStore {
                 match &self.encryption {
                     Some(encrypted_keystore) => {
                         // Flush For EncryptedKeyStore
-                        let data = serde_ipld_dagcbor::to_vec(&self.key_info).map_err(|e| {
+                        let data = if self.labels.is_empty() {
+                            serde_ipld_dagcbor::to_vec(&self.key_info)
+                        } else {
+                            serde_ipld_dagcbor::to_vec(&LabelledKeys {
+                                keys: &self.key_info,
+                                labels: &self.labels,
+                            })
+                        }
+                        .map_err(|e| {
                             Error::Other(format!("failed to serialize and write key info: {e}"))
                         })?;
 
@@ -363,7 +400,18 @@ impl KeyStore {
                         }
 
                         // Flush for PersistentKeyStore
-                        serde_json::to_writer_pretty(writer, &key_info).map_err(|e| {
+                        if self.labels.is_empty() {
+                            serde_json::to_writer_pretty(writer, &key_info)
+                        } else {
+                            serde_json::to_writer_pretty(
+                                writer,
+                                &LabelledKeys {
+                                    keys: &key_info,
+                                    labels: &self.labels,
+                                },
+                            )
+                        }
+                        .map_err(|e| {
                             Error::Other(format!("failed to serialize and write key info: {e}"))
                         })?;
 
@@ -378,6 +426,11 @@ impl KeyStore {
         }
     }
 
+    /// Returns whether the keys are persisted to disk without encryption.
+    pub fn is_persisted_in_plaintext(&self) -> bool {
+        self.persistence.is_some() && self.encryption.is_none()
+    }
+
     /// Return all of the keys that are stored in the `KeyStore`
     pub fn list(&self) -> Vec<String> {
         self.key_info.keys().cloned().collect()
@@ -412,6 +465,33 @@ impl KeyStore {
 
         Ok(key_out)
     }
+
+    /// Return all of the labels that are stored in the `KeyStore`, indexed by address
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    /// Return the label of an address, if any
+    pub fn get_label(&self, addr: &str) -> Option<&str> {
+        self.labels.get(addr).map(String::as_str)
+    }
+
+    /// Set the label of an address, or remove it if the label is empty
+    pub fn set_label(&mut self, addr: String, label: String) -> anyhow::Result<()> {
+        if label.is_empty() {
+            if self.labels.remove(&addr).is_none() {
+                return Ok(());
+            }
+        } else {
+            self.labels.insert(addr, label);
+        }
+
+        if self.persistence.is_some() {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
 }
 
 impl EncryptedKeyStore {
@@ -594,6 +674,30 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn labels_are_persisted_with_keys() -> Result<()> {
+        let keystore_location = tempfile::tempdir()?.into_path();
+        let config =
+            || KeyStoreConfig::Encrypted(keystore_location.clone(), PASSPHRASE.to_string());
+        let mut ks = KeyStore::new(config())?;
+        let key = wallet::generate_key(SignatureType::BLS)?;
+        let addr = key.address.to_string();
+        ks.put(format!("wallet-{addr}"), key.key_info)?;
+        ks.set_label(addr.clone(), "savings".to_owned())?;
+        ensure!(ks.list().len() == 1);
+        ensure!(fs::read_dir(&keystore_location)?.count() == 1);
+
+        let mut ks_read = KeyStore::new(config())?;
+        ensure!(ks == ks_read);
+        ensure!(ks_read.get_label(&addr) == Some("savings"));
+
+        ks_read.set_label(addr.clone(), String::new())?;
+        let ks_read = KeyStore::new(config())?;
+        ensure!(ks_read.labels().is_empty());
+        ensure!(ks_read.list().len() == 1);
+        Ok(())
+    }
+
     impl quickcheck::Arbitrary for KeyInfo {
         fn arbitrary(g: &mut quickcheck::Gen) -> Self {
             let sigtype = g
@@ -3,10 +3,12 @@
 
 mod errors;
 mod keystore;
+mod mnemonic;
 mod wallet;
 mod wallet_helpers;
 
 pub use errors::*;
 pub use keystore::*;
+pub use mnemonic::*;
 pub use wallet::*;
 pub use wallet_helpers::*;
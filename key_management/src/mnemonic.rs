@@ -0,0 +1,217 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Mnemonic based wallets. A [BIP39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki)
+//! phrase is turned into a seed from which keys are deterministically derived:
+//! - secp256k1 keys along the Filecoin `m/44'/461'/0'/0/<index>` path, following
+//!   [BIP32](https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki) and
+//!   [BIP44](https://github.com/bitcoin/bips/blob/master/bip-0044.mediawiki);
+//! - BLS keys along the `m/12381/461/0/<index>` path, following
+//!   [EIP-2333](https://eips.ethereum.org/EIPS/eip-2333).
+
+use super::errors::Error;
+use super::wallet::Key;
+use super::KeyInfo;
+use bip39::Mnemonic;
+use fvm_shared::crypto::signature::SignatureType;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use libsecp256k1::{PublicKey as SecpPublic, SecretKey as SecpPrivate};
+use num_bigint::BigUint;
+use once_cell::sync::Lazy;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use std::convert::TryFrom;
+
+/// Name of the `KeyStore` entry holding the wallet seed.
+pub const SEED_KEY: &str = "mnemonic-seed";
+
+/// Filecoin coin type, as registered in
+/// [SLIP-0044](https://github.com/satoshilabs/slips/blob/master/slip-0044.md).
+const FILECOIN_COIN_TYPE: u32 = 461;
+
+const HARDENED: u32 = 1 << 31;
+
+/// Order of the BLS12-381 scalar field.
+static BLS_CURVE_ORDER: Lazy<BigUint> = Lazy::new(|| {
+    BigUint::parse_bytes(
+        b"73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001",
+        16,
+    )
+    .expect("valid curve order")
+});
+
+/// Returns a new 24 words mnemonic phrase.
+pub fn generate_mnemonic() -> Result<String, Error> {
+    let mut entropy = [0u8; 32];
+    OsRng.fill_bytes(&mut entropy);
+    let mnemonic = Mnemonic::from_entropy(&entropy).map_err(|e| Error::Other(e.to_string()))?;
+    Ok(mnemonic.to_string())
+}
+
+/// Returns the seed of a mnemonic phrase, without passphrase.
+pub fn mnemonic_to_seed(phrase: &str) -> Result<Vec<u8>, Error> {
+    let normalized = phrase
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mnemonic =
+        Mnemonic::parse_normalized(&normalized).map_err(|e| Error::Other(e.to_string()))?;
+    Ok(mnemonic.to_seed_normalized("").to_vec())
+}
+
+/// Derives the key of the given type at `index` from a wallet seed.
+pub fn derive_key(seed: &[u8], sig_type: SignatureType, index: u32) -> Result<Key, Error> {
+    let private_key = match sig_type {
+        SignatureType::Secp256k1 => derive_secp256k1(
+            seed,
+            &[
+                44 | HARDENED,
+                FILECOIN_COIN_TYPE | HARDENED,
+                HARDENED,
+                0,
+                index,
+            ],
+        )?,
+        SignatureType::BLS => derive_bls(seed, &[12381, FILECOIN_COIN_TYPE, 0, index])?,
+    };
+    Key::try_from(KeyInfo::new(sig_type, private_key))
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for d in data {
+        mac.update(d);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// Derives a secp256k1 private key following BIP32.
+fn derive_secp256k1(seed: &[u8], path: &[u32]) -> Result<Vec<u8>, Error> {
+    let i = hmac_sha512(b"Bitcoin seed", &[seed]);
+    let mut key = SecpPrivate::parse_slice(&i[..32]).map_err(|e| Error::Other(e.to_string()))?;
+    let mut chain_code = i[32..].to_vec();
+
+    for index in path {
+        let i = if index & HARDENED != 0 {
+            hmac_sha512(&chain_code, &[&[0], &key.serialize(), &index.to_be_bytes()])
+        } else {
+            let public_key = SecpPublic::from_secret_key(&key).serialize_compressed();
+            hmac_sha512(&chain_code, &[&public_key, &index.to_be_bytes()])
+        };
+        let tweak = SecpPrivate::parse_slice(&i[..32]).map_err(|e| Error::Other(e.to_string()))?;
+        key.tweak_add_assign(&tweak)
+            .map_err(|e| Error::Other(e.to_string()))?;
+        chain_code = i[32..].to_vec();
+    }
+
+    Ok(key.serialize().to_vec())
+}
+
+/// Derives a BLS private key following EIP-2333. The key is serialized in little-endian, as
+/// expected by `bls_signatures`.
+fn derive_bls(seed: &[u8], path: &[u32]) -> Result<Vec<u8>, Error> {
+    if seed.len() < 32 {
+        return Err(Error::Other("Seed must be at least 32 bytes".to_string()));
+    }
+    let mut key = hkdf_mod_r(seed);
+    for index in path {
+        key = hkdf_mod_r(&parent_sk_to_lamport_pk(&key, *index));
+    }
+
+    let mut bytes = key.to_bytes_le();
+    bytes.resize(32, 0);
+    Ok(bytes)
+}
+
+fn hkdf_mod_r(ikm: &[u8]) -> BigUint {
+    let mut salt = b"BLS-SIG-KEYGEN-SALT-".to_vec();
+    let mut input = ikm.to_vec();
+    input.push(0);
+    loop {
+        salt = Sha256::digest(&salt).to_vec();
+        let mut okm = [0u8; 48];
+        Hkdf::<Sha256>::new(Some(&salt), &input)
+            .expand(&48u16.to_be_bytes(), &mut okm)
+            .expect("valid HKDF output length");
+        let key = BigUint::from_bytes_be(&okm) % &*BLS_CURVE_ORDER;
+        if key != BigUint::default() {
+            return key;
+        }
+    }
+}
+
+fn ikm_to_lamport_sk(ikm: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut okm = vec![0u8; 255 * 32];
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(&[], &mut okm)
+        .expect("valid HKDF output length");
+    okm
+}
+
+fn parent_sk_to_lamport_pk(parent_sk: &BigUint, index: u32) -> Vec<u8> {
+    let salt = index.to_be_bytes();
+    let mut ikm = parent_sk.to_bytes_be();
+    while ikm.len() < 32 {
+        ikm.insert(0, 0);
+    }
+    let not_ikm: Vec<u8> = ikm.iter().map(|b| !b).collect();
+
+    let mut lamport_pk = Sha256::new();
+    for lamport_sk in [
+        ikm_to_lamport_sk(&ikm, &salt),
+        ikm_to_lamport_sk(&not_ikm, &salt),
+    ] {
+        for chunk in lamport_sk.chunks(32) {
+            lamport_pk.update(Sha256::digest(chunk));
+        }
+    }
+    lamport_pk.finalize().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bip32_test_vector() {
+        // Test vector 1 of BIP32
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        assert_eq!(
+            hex::encode(derive_secp256k1(&seed, &[]).unwrap()),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        assert_eq!(
+            hex::encode(derive_secp256k1(&seed, &[HARDENED]).unwrap()),
+            "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"
+        );
+    }
+
+    #[test]
+    fn eip2333_test_vector() {
+        // Test case 0 of EIP-2333
+        let seed = hex::decode("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04").unwrap();
+        let master_sk = hkdf_mod_r(&seed);
+        assert_eq!(
+            master_sk.to_string(),
+            "6083874454709270928345386274498605044986640685124978867557563392430687146096"
+        );
+        assert_eq!(
+            hkdf_mod_r(&parent_sk_to_lamport_pk(&master_sk, 0)).to_string(),
+            "20397789859736650942317412262472558107875392172444076792671091975210932703118"
+        );
+    }
+
+    #[test]
+    fn derivation_is_deterministic() {
+        let phrase = generate_mnemonic().unwrap();
+        let seed = mnemonic_to_seed(&phrase).unwrap();
+        for sig_type in [SignatureType::Secp256k1, SignatureType::BLS] {
+            let first = derive_key(&seed, sig_type, 0).unwrap();
+            assert_eq!(first, derive_key(&seed, sig_type, 0).unwrap());
+            assert_ne!(first, derive_key(&seed, sig_type, 1).unwrap());
+        }
+    }
+}
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use super::errors::Error;
-use super::{wallet_helpers, KeyInfo, KeyStore};
+use super::{derive_key, mnemonic_to_seed, wallet_helpers, KeyInfo, KeyStore, SEED_KEY};
 use ahash::{HashMap, HashMapExt};
 use fvm_shared::address::Address;
 use fvm_shared::crypto::signature::{Signature, SignatureType};
@@ -143,6 +143,64 @@ impl Wallet {
         Ok(key.address)
     }
 
+    /// Store the seed of a mnemonic phrase, from which keys are then derived with
+    /// [`Wallet::derive_addr`]. The seed is only stored in encrypted or in-memory key stores,
+    /// and cannot be replaced once set.
+    pub fn set_seed_from_mnemonic(&mut self, phrase: &str) -> Result<(), Error> {
+        if self.keystore.is_persisted_in_plaintext() {
+            return Err(Error::Other(
+                "Mnemonic seeds can only be stored in an encrypted keystore".to_string(),
+            ));
+        }
+        let seed = mnemonic_to_seed(phrase)?;
+        // The key type is a placeholder, the seed is not a private key
+        self.keystore
+            .put(SEED_KEY.to_string(), KeyInfo::new(SignatureType::BLS, seed))
+    }
+
+    /// Return whether or not a mnemonic seed is stored in the wallet
+    pub fn has_seed(&self) -> bool {
+        self.keystore.get(SEED_KEY).is_ok()
+    }
+
+    /// Derive the address at `index` of the given `SignatureType` from the wallet seed and add
+    /// its key to the wallet. Deriving an address twice returns the same address.
+    pub fn derive_addr(&mut self, typ: SignatureType, index: u32) -> Result<Address, Error> {
+        let seed = self
+            .keystore
+            .get(SEED_KEY)
+            .map_err(|_| Error::Other("No mnemonic seed found in the keystore".to_string()))?;
+        let key = derive_key(seed.private_key(), typ, index)?;
+        let addr = format!("wallet-{}", key.address);
+        if self.keystore.get(&addr).is_err() {
+            self.keystore.put(addr, key.key_info.clone())?;
+        }
+        if self.keystore.get("default").is_err() {
+            self.keystore
+                .put("default".to_string(), key.key_info.clone())?;
+        }
+        self.keys.insert(key.address, key.clone());
+
+        Ok(key.address)
+    }
+
+    /// Remove the key resolved by the given address from the wallet, along with its label. The
+    /// default address is unset if it was the deleted one.
+    pub fn delete(&mut self, addr: &Address) -> anyhow::Result<()> {
+        self.keys.remove(addr);
+        delete_key(addr, &mut self.keystore)
+    }
+
+    /// Set the label of an address, or remove it if the label is empty
+    pub fn set_label(&mut self, addr: &Address, label: &str) -> anyhow::Result<()> {
+        set_label(addr, label, &mut self.keystore)
+    }
+
+    /// Return the label of an address, if any
+    pub fn get_label(&self, addr: &Address) -> Option<String> {
+        get_label(addr, &self.keystore)
+    }
+
     /// Return whether or not the Wallet contains a key that is resolved by the supplied address
     pub fn has_key(&mut self, addr: &Address) -> bool {
         self.find_key(addr).is_ok()
@@ -221,6 +279,27 @@ pub fn import(key_info: KeyInfo, keystore: &mut KeyStore) -> anyhow::Result<Addr
     Ok(k.address)
 }
 
+/// Remove the key of the given address and its label from `KeyStore`. The default address is
+/// unset if it was the deleted one.
+pub fn delete_key(addr: &Address, keystore: &mut KeyStore) -> anyhow::Result<()> {
+    let key_info = keystore.remove(format!("wallet-{addr}"))?;
+    if keystore.get("default").ok().as_ref() == Some(&key_info) {
+        keystore.remove("default".to_string())?;
+    }
+    keystore.set_label(addr.to_string(), String::new())
+}
+
+/// Set the label of an address in `KeyStore`, or remove it if the label is empty
+pub fn set_label(addr: &Address, label: &str, keystore: &mut KeyStore) -> anyhow::Result<()> {
+    find_key(addr, keystore)?;
+    keystore.set_label(addr.to_string(), label.to_owned())
+}
+
+/// Return the label of an address in `KeyStore`, if any
+pub fn get_label(addr: &Address, keystore: &KeyStore) -> Option<String> {
+    keystore.get_label(&addr.to_string()).map(str::to_owned)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -406,6 +485,31 @@ mod tests {
         assert_eq!(wallet.get_default().unwrap(), test_addr);
     }
 
+    #[test]
+    fn delete_and_label() {
+        let mut wallet = Wallet::new(KeyStore::new(KeyStoreConfig::Memory).unwrap());
+        let addr = wallet.generate_addr(SignatureType::Secp256k1).unwrap();
+        assert_eq!(wallet.get_default().unwrap(), addr);
+
+        wallet.set_label(&addr, "savings").unwrap();
+        assert_eq!(wallet.get_label(&addr), Some("savings".to_string()));
+        wallet.set_label(&addr, "cold").unwrap();
+        assert_eq!(wallet.get_label(&addr), Some("cold".to_string()));
+        assert_eq!(wallet.list_addrs().unwrap(), vec![addr]);
+        // Labels aren't listed as keys
+        assert!(wallet
+            .keystore
+            .list()
+            .iter()
+            .all(|k| k.starts_with("wallet-") || k == "default"));
+
+        wallet.delete(&addr).unwrap();
+        assert!(!wallet.has_key(&addr));
+        assert!(wallet.get_default().is_err());
+        assert_eq!(wallet.get_label(&addr), None);
+        assert!(wallet.set_label(&addr, "gone").is_err());
+    }
+
     #[test]
     fn secp_verify() {
         let secp_priv_key = generate(SignatureType::Secp256k1).unwrap();
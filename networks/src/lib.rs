@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use fil_actors_runtime::runtime::Policy;
-use forest_beacon::{BeaconPoint, BeaconSchedule, DrandBeacon, DrandConfig};
+use forest_beacon::{BeaconPoint, BeaconSchedule, DrandBeacon, DrandConfig, DrandNetwork};
+use forest_db::Store;
 use forest_shim::version::NetworkVersion;
 use fvm_shared::clock::{ChainEpoch, EPOCH_DURATION_SECONDS};
 
@@ -107,6 +108,12 @@ pub struct ChainConfig {
     pub height_infos: Vec<HeightInfo>,
     #[serde(default = "default_policy")]
     pub policy: Policy,
+    /// `Drand` mainnet endpoints, tried in order until one responds. The public endpoints are
+    /// used when empty.
+    pub drand_servers: Vec<String>,
+    /// Never contact `Drand`, beacon entries are only served from those verified in block
+    /// headers.
+    pub drand_offline: bool,
 }
 
 impl ChainConfig {
@@ -119,6 +126,8 @@ impl ChainConfig {
             block_delay_secs: EPOCH_DURATION_SECONDS as u64,
             height_infos: HEIGHT_INFOS.to_vec(),
             policy: Policy::calibnet(),
+            drand_servers: Vec::new(),
+            drand_offline: false,
         }
     }
 
@@ -133,23 +142,42 @@ impl ChainConfig {
         From::from(height)
     }
 
-    pub fn get_beacon_schedule(
+    /// Returns the beacon schedule of the network. Verified beacon entries are cached in the
+    /// given store.
+    pub fn get_beacon_schedule<DB>(
         &self,
         genesis_ts: u64,
-    ) -> Result<BeaconSchedule<DrandBeacon>, anyhow::Error> {
+        db: DB,
+    ) -> Result<BeaconSchedule<DrandBeacon>, anyhow::Error>
+    where
+        DB: Store + Clone + Send + Sync + 'static,
+    {
         let ds_iter = if self.name == "calibnet" {
             calibnet::DRAND_SCHEDULE.iter()
         } else {
             mainnet::DRAND_SCHEDULE.iter()
         };
+        let drand_servers: Vec<&str> = self.drand_servers.iter().map(String::as_str).collect();
         let mut points = BeaconSchedule::with_capacity(ds_iter.len());
         for dc in ds_iter {
+            let servers =
+                if dc.config.network_type == DrandNetwork::Mainnet && !drand_servers.is_empty() {
+                    drand_servers.as_slice()
+                } else {
+                    dc.config.servers
+                };
+            let config = DrandConfig {
+                servers,
+                offline: self.drand_offline,
+                ..dc.config.clone()
+            };
             points.0.push(BeaconPoint {
                 height: dc.height,
                 beacon: Arc::new(DrandBeacon::new(
                     genesis_ts,
                     self.block_delay_secs,
-                    dc.config,
+                    &config,
+                    db.clone(),
                 )?),
             });
         }
@@ -183,6 +211,8 @@ impl Default for ChainConfig {
             block_delay_secs: EPOCH_DURATION_SECONDS as u64,
             height_infos: HEIGHT_INFOS.to_vec(),
             policy: Policy::mainnet(),
+            drand_servers: Vec::new(),
+            drand_offline: false,
         }
     }
 }
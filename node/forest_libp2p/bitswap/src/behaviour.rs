@@ -35,6 +35,24 @@ impl BitswapBehaviour {
             .send_request(peer, vec![BitswapMessage::Request(request)])
     }
 
+    /// Sends a batch of wantlist entries to `peer` in a single message.
+    pub fn send_requests(&mut self, peer: &PeerId, requests: Vec<BitswapRequest>) -> RequestId {
+        for request in &requests {
+            if request.cancel {
+                metrics::message_counter_outbound_request_cancel().inc();
+            } else {
+                match request.ty {
+                    RequestType::Have => metrics::message_counter_outbound_request_have().inc(),
+                    RequestType::Block => metrics::message_counter_outbound_request_block().inc(),
+                }
+            }
+        }
+        self.inner.send_request(
+            peer,
+            requests.into_iter().map(BitswapMessage::Request).collect(),
+        )
+    }
+
     pub fn send_response(&mut self, peer: &PeerId, response: (Cid, BitswapResponse)) -> RequestId {
         match response.1 {
             BitswapResponse::Have(..) => metrics::message_counter_outbound_response_have().inc(),
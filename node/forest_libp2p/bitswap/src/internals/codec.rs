@@ -29,10 +29,6 @@ impl RequestResponseCodec for BitswapRequestResponseCodec {
         let pb_msg = proto::Message::decode(&data[..]).map_err(map_io_err)?;
         let mut parts = vec![];
         for entry in pb_msg.wantlist.unwrap_or_default().entries {
-            // TODO: Implement cancellation
-            if entry.cancel {
-                continue;
-            }
             let cid = Cid::try_from(entry.block).map_err(map_io_err)?;
             let ty = match entry.want_type {
                 ty if proto::message::wantlist::WantType::Have as i32 == ty => RequestType::Have,
@@ -46,6 +42,7 @@ impl RequestResponseCodec for BitswapRequestResponseCodec {
                 ty,
                 cid,
                 send_dont_have: entry.send_dont_have,
+                cancel: entry.cancel,
             }));
         }
         for payload in pb_msg.payload {
@@ -91,14 +88,7 @@ impl RequestResponseCodec for BitswapRequestResponseCodec {
     where
         T: AsyncWrite + Send + Unpin,
     {
-        // TODO: Low priority, batch sending is not supported in `libp2p-bitswap` either
-        // panic here means bug in public API of this crate
-        assert!(
-            messages.len() == 1,
-            "It's only supported to send a single message"
-        );
-
-        let bytes = messages[0].to_bytes()?;
+        let bytes = BitswapMessage::batch_to_bytes(&messages)?;
 
         metrics::outbound_stream_count().inc();
         metrics::outbound_bytes().inc_by(bytes.len() as _);
@@ -10,10 +10,12 @@ use std::sync::Arc;
 
 pub enum BitswapInboundResponseEvent {
     HaveBlock(PeerId, Cid),
+    DontHaveBlock(PeerId, Cid),
     DataBlock(PeerId, Cid, Vec<u8>),
 }
 
-// Note: This method performs db IO syncronously to reduce complexity
+// Note: This method performs db IO syncronously to reduce complexity. Inbound wants are queued
+// and served by `BitswapRequestManager::serve` after the event is handled.
 pub fn handle_event_impl<S: BitswapStore>(
     request_manager: &Arc<BitswapRequestManager>,
     bitswap: &mut BitswapBehaviour,
@@ -34,9 +36,7 @@ pub fn handle_event_impl<S: BitswapStore>(
                     for message in request {
                         match message {
                             BitswapMessage::Request(request) => {
-                                if let Some(response) = handle_inbound_request(store, &request) {
-                                    bitswap.send_response(&peer, (request.cid, response));
-                                }
+                                request_manager.server.lock().enqueue(peer, request);
                             }
                             BitswapMessage::Response(cid, response) => {
                                 if let Some(event) = match response {
@@ -48,7 +48,9 @@ pub fn handle_event_impl<S: BitswapStore>(
                                         } else {
                                             metrics::message_counter_inbound_response_have_no()
                                                 .inc();
-                                            None
+                                            Some(BitswapInboundResponseEvent::DontHaveBlock(
+                                                peer, cid,
+                                            ))
                                         }
                                     }
                                     BitswapResponse::Block(data) => {
@@ -64,11 +66,16 @@ pub fn handle_event_impl<S: BitswapStore>(
                         }
                     }
                 }
-                RequestResponseMessage::Response { .. } => {
-                    // Left empty by design
+                RequestResponseMessage::Response { request_id, .. } => {
+                    request_manager.server.lock().on_outbound_done(&request_id);
                 }
             }
         }
+        BitswapBehaviourEvent::Inner(RequestResponseEvent::OutboundFailure {
+            request_id, ..
+        }) => {
+            request_manager.server.lock().on_outbound_done(&request_id);
+        }
         BitswapBehaviourEvent::Inner(_) => {
             // TODO: trace
         }
@@ -77,10 +84,15 @@ pub fn handle_event_impl<S: BitswapStore>(
     Ok(())
 }
 
-fn handle_inbound_request<S: BitswapStore>(
+pub(crate) fn handle_inbound_request<S: BitswapStore>(
     store: &S,
     request: &BitswapRequest,
 ) -> Option<BitswapResponse> {
+    // Responses are sent right away, there is no pending want to remove
+    if request.cancel {
+        metrics::message_counter_inbound_request_cancel().inc();
+        return None;
+    }
     match request.ty {
         RequestType::Have => {
             metrics::message_counter_inbound_request_have().inc();
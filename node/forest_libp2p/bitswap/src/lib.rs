@@ -24,5 +24,9 @@ pub use metrics::register_metrics;
 mod request_manager;
 pub use request_manager::*;
 
+mod server;
+use server::BitswapServer;
+pub use server::{BitswapServerConfig, BitswapStats, PeerLedger};
+
 mod store;
 pub use store::*;
@@ -14,6 +14,7 @@ pub struct BitswapRequest {
     pub ty: RequestType,
     pub cid: Cid,
     pub send_dont_have: bool,
+    pub cancel: bool,
 }
 
 impl BitswapRequest {
@@ -22,6 +23,7 @@ impl BitswapRequest {
             ty: RequestType::Have,
             cid,
             send_dont_have: false,
+            cancel: false,
         }
     }
 
@@ -30,6 +32,17 @@ impl BitswapRequest {
             ty: RequestType::Block,
             cid,
             send_dont_have: false,
+            cancel: false,
+        }
+    }
+
+    /// Removes a previous want for `cid` from the wantlist of the remote peer.
+    pub fn new_cancel(cid: Cid) -> Self {
+        Self {
+            ty: RequestType::Block,
+            cid,
+            send_dont_have: false,
+            cancel: true,
         }
     }
 
@@ -53,14 +66,29 @@ pub enum BitswapMessage {
 
 impl BitswapMessage {
     pub fn to_bytes(&self) -> IOResult<Vec<u8>> {
+        Self::batch_to_bytes(std::slice::from_ref(self))
+    }
+
+    /// Encodes several messages into a single protobuf message, so that a whole wantlist update
+    /// is sent over one stream.
+    pub fn batch_to_bytes(messages: &[BitswapMessage]) -> IOResult<Vec<u8>> {
         let mut msg = proto::Message::default();
+        for message in messages {
+            message.append_to(&mut msg);
+        }
+        let mut bytes = Vec::with_capacity(msg.encoded_len());
+        msg.encode(&mut bytes).map_err(map_io_err)?;
+        Ok(bytes)
+    }
+
+    fn append_to(&self, msg: &mut proto::Message) {
         match self {
             Self::Request(BitswapRequest {
                 ty,
                 cid,
                 send_dont_have,
+                cancel,
             }) => {
-                let mut wantlist = proto::message::Wantlist::default();
                 let entry = proto::message::wantlist::Entry {
                     block: cid.to_bytes(),
                     want_type: match ty {
@@ -68,11 +96,13 @@ impl BitswapMessage {
                         RequestType::Block => proto::message::wantlist::WantType::Block,
                     } as _,
                     send_dont_have: *send_dont_have,
-                    cancel: false,
+                    cancel: *cancel,
                     priority: 1,
                 };
-                wantlist.entries.push(entry);
-                msg.wantlist = Some(wantlist);
+                msg.wantlist
+                    .get_or_insert_with(Default::default)
+                    .entries
+                    .push(entry);
             }
             Self::Response(cid, BitswapResponse::Have(have)) => {
                 let block_presence = proto::message::BlockPresence {
@@ -93,8 +123,5 @@ impl BitswapMessage {
                 msg.payload.push(payload);
             }
         }
-        let mut bytes = Vec::with_capacity(msg.encoded_len());
-        msg.encode(&mut bytes).map_err(map_io_err)?;
-        Ok(bytes)
     }
 }
@@ -4,7 +4,7 @@
 use lazy_static::lazy_static;
 use prometheus::{
     core::{AtomicU64, GenericCounter, GenericGauge, GenericGaugeVec},
-    Histogram, HistogramOpts, IntCounterVec, Opts, Registry,
+    Histogram, HistogramOpts, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
 };
 
 lazy_static! {
@@ -31,6 +31,22 @@ lazy_static! {
         buckets: vec![0.1, 0.5, 0.75, 1.0, 1.5, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0],
     })
     .expect("Infallible");
+    pub(crate) static ref BLOCK_LATENCY: Histogram = Histogram::with_opts(HistogramOpts {
+        common_opts: Opts::new(
+            "bitswap_block_latency",
+            "Duration between wanting a block and receiving it"
+        ),
+        buckets: vec![0.05, 0.1, 0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 3.0, 5.0, 10.0],
+    })
+    .expect("Infallible");
+    static ref WANTS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "bitswap_wants",
+            "Number of blocks being fetched by the session layer"
+        ),
+        &["type"],
+    )
+    .expect("Infallible");
 }
 
 pub fn register_metrics(registry: &Registry) -> anyhow::Result<()> {
@@ -38,6 +54,8 @@ pub fn register_metrics(registry: &Registry) -> anyhow::Result<()> {
     registry.register(Box::new(MESSAGE_COUNTER.clone()))?;
     registry.register(Box::new(CONTAINER_CAPACITIES.clone()))?;
     registry.register(Box::new(GET_BLOCK_TIME.clone()))?;
+    registry.register(Box::new(BLOCK_LATENCY.clone()))?;
+    registry.register(Box::new(WANTS.clone()))?;
 
     Ok(())
 }
@@ -74,6 +92,15 @@ pub(crate) fn message_counter_inbound_request_block() -> GenericCounter<AtomicU6
     MESSAGE_COUNTER.with_label_values(&["inbound_request_block"])
 }
 
+/// Wants dropped because the queue of the peer is full
+pub(crate) fn message_counter_inbound_request_dropped() -> GenericCounter<AtomicU64> {
+    MESSAGE_COUNTER.with_label_values(&["inbound_request_dropped"])
+}
+
+pub(crate) fn message_counter_inbound_request_cancel() -> GenericCounter<AtomicU64> {
+    MESSAGE_COUNTER.with_label_values(&["inbound_request_cancel"])
+}
+
 pub(crate) fn message_counter_outbound_request_block() -> GenericCounter<AtomicU64> {
     MESSAGE_COUNTER.with_label_values(&["outbound_request_block"])
 }
@@ -82,6 +109,10 @@ pub(crate) fn message_counter_outbound_request_have() -> GenericCounter<AtomicU6
     MESSAGE_COUNTER.with_label_values(&["outbound_request_have"])
 }
 
+pub(crate) fn message_counter_outbound_request_cancel() -> GenericCounter<AtomicU64> {
+    MESSAGE_COUNTER.with_label_values(&["outbound_request_cancel"])
+}
+
 pub(crate) fn message_counter_inbound_response_have_yes() -> GenericCounter<AtomicU64> {
     MESSAGE_COUNTER.with_label_values(&["inbound_response_have_yes"])
 }
@@ -107,6 +138,15 @@ pub(crate) fn message_counter_inbound_response_block_not_requested() -> GenericC
     MESSAGE_COUNTER.with_label_values(&["inbound_response_block_not_requested"])
 }
 
+/// Blocks received after their want was already satisfied, e.g. from a second peer
+pub(crate) fn message_counter_inbound_response_block_duplicate() -> GenericCounter<AtomicU64> {
+    MESSAGE_COUNTER.with_label_values(&["inbound_response_block_duplicate"])
+}
+
+pub(crate) fn message_counter_inbound_response_block_invalid() -> GenericCounter<AtomicU64> {
+    MESSAGE_COUNTER.with_label_values(&["inbound_response_block_invalid"])
+}
+
 pub(crate) fn message_counter_inbound_response_block_update_db_failure() -> GenericCounter<AtomicU64>
 {
     MESSAGE_COUNTER.with_label_values(&["inbound_response_block_update_db_failure"])
@@ -124,6 +164,18 @@ pub(crate) fn peer_container_capacity() -> GenericGauge<AtomicU64> {
     CONTAINER_CAPACITIES.with_label_values(&["peer_container_capacity"])
 }
 
-pub(crate) fn response_channel_container_capacity() -> GenericGauge<AtomicU64> {
-    CONTAINER_CAPACITIES.with_label_values(&["response_channel_container_capacity"])
+pub(crate) fn want_container_capacity() -> GenericGauge<AtomicU64> {
+    CONTAINER_CAPACITIES.with_label_values(&["want_container_capacity"])
+}
+
+pub(crate) fn active_wants() -> IntGauge {
+    WANTS.with_label_values(&["active"])
+}
+
+pub(crate) fn in_flight_block_requests() -> IntGauge {
+    WANTS.with_label_values(&["in_flight_block_requests"])
+}
+
+pub(crate) fn queued_inbound_wants() -> IntGauge {
+    WANTS.with_label_values(&["queued_inbound"])
 }
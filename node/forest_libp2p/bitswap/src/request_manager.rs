@@ -2,60 +2,322 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use crate::{event_handlers::*, *};
-use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
-use flume::TryRecvError;
+use ahash::{HashMap, HashSet, HashSetExt};
 use libipld::Block;
 use libipld::Cid;
 use libp2p::PeerId;
-use parking_lot::RwLock;
+use parking_lot::Mutex;
 use std::{
+    collections::VecDeque,
     sync::Arc,
     time::{Duration, Instant},
 };
 
-const BITSWAP_BLOCK_REQUEST_INTERVAL: Duration = Duration::from_millis(500);
+/// Interval at which pending wants are re-evaluated, i.e. stalled block requests are escalated
+/// to the next peer and unanswered want-haves are re-broadcast.
+const BITSWAP_WANT_TICK_INTERVAL: Duration = Duration::from_millis(500);
+/// Time after which want-haves that no peer answered with `HAVE` are sent again.
+const BITSWAP_REBROADCAST_INTERVAL: Duration = Duration::from_secs(3);
+/// Time after which an unanswered want-block is sent to the next peer having the block.
+const BITSWAP_BLOCK_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maximum number of unanswered want-block entries per peer, the others wait in its queue.
+const MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER: usize = 32;
 
-#[derive(Debug, Clone)]
-struct ResponseChannels {
-    block_have: flume::Sender<PeerId>,
-    block_saved: flume::Sender<()>,
+/// Outbound message queue of a peer, similar to the one of `go-bitswap`. Wantlist entries are
+/// accumulated and sent in a single message on [`BitswapRequestManager::flush`].
+#[derive(Debug, Default)]
+struct PeerQueue {
+    /// Entries to send in the next message
+    pending: Vec<BitswapRequest>,
+    /// Want-blocks waiting for in-flight ones to complete
+    waiting: VecDeque<Cid>,
+    /// Want-blocks not answered yet
+    in_flight: HashSet<Cid>,
+    /// Cids in the wantlist of the peer, that need to be cancelled once satisfied
+    sent: HashSet<Cid>,
 }
 
-// TODO: Use message queue like `go-bitswap`
+impl PeerQueue {
+    fn want_have(&mut self, cid: Cid) {
+        self.pending
+            .push(BitswapRequest::new_have(cid).send_dont_have(true));
+    }
+
+    fn want_block(&mut self, cid: Cid) {
+        if self.in_flight.len() < MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER {
+            if self.in_flight.insert(cid) {
+                metrics::in_flight_block_requests().inc();
+                self.pending
+                    .push(BitswapRequest::new_block(cid).send_dont_have(true));
+            }
+        } else if !self.waiting.contains(&cid) {
+            self.waiting.push_back(cid);
+        }
+    }
+
+    /// Marks the want for `cid` as answered, which makes room for a waiting want-block.
+    fn complete(&mut self, cid: &Cid) {
+        self.sent.remove(cid);
+        self.pending.retain(|request| request.cid != *cid);
+        self.waiting.retain(|waiting| waiting != cid);
+        if self.in_flight.remove(cid) {
+            metrics::in_flight_block_requests().dec();
+        }
+        while self.in_flight.len() < MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER {
+            match self.waiting.pop_front() {
+                Some(cid) => self.want_block(cid),
+                None => break,
+            }
+        }
+    }
+
+    /// Removes the want for `cid`, sending a cancel if the peer already received it.
+    fn cancel(&mut self, cid: &Cid) {
+        let sent = self.sent.contains(cid);
+        self.complete(cid);
+        if sent {
+            self.pending.push(BitswapRequest::new_cancel(*cid));
+        }
+    }
+
+    fn take(&mut self) -> Vec<BitswapRequest> {
+        let requests = std::mem::take(&mut self.pending);
+        for request in &requests {
+            if request.cancel {
+                self.sent.remove(&request.cid);
+            } else {
+                self.sent.insert(request.cid);
+            }
+        }
+        requests
+    }
+}
+
+impl Drop for PeerQueue {
+    fn drop(&mut self) {
+        metrics::in_flight_block_requests().sub(self.in_flight.len() as _);
+    }
+}
+
+#[derive(Debug)]
+struct Want {
+    started: Instant,
+    last_broadcast: Instant,
+    /// Sessions waiting for the block
+    waiters: Vec<flume::Sender<Cid>>,
+    /// Peers the want was sent to
+    peers: HashSet<PeerId>,
+    /// Peers that answered `HAVE`, in the order of their answers
+    have: VecDeque<PeerId>,
+    /// Peers that answered `DONT_HAVE`, or failed to send the block
+    dont_have: HashSet<PeerId>,
+    /// Peer the block is requested from, and when
+    block_request: Option<(PeerId, Instant)>,
+}
+
+impl Want {
+    fn new(waiter: flume::Sender<Cid>) -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            last_broadcast: now,
+            waiters: vec![waiter],
+            peers: HashSet::new(),
+            have: VecDeque::new(),
+            dont_have: HashSet::new(),
+            block_request: None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    peers: HashMap<PeerId, PeerQueue>,
+    wants: HashMap<Cid, Want>,
+}
+
+impl SessionState {
+    /// Sends a want-have to the peers that haven't answered yet.
+    fn broadcast_want_have(&mut self, cid: &Cid) {
+        if let Some(want) = self.wants.get_mut(cid) {
+            want.last_broadcast = Instant::now();
+            for (peer, queue) in self.peers.iter_mut() {
+                if !want.dont_have.contains(peer) && !want.have.contains(peer) {
+                    queue.want_have(*cid);
+                    want.peers.insert(*peer);
+                }
+            }
+        }
+    }
+
+    /// Escalates to a want-block to the first peer that answered `HAVE`, unless the block is
+    /// already requested.
+    fn request_block(&mut self, cid: &Cid) {
+        if let Some(want) = self.wants.get_mut(cid) {
+            if want.block_request.is_some() {
+                return;
+            }
+            while let Some(&peer) = want.have.front() {
+                if let Some(queue) = self.peers.get_mut(&peer) {
+                    queue.want_block(*cid);
+                    want.peers.insert(peer);
+                    want.block_request = Some((peer, Instant::now()));
+                    return;
+                }
+                // Disconnected
+                want.have.pop_front();
+            }
+        }
+    }
+
+    fn on_have(&mut self, cid: &Cid, peer: PeerId) {
+        if let Some(want) = self.wants.get_mut(cid) {
+            want.dont_have.remove(&peer);
+            if !want.have.contains(&peer) {
+                want.have.push_back(peer);
+            }
+            self.request_block(cid);
+        }
+    }
+
+    /// Forgets `peer` as a provider of `cid`, moving the block request to the next peer if
+    /// needed.
+    fn on_dont_have(&mut self, cid: &Cid, peer: &PeerId) {
+        if let Some(want) = self.wants.get_mut(cid) {
+            want.have.retain(|p| p != peer);
+            want.dont_have.insert(*peer);
+            if matches!(want.block_request, Some((p, _)) if p == *peer) {
+                want.block_request = None;
+            }
+            if let Some(queue) = self.peers.get_mut(peer) {
+                queue.cancel(cid);
+            }
+            self.request_block(cid);
+        }
+    }
+
+    fn tick(&mut self, cid: &Cid) {
+        let now = Instant::now();
+        let (stalled, rebroadcast) = match self.wants.get_mut(cid) {
+            Some(want) => {
+                let mut stalled = None;
+                if let Some((peer, at)) = want.block_request.as_mut() {
+                    let waiting = self
+                        .peers
+                        .get(peer)
+                        .map(|queue| queue.waiting.contains(cid))
+                        .unwrap_or_default();
+                    if waiting {
+                        // The in-flight limit of the peer is reached, that's not a stall
+                        *at = now;
+                    } else if now.duration_since(*at) > BITSWAP_BLOCK_REQUEST_TIMEOUT {
+                        stalled = Some(*peer);
+                    }
+                }
+                let rebroadcast = want.block_request.is_none()
+                    && want.have.is_empty()
+                    && now.duration_since(want.last_broadcast) > BITSWAP_REBROADCAST_INTERVAL;
+                if rebroadcast {
+                    // Peers may have received the block in the meantime
+                    want.dont_have.clear();
+                }
+                (stalled, rebroadcast)
+            }
+            None => return,
+        };
+        if let Some(peer) = stalled {
+            self.on_dont_have(cid, &peer);
+        }
+        if rebroadcast {
+            self.broadcast_want_have(cid);
+        }
+    }
+
+    fn remove_want(&mut self, cid: &Cid) -> Option<Want> {
+        let want = self.wants.remove(cid)?;
+        for peer in want.peers.iter() {
+            if let Some(queue) = self.peers.get_mut(peer) {
+                queue.cancel(cid);
+            }
+        }
+        Some(want)
+    }
+
+    fn update_metrics(&self) {
+        metrics::active_wants().set(self.wants.len() as _);
+        metrics::want_container_capacity().set(self.wants.capacity() as _);
+    }
+}
+
+/// Session layer of `bitswap`. Blocks are wanted with want-have entries broadcast to all the
+/// peers, then requested with a want-block from the peers that answered `HAVE`, one at a time.
+/// Wantlist entries are batched per peer, and cancelled once the block is received.
 #[derive(Debug)]
 pub struct BitswapRequestManager {
-    outbound_request_tx: flume::Sender<(PeerId, BitswapRequest)>,
-    peers: RwLock<HashSet<PeerId>>,
-    response_channels: RwLock<HashMap<Cid, ResponseChannels>>,
+    outbound_request_tx: flume::Sender<(PeerId, Vec<BitswapRequest>)>,
+    state: Mutex<SessionState>,
+    pub(crate) server: Mutex<BitswapServer>,
 }
 
 impl BitswapRequestManager {
-    pub fn new(outbound_request_tx: flume::Sender<(PeerId, BitswapRequest)>) -> Self {
+    pub fn new(
+        outbound_request_tx: flume::Sender<(PeerId, Vec<BitswapRequest>)>,
+        server_config: BitswapServerConfig,
+    ) -> Self {
         Self {
             outbound_request_tx,
-            peers: RwLock::new(HashSet::new()),
-            response_channels: RwLock::new(HashMap::new()),
+            state: Mutex::new(SessionState::default()),
+            server: Mutex::new(BitswapServer::new(server_config)),
         }
     }
 }
 
 impl BitswapRequestManager {
     pub fn on_peer_connected(&self, peer: PeerId) -> bool {
-        let mut peers = self.peers.write();
-        let success = peers.insert(peer);
-        if success {
+        {
+            let mut state = self.state.lock();
+            if state.peers.contains_key(&peer) {
+                return false;
+            }
+            // The new peer gets the current wantlist
+            let mut queue = PeerQueue::default();
+            let SessionState { wants, peers } = &mut *state;
+            for (cid, want) in wants.iter_mut() {
+                queue.want_have(*cid);
+                want.peers.insert(peer);
+            }
+            peers.insert(peer, queue);
             metrics::peer_container_capacity().set(peers.capacity() as _);
         }
-        success
+        self.flush();
+        true
     }
 
     pub fn on_peer_disconnected(&self, peer: &PeerId) -> bool {
-        let mut peers = self.peers.write();
-        let success = peers.remove(peer);
-        if success {
-            metrics::peer_container_capacity().set(peers.capacity() as _);
+        self.server.lock().on_peer_disconnected(peer);
+        {
+            let mut state = self.state.lock();
+            if state.peers.remove(peer).is_none() {
+                return false;
+            }
+            metrics::peer_container_capacity().set(state.peers.capacity() as _);
+
+            let mut orphans = vec![];
+            for (cid, want) in state.wants.iter_mut() {
+                want.peers.remove(peer);
+                want.have.retain(|p| p != peer);
+                want.dont_have.remove(peer);
+                if matches!(want.block_request, Some((p, _)) if p == *peer) {
+                    want.block_request = None;
+                    orphans.push(*cid);
+                }
+            }
+            for cid in orphans {
+                state.request_block(&cid);
+            }
         }
-        success
+        self.flush();
+        true
     }
 
     pub fn handle_event<S: BitswapStore>(
@@ -64,7 +326,50 @@ impl BitswapRequestManager {
         store: &S,
         event: BitswapBehaviourEvent,
     ) -> anyhow::Result<()> {
-        handle_event_impl(self, bitswap, store, event)
+        let result = handle_event_impl(self, bitswap, store, event);
+        // Escalations and cancels triggered by the responses
+        self.flush();
+        self.serve(bitswap, store);
+        result
+    }
+
+    /// Serves the queued wants of other peers. Called on every event, and periodically to resume
+    /// serving once the bandwidth limit allows it.
+    pub fn serve<S: BitswapStore>(&self, bitswap: &mut BitswapBehaviour, store: &S) {
+        self.server.lock().serve(bitswap, store);
+    }
+
+    /// Whether wants of other peers are waiting to be served.
+    pub fn has_queued_wants(&self) -> bool {
+        self.server.lock().queued_wants() > 0
+    }
+
+    pub fn stats(&self) -> BitswapStats {
+        let mut stats = self.server.lock().stats();
+        let state = self.state.lock();
+        stats.wantlist = state.wants.keys().copied().collect();
+        stats.peers = state.peers.keys().copied().collect();
+        stats
+    }
+
+    /// Sends the pending wantlist entries, one message per peer.
+    pub fn flush(&self) {
+        let batches: Vec<_> = {
+            let mut state = self.state.lock();
+            state
+                .peers
+                .iter_mut()
+                .filter_map(|(peer, queue)| {
+                    let requests = queue.take();
+                    (!requests.is_empty()).then_some((*peer, requests))
+                })
+                .collect()
+        };
+        for batch in batches {
+            if let Err(e) = self.outbound_request_tx.send(batch) {
+                warn!("{e}");
+            }
+        }
     }
 
     pub fn get_block(
@@ -74,22 +379,12 @@ impl BitswapRequestManager {
         timeout: Duration,
         responder: Option<flume::Sender<bool>>,
     ) {
-        let start = Instant::now();
         let timer = metrics::GET_BLOCK_TIME.start_timer();
         tokio::spawn(async move {
-            let mut success = store.contains(&cid).unwrap_or_default();
-            if !success {
-                let deadline = start.checked_add(timeout).expect("Infallible");
-                success = tokio::task::spawn_blocking(move || self.get_block_sync(cid, deadline))
-                    .await
-                    .unwrap_or_default();
-                // Spin check db when `get_block_sync` fails fast,
-                // which means there is other task actually processing the same `cid`
-                while !success && Instant::now() < deadline {
-                    tokio::time::sleep(BITSWAP_BLOCK_REQUEST_INTERVAL).await;
-                    success = store.contains(&cid).unwrap_or_default();
-                }
-            }
+            let success = self
+                .get_blocks(store.as_ref(), [cid], timeout)
+                .await
+                .is_empty();
 
             if success {
                 metrics::message_counter_get_block_success().inc();
@@ -107,59 +402,96 @@ impl BitswapRequestManager {
         });
     }
 
-    fn get_block_sync(&self, cid: Cid, deadline: Instant) -> bool {
-        // Fail fast here when the given `cid` is being processed by other tasks
-        if self.response_channels.read().contains_key(&cid) {
-            return false;
+    /// Fetches the blocks missing from the store in a single session, so that the wantlist
+    /// entries of all the blocks are batched in the same messages. Concurrent sessions wanting
+    /// the same block share its requests. Returns the CIDs that couldn't be fetched before the
+    /// timeout.
+    pub async fn get_blocks(
+        self: &Arc<Self>,
+        store: &impl BitswapStore,
+        cids: impl IntoIterator<Item = Cid>,
+        timeout: Duration,
+    ) -> Vec<Cid> {
+        let deadline = Instant::now().checked_add(timeout).expect("Infallible");
+        let mut remaining: HashSet<Cid> = cids
+            .into_iter()
+            .filter(|cid| !store.contains(cid).unwrap_or_default())
+            .collect();
+        if remaining.is_empty() {
+            return vec![];
         }
 
-        let (block_have_tx, block_have_rx) = flume::unbounded();
+        let wanted: Vec<Cid> = remaining.iter().copied().collect();
         let (block_saved_tx, block_saved_rx) = flume::unbounded();
-        let channels = ResponseChannels {
-            block_have: block_have_tx,
-            block_saved: block_saved_tx,
-        };
-        {
-            self.response_channels.write().insert(cid, channels);
-        }
+        self.want(&wanted, &block_saved_tx);
+        // Blocks saved between the first check and the want won't be notified
+        remaining.retain(|cid| !store.contains(cid).unwrap_or_default());
 
-        let have_request = BitswapRequest::new_have(cid).send_dont_have(false);
-        for &peer in self.peers.read().iter() {
-            if let Err(e) = self.outbound_request_tx.send((peer, have_request.clone())) {
-                warn!("{e}");
+        while !remaining.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
             }
-        }
-
-        let mut success = false;
-        let block_request = BitswapRequest::new_block(cid).send_dont_have(false);
-        while !success && Instant::now() < deadline {
-            match block_have_rx.try_recv() {
-                Ok(peer) => {
-                    _ = self.outbound_request_tx.send((peer, block_request.clone()));
-                }
-                Err(TryRecvError::Empty) => {}
-                Err(TryRecvError::Disconnected) => {
-                    break;
+            let wait = deadline.duration_since(now).min(BITSWAP_WANT_TICK_INTERVAL);
+            match tokio::time::timeout(wait, block_saved_rx.recv_async()).await {
+                Ok(Ok(cid)) => {
+                    remaining.remove(&cid);
                 }
+                Ok(Err(_)) => break,
+                Err(_) => self.tick(&remaining),
             }
+        }
 
-            if let Ok(()) = block_saved_rx.recv_timeout(BITSWAP_BLOCK_REQUEST_INTERVAL) {
-                success = true;
+        remaining.retain(|cid| !store.contains(cid).unwrap_or_default());
+        self.unwant(&wanted, &block_saved_tx);
+        remaining.into_iter().collect()
+    }
+
+    fn want(&self, cids: &[Cid], waiter: &flume::Sender<Cid>) {
+        {
+            let mut state = self.state.lock();
+            for cid in cids {
+                if let Some(want) = state.wants.get_mut(cid) {
+                    want.waiters.push(waiter.clone());
+                } else {
+                    state.wants.insert(*cid, Want::new(waiter.clone()));
+                    state.broadcast_want_have(cid);
+                }
             }
+            state.update_metrics();
         }
+        self.flush();
+    }
 
-        if !success {
-            success = block_saved_rx.recv_deadline(deadline).is_ok();
+    /// Removes the session from the waiters of `cids`, cancelling the wants nobody waits for.
+    fn unwant(&self, cids: &[Cid], waiter: &flume::Sender<Cid>) {
+        {
+            let mut state = self.state.lock();
+            for cid in cids {
+                let abandoned = match state.wants.get_mut(cid) {
+                    Some(want) => {
+                        want.waiters.retain(|w| !w.same_channel(waiter));
+                        want.waiters.is_empty()
+                    }
+                    None => false,
+                };
+                if abandoned {
+                    state.remove_want(cid);
+                }
+            }
+            state.update_metrics();
         }
+        self.flush();
+    }
 
-        // Cleanup
+    fn tick(&self, cids: &HashSet<Cid>) {
         {
-            let mut response_channels = self.response_channels.write();
-            response_channels.remove(&cid);
-            metrics::response_channel_container_capacity().set(response_channels.capacity() as _);
+            let mut state = self.state.lock();
+            for cid in cids {
+                state.tick(cid);
+            }
         }
-
-        success
+        self.flush();
     }
 
     pub(crate) fn on_inbound_response_event<S: BitswapStore>(
@@ -169,46 +501,349 @@ impl BitswapRequestManager {
     ) {
         use BitswapInboundResponseEvent::*;
         match response {
-            HaveBlock(peer, cid) => {
-                if let Some(chans) = self.response_channels.read().get(&cid) {
-                    _ = chans.block_have.send(peer);
-                }
-            }
-            DataBlock(_peer, cid, data) => {
-                if let Some(chans) = self.response_channels.read().get(&cid) {
-                    if let Ok(true) = store.contains(&cid) {
-                        // Avoid duplicate writes, still notify the receiver
-                        metrics::message_counter_inbound_response_block_already_exists_in_db()
-                            .inc();
-                        _ = chans.block_saved.send(());
-                        _ = chans.block_saved.send(());
+            HaveBlock(peer, cid) => self.state.lock().on_have(&cid, peer),
+            DontHaveBlock(peer, cid) => self.state.lock().on_dont_have(&cid, &peer),
+            DataBlock(peer, cid, data) => {
+                let size = data.len();
+                let duplicate = store.contains(&cid).unwrap_or_default();
+                if !self.state.lock().wants.contains_key(&cid) {
+                    if duplicate {
+                        metrics::message_counter_inbound_response_block_duplicate().inc();
                     } else {
-                        match Block::new(cid, data) {
-                            Ok(block) => match store.insert(&block) {
-                                Ok(()) => {
-                                    metrics::message_counter_inbound_response_block_update_db()
-                                        .inc();
-                                    _ = chans.block_saved.send(());
-                                }
-                                Err(e) => {
-                                    metrics::message_counter_inbound_response_block_update_db_failure()
-                                    .inc();
-                                    warn!(
-                                        "Failed to update db: {e}, cid: {cid}, data: {:?}",
-                                        block.data()
-                                    );
-                                }
-                            },
+                        metrics::message_counter_inbound_response_block_not_requested().inc();
+                    }
+                    return;
+                }
+
+                if duplicate {
+                    // Avoid duplicate writes, still notify the waiters
+                    metrics::message_counter_inbound_response_block_already_exists_in_db().inc();
+                } else {
+                    match Block::new(cid, data) {
+                        Ok(block) => match store.insert(&block) {
+                            Ok(()) => {
+                                metrics::message_counter_inbound_response_block_update_db().inc();
+                            }
                             Err(e) => {
-                                // TODO: log data
-                                warn!("Failed to construct block: {e}, cid: {cid}");
+                                metrics::message_counter_inbound_response_block_update_db_failure()
+                                    .inc();
+                                warn!(
+                                    "Failed to update db: {e}, cid: {cid}, data: {:?}",
+                                    block.data()
+                                );
+                                return;
                             }
+                        },
+                        Err(e) => {
+                            metrics::message_counter_inbound_response_block_invalid().inc();
+                            warn!("Failed to construct block from {peer}: {e}, cid: {cid}");
+                            self.state.lock().on_dont_have(&cid, &peer);
+                            return;
                         }
                     }
-                } else {
-                    metrics::message_counter_inbound_response_block_not_requested().inc();
+                }
+
+                // Only the valid blocks we asked for are credited to the peer
+                self.server.lock().on_block_received(peer, size, duplicate);
+
+                let want = {
+                    let mut state = self.state.lock();
+                    if let Some(queue) = state.peers.get_mut(&peer) {
+                        queue.complete(&cid);
+                    }
+                    let want = state.remove_want(&cid);
+                    state.update_metrics();
+                    want
+                };
+                if let Some(want) = want {
+                    metrics::BLOCK_LATENCY.observe(want.started.elapsed().as_secs_f64());
+                    for waiter in want.waiters {
+                        _ = waiter.send(cid);
+                    }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libipld::multihash::MultihashGeneric;
+    use multihash::{Code, MultihashDigest};
+
+    #[derive(Default)]
+    struct TestStore(Mutex<HashMap<Cid, Vec<u8>>>);
+
+    impl BitswapStore for TestStore {
+        type Params = libipld::DefaultParams;
+
+        fn contains(&self, cid: &Cid) -> anyhow::Result<bool> {
+            Ok(self.0.lock().contains_key(cid))
+        }
+
+        fn get(&self, cid: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(self.0.lock().get(cid).cloned())
+        }
+
+        fn insert(&self, block: &Block<Self::Params>) -> anyhow::Result<()> {
+            self.0.lock().insert(*block.cid(), block.data().to_vec());
+            Ok(())
+        }
+    }
+
+    /// Raw identity CID of `i`
+    fn cid(i: usize) -> Cid {
+        let digest = MultihashGeneric::wrap(0x0, &i.to_be_bytes()).unwrap();
+        Cid::new_v1(0x55, digest)
+    }
+
+    fn want_have(cid: Cid) -> BitswapRequest {
+        BitswapRequest::new_have(cid).send_dont_have(true)
+    }
+
+    fn want_block(cid: Cid) -> BitswapRequest {
+        BitswapRequest::new_block(cid).send_dont_have(true)
+    }
+
+    /// Session connected to `peers`, wanting `cids`, with the initial want-haves sent.
+    fn session(peers: &[PeerId], cids: &[Cid]) -> (SessionState, flume::Receiver<Cid>) {
+        let (tx, rx) = flume::unbounded();
+        let mut state = SessionState::default();
+        for peer in peers {
+            state.peers.insert(*peer, PeerQueue::default());
+        }
+        for cid in cids {
+            state.wants.insert(*cid, Want::new(tx.clone()));
+            state.broadcast_want_have(cid);
+        }
+        for peer in peers {
+            take(&mut state, peer);
+        }
+        (state, rx)
+    }
+
+    fn take(state: &mut SessionState, peer: &PeerId) -> Vec<BitswapRequest> {
+        state.peers.get_mut(peer).unwrap().take()
+    }
+
+    fn block_request(state: &SessionState, cid: &Cid) -> Option<PeerId> {
+        state.wants[cid].block_request.map(|(peer, _)| peer)
+    }
+
+    #[test]
+    fn want_haves_are_broadcast() {
+        let (a, b) = (PeerId::random(), PeerId::random());
+        let (mut state, _rx) = session(&[a, b], &[]);
+        state.wants.insert(cid(0), Want::new(flume::unbounded().0));
+        state.broadcast_want_have(&cid(0));
+        assert_eq!(take(&mut state, &a), vec![want_have(cid(0))]);
+        assert_eq!(take(&mut state, &b), vec![want_have(cid(0))]);
+    }
+
+    #[test]
+    fn have_escalates_to_want_block() {
+        let (a, b) = (PeerId::random(), PeerId::random());
+        let (mut state, _rx) = session(&[a, b], &[cid(0)]);
+
+        state.on_have(&cid(0), a);
+        assert_eq!(take(&mut state, &a), vec![want_block(cid(0))]);
+        assert_eq!(block_request(&state, &cid(0)), Some(a));
+
+        // The block is requested from one peer at a time
+        state.on_have(&cid(0), b);
+        assert!(take(&mut state, &b).is_empty());
+        assert_eq!(block_request(&state, &cid(0)), Some(a));
+        assert_eq!(state.wants[&cid(0)].have, [a, b]);
+    }
+
+    #[test]
+    fn dont_have_moves_to_next_peer() {
+        let (a, b) = (PeerId::random(), PeerId::random());
+        let (mut state, _rx) = session(&[a, b], &[cid(0)]);
+        state.on_have(&cid(0), a);
+        state.on_have(&cid(0), b);
+        take(&mut state, &a);
+
+        state.on_dont_have(&cid(0), &a);
+        assert_eq!(
+            take(&mut state, &a),
+            vec![BitswapRequest::new_cancel(cid(0))]
+        );
+        assert_eq!(take(&mut state, &b), vec![want_block(cid(0))]);
+        assert_eq!(block_request(&state, &cid(0)), Some(b));
+        assert!(state.wants[&cid(0)].dont_have.contains(&a));
+
+        // No peer left having the block
+        state.on_dont_have(&cid(0), &b);
+        assert_eq!(block_request(&state, &cid(0)), None);
+    }
+
+    #[test]
+    fn stalled_block_request_moves_to_next_peer() {
+        let (a, b) = (PeerId::random(), PeerId::random());
+        let (mut state, _rx) = session(&[a, b], &[cid(0)]);
+        state.on_have(&cid(0), a);
+        state.on_have(&cid(0), b);
+        take(&mut state, &a);
+
+        state.tick(&cid(0));
+        assert_eq!(block_request(&state, &cid(0)), Some(a));
+
+        let stalled_since = Instant::now()
+            .checked_sub(BITSWAP_BLOCK_REQUEST_TIMEOUT * 2)
+            .unwrap();
+        state.wants.get_mut(&cid(0)).unwrap().block_request = Some((a, stalled_since));
+        state.tick(&cid(0));
+        assert_eq!(block_request(&state, &cid(0)), Some(b));
+        assert_eq!(
+            take(&mut state, &a),
+            vec![BitswapRequest::new_cancel(cid(0))]
+        );
+        assert_eq!(take(&mut state, &b), vec![want_block(cid(0))]);
+    }
+
+    #[test]
+    fn waiting_block_request_is_not_stalled() {
+        let a = PeerId::random();
+        let cids: Vec<_> = (0..=MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER)
+            .map(cid)
+            .collect();
+        let (mut state, _rx) = session(&[a], &cids);
+        for cid in &cids {
+            state.on_have(cid, a);
+        }
+        let last = cids.last().unwrap();
+        assert!(state.peers[&a].waiting.contains(last));
+
+        let stalled_since = Instant::now()
+            .checked_sub(BITSWAP_BLOCK_REQUEST_TIMEOUT * 2)
+            .unwrap();
+        state.wants.get_mut(last).unwrap().block_request = Some((a, stalled_since));
+        state.tick(last);
+        assert_eq!(block_request(&state, last), Some(a));
+        assert!(!state.wants[last].dont_have.contains(&a));
+    }
+
+    #[test]
+    fn in_flight_cap_drains_waiting_queue() {
+        let mut queue = PeerQueue::default();
+        for i in 0..MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER + 2 {
+            queue.want_block(cid(i));
+        }
+        assert_eq!(queue.in_flight.len(), MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER);
+        assert_eq!(
+            queue.waiting,
+            [
+                cid(MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER),
+                cid(MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER + 1)
+            ]
+        );
+        assert_eq!(queue.take().len(), MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER);
+
+        // Waiting want-blocks are sent in order as the in-flight ones are answered
+        queue.complete(&cid(0));
+        assert_eq!(
+            queue.take(),
+            vec![want_block(cid(MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER))]
+        );
+        queue.complete(&cid(1));
+        assert_eq!(
+            queue.take(),
+            vec![want_block(cid(MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER + 1))]
+        );
+        assert!(queue.waiting.is_empty());
+        assert_eq!(queue.in_flight.len(), MAX_IN_FLIGHT_BLOCK_REQUESTS_PER_PEER);
+    }
+
+    #[test]
+    fn cancels_are_sent_only_for_sent_entries() {
+        let mut queue = PeerQueue::default();
+
+        // Never sent, the entry is just dropped
+        queue.want_have(cid(0));
+        queue.cancel(&cid(0));
+        assert!(queue.take().is_empty());
+
+        queue.want_have(cid(1));
+        assert_eq!(queue.take(), vec![want_have(cid(1))]);
+        queue.cancel(&cid(1));
+        assert_eq!(queue.take(), vec![BitswapRequest::new_cancel(cid(1))]);
+        assert!(queue.sent.is_empty());
+
+        // Already cancelled
+        queue.cancel(&cid(1));
+        assert!(queue.take().is_empty());
+    }
+
+    #[test]
+    fn disconnect_reroutes_orphaned_wants() {
+        let (tx, rx) = flume::unbounded();
+        let manager = BitswapRequestManager::new(tx, BitswapServerConfig::default());
+        let (a, b) = (PeerId::random(), PeerId::random());
+        assert!(manager.on_peer_connected(a));
+        assert!(manager.on_peer_connected(b));
+        assert!(!manager.on_peer_connected(a));
+
+        let (waiter, _block_saved_rx) = flume::unbounded();
+        manager.want(&[cid(0)], &waiter);
+        {
+            let mut state = manager.state.lock();
+            state.on_have(&cid(0), a);
+            state.on_have(&cid(0), b);
+        }
+        manager.flush();
+        rx.drain();
+
+        assert!(manager.on_peer_disconnected(&a));
+        assert!(!manager.on_peer_disconnected(&a));
+        {
+            let state = manager.state.lock();
+            assert_eq!(block_request(&state, &cid(0)), Some(b));
+            assert!(!state.wants[&cid(0)].peers.contains(&a));
+        }
+        let sent: Vec<_> = rx.drain().collect();
+        assert_eq!(sent, vec![(b, vec![want_block(cid(0))])]);
+    }
+
+    #[test]
+    fn blocks_not_matching_their_cid_are_not_stored() {
+        let (tx, _rx) = flume::unbounded();
+        let manager = BitswapRequestManager::new(tx, BitswapServerConfig::default());
+        let store = TestStore::default();
+        let (a, b) = (PeerId::random(), PeerId::random());
+        manager.on_peer_connected(a);
+        manager.on_peer_connected(b);
+
+        let data = b"block".to_vec();
+        let cid = Cid::new_v1(0x55, Code::Blake2b256.digest(&data));
+        let (waiter, block_saved_rx) = flume::unbounded();
+        manager.want(&[cid], &waiter);
+        manager.state.lock().on_have(&cid, a);
+        manager.state.lock().on_have(&cid, b);
+
+        manager.on_inbound_response_event(
+            &store,
+            BitswapInboundResponseEvent::DataBlock(a, cid, b"garbage".to_vec()),
+        );
+        assert!(!store.contains(&cid).unwrap());
+        assert!(block_saved_rx.is_empty());
+        {
+            let state = manager.state.lock();
+            assert!(state.wants[&cid].dont_have.contains(&a));
+            assert_eq!(block_request(&state, &cid), Some(b));
+        }
+
+        manager.on_inbound_response_event(
+            &store,
+            BitswapInboundResponseEvent::DataBlock(b, cid, data.clone()),
+        );
+        assert_eq!(store.get(&cid).unwrap(), Some(data));
+        assert_eq!(block_saved_rx.try_recv(), Ok(cid));
+        assert!(!manager.state.lock().wants.contains_key(&cid));
+        // Only the valid block is credited
+        let ledgers = manager.stats().ledgers;
+        assert_eq!(ledgers.len(), 1);
+        assert_eq!(ledgers[0].0, b);
+    }
+}
@@ -0,0 +1,401 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::{event_handlers::handle_inbound_request, *};
+use ahash::HashMap;
+use libp2p::{request_response::RequestId, PeerId};
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, num::NonZeroUsize, time::Instant};
+
+/// Maximum number of peer ledgers kept, the least recently active peers are forgotten first.
+const MAX_LEDGERS: usize = 4096;
+
+/// Configuration of the serving side of `bitswap`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct BitswapServerConfig {
+    /// Answer the wants of other peers.
+    pub enabled: bool,
+    /// Maximum number of bytes per second sent in responses, `0` for unlimited.
+    pub max_outbound_bytes_per_sec: u64,
+    /// Maximum number of responses being sent to a peer at the same time.
+    pub max_concurrent_responses_per_peer: usize,
+    /// Maximum number of wants queued per peer, further ones are dropped.
+    pub max_queued_wants_per_peer: usize,
+}
+
+impl Default for BitswapServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_outbound_bytes_per_sec: 0,
+            max_concurrent_responses_per_peer: 8,
+            max_queued_wants_per_peer: 1024,
+        }
+    }
+}
+
+/// Data exchanged with a peer, used to prioritize the peers that contribute the most.
+#[derive(Debug, Default, Clone)]
+pub struct PeerLedger {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub blocks_sent: u64,
+    pub blocks_received: u64,
+    /// Number of responses sent to the peer
+    pub exchanged: u64,
+}
+
+impl PeerLedger {
+    /// Ratio of the bytes sent to the peer over the bytes received from it. Peers with the lowest
+    /// ratio are served first.
+    pub fn debt_ratio(&self) -> f64 {
+        self.bytes_sent as f64 / (self.bytes_received as f64 + 1.0)
+    }
+}
+
+/// Snapshot of the `bitswap` activity, see [`BitswapRequestManager::stats`].
+#[derive(Debug, Default, Clone)]
+pub struct BitswapStats {
+    pub serving: bool,
+    pub blocks_received: u64,
+    pub data_received: u64,
+    pub blocks_sent: u64,
+    pub data_sent: u64,
+    pub dup_blocks_received: u64,
+    pub dup_data_received: u64,
+    /// Wants of other peers waiting to be served
+    pub queued_wants: usize,
+    /// Blocks being fetched
+    pub wantlist: Vec<Cid>,
+    pub peers: Vec<PeerId>,
+    pub ledgers: Vec<(PeerId, PeerLedger)>,
+}
+
+#[derive(Debug, Default)]
+struct PeerTasks {
+    wants: VecDeque<BitswapRequest>,
+    /// Responses sent and not acknowledged yet
+    active: usize,
+}
+
+/// Token bucket limiting the outbound bandwidth. The balance can go negative, so that a block
+/// larger than the per-second budget is still sent, and the following ones are delayed.
+#[derive(Debug)]
+struct Bandwidth {
+    bytes_per_sec: u64,
+    balance: i64,
+    refilled_at: Instant,
+}
+
+impl Bandwidth {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec,
+            balance: bytes_per_sec as _,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn is_limited(&self) -> bool {
+        self.bytes_per_sec > 0
+    }
+
+    fn has_budget(&mut self) -> bool {
+        if !self.is_limited() {
+            return true;
+        }
+        let now = Instant::now();
+        let refill = now.duration_since(self.refilled_at).as_secs_f64() * self.bytes_per_sec as f64;
+        if refill >= 1.0 {
+            self.balance = (self.balance + refill as i64).min(self.bytes_per_sec as _);
+            self.refilled_at = now;
+        }
+        self.balance > 0
+    }
+
+    fn consume(&mut self, bytes: usize) {
+        if self.is_limited() {
+            self.balance -= bytes as i64;
+        }
+    }
+}
+
+/// Serving side of `bitswap`. Inbound wants are queued per peer and served by [`Self::serve`],
+/// peers with the lowest debt ratio first, within the bandwidth and per-peer concurrency limits.
+#[derive(Debug)]
+pub(crate) struct BitswapServer {
+    config: BitswapServerConfig,
+    ledgers: LruCache<PeerId, PeerLedger>,
+    tasks: HashMap<PeerId, PeerTasks>,
+    in_flight: HashMap<RequestId, PeerId>,
+    bandwidth: Bandwidth,
+    stats: BitswapStats,
+}
+
+impl BitswapServer {
+    pub(crate) fn new(config: BitswapServerConfig) -> Self {
+        let bandwidth = Bandwidth::new(config.max_outbound_bytes_per_sec);
+        Self {
+            stats: BitswapStats {
+                serving: config.enabled,
+                ..Default::default()
+            },
+            config,
+            ledgers: LruCache::new(NonZeroUsize::new(MAX_LEDGERS).expect("Infallible")),
+            tasks: HashMap::default(),
+            in_flight: HashMap::default(),
+            bandwidth,
+        }
+    }
+
+    pub(crate) fn enqueue(&mut self, peer: PeerId, request: BitswapRequest) {
+        if !self.config.enabled {
+            return;
+        }
+        let tasks = self.tasks.entry(peer).or_default();
+        if request.cancel {
+            tasks.wants.retain(|want| want.cid != request.cid);
+        } else if tasks.wants.len() < self.config.max_queued_wants_per_peer {
+            tasks.wants.push_back(request);
+        } else {
+            metrics::message_counter_inbound_request_dropped().inc();
+        }
+    }
+
+    /// Sends the responses to the queued wants, as long as the limits allow it.
+    pub(crate) fn serve<S: BitswapStore>(&mut self, bitswap: &mut BitswapBehaviour, store: &S) {
+        while self.bandwidth.has_budget() {
+            let peer = match self.next_peer() {
+                Some(peer) => peer,
+                None => break,
+            };
+
+            let tasks = self.tasks.get_mut(&peer).expect("Infallible");
+            let request = tasks.wants.pop_front().expect("Infallible");
+            if let Some(response) = handle_inbound_request(store, &request) {
+                let size = match &response {
+                    BitswapResponse::Block(data) => data.len(),
+                    BitswapResponse::Have(_) => 0,
+                };
+                let request_id = bitswap.send_response(&peer, (request.cid, response));
+                tasks.active += 1;
+                self.in_flight.insert(request_id, peer);
+                self.bandwidth.consume(size);
+
+                let ledger = self.ledger(peer);
+                ledger.exchanged += 1;
+                if size > 0 {
+                    ledger.blocks_sent += 1;
+                    ledger.bytes_sent += size as u64;
+                    self.stats.blocks_sent += 1;
+                    self.stats.data_sent += size as u64;
+                }
+            }
+        }
+        metrics::queued_inbound_wants().set(self.queued_wants() as _);
+    }
+
+    /// Peer with the lowest debt ratio among the ones with queued wants and a free concurrency
+    /// slot.
+    fn next_peer(&self) -> Option<PeerId> {
+        let max_active = self.config.max_concurrent_responses_per_peer;
+        self.tasks
+            .iter()
+            .filter(|(_, tasks)| !tasks.wants.is_empty() && tasks.active < max_active)
+            .map(|(peer, _)| {
+                let debt_ratio = self.ledgers.peek(peer).map(PeerLedger::debt_ratio);
+                (*peer, debt_ratio.unwrap_or_default())
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(peer, _)| peer)
+    }
+
+    /// Frees the concurrency slot of the response, once it's sent or failed.
+    pub(crate) fn on_outbound_done(&mut self, request_id: &RequestId) {
+        if let Some(peer) = self.in_flight.remove(request_id) {
+            if let Some(tasks) = self.tasks.get_mut(&peer) {
+                tasks.active = tasks.active.saturating_sub(1);
+            }
+        }
+    }
+
+    pub(crate) fn on_block_received(&mut self, peer: PeerId, size: usize, duplicate: bool) {
+        let ledger = self.ledger(peer);
+        ledger.blocks_received += 1;
+        ledger.bytes_received += size as u64;
+        self.stats.blocks_received += 1;
+        self.stats.data_received += size as u64;
+        if duplicate {
+            self.stats.dup_blocks_received += 1;
+            self.stats.dup_data_received += size as u64;
+        }
+    }
+
+    fn ledger(&mut self, peer: PeerId) -> &mut PeerLedger {
+        if !self.ledgers.contains(&peer) {
+            self.ledgers.push(peer, PeerLedger::default());
+        }
+        self.ledgers.get_mut(&peer).expect("Infallible")
+    }
+
+    /// The ledger of the peer is kept, so that reconnecting doesn't reset its debt ratio.
+    pub(crate) fn on_peer_disconnected(&mut self, peer: &PeerId) {
+        self.tasks.remove(peer);
+        self.in_flight.retain(|_, p| p != peer);
+    }
+
+    pub(crate) fn queued_wants(&self) -> usize {
+        self.tasks.values().map(|tasks| tasks.wants.len()).sum()
+    }
+
+    pub(crate) fn stats(&self) -> BitswapStats {
+        BitswapStats {
+            queued_wants: self.queued_wants(),
+            ledgers: self
+                .ledgers
+                .iter()
+                .map(|(peer, ledger)| (*peer, ledger.clone()))
+                .collect(),
+            ..self.stats.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libipld::multihash::MultihashGeneric;
+    use std::time::Duration;
+
+    fn cid(i: usize) -> Cid {
+        let digest = MultihashGeneric::wrap(0x0, &i.to_be_bytes()).unwrap();
+        Cid::new_v1(0x55, digest)
+    }
+
+    fn ledger(bytes_sent: u64, bytes_received: u64) -> PeerLedger {
+        PeerLedger {
+            bytes_sent,
+            bytes_received,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unlimited_bandwidth() {
+        let mut bandwidth = Bandwidth::new(0);
+        bandwidth.consume(1 << 30);
+        assert!(bandwidth.has_budget());
+    }
+
+    #[test]
+    fn bandwidth_is_refilled_over_time() {
+        let mut bandwidth = Bandwidth::new(1000);
+        assert!(bandwidth.has_budget());
+
+        // A block larger than the budget is sent, the next ones wait
+        bandwidth.consume(1500);
+        assert!(!bandwidth.has_budget());
+        assert_eq!(bandwidth.balance, -500);
+
+        bandwidth.refilled_at = Instant::now().checked_sub(Duration::from_secs(1)).unwrap();
+        assert!(bandwidth.has_budget());
+        assert!((500..=501).contains(&bandwidth.balance));
+
+        // The balance never exceeds one second worth of bytes
+        bandwidth.refilled_at = Instant::now().checked_sub(Duration::from_secs(10)).unwrap();
+        assert!(bandwidth.has_budget());
+        assert_eq!(bandwidth.balance, 1000);
+    }
+
+    #[test]
+    fn lowest_debt_ratio_is_served_first() {
+        let mut server = BitswapServer::new(BitswapServerConfig::default());
+        let (a, b, c) = (PeerId::random(), PeerId::random(), PeerId::random());
+        assert_eq!(server.next_peer(), None);
+
+        server.enqueue(a, BitswapRequest::new_block(cid(0)));
+        server.enqueue(b, BitswapRequest::new_block(cid(0)));
+        server.ledgers.put(a, ledger(1000, 0));
+        server.ledgers.put(b, ledger(1000, 999));
+        server.ledgers.put(c, ledger(0, 1000));
+        // `c` has nothing queued
+        assert_eq!(server.next_peer(), Some(b));
+
+        server.ledgers.put(a, ledger(1000, 2000));
+        assert_eq!(server.next_peer(), Some(a));
+
+        // Peers without a ledger have a null debt ratio
+        server.ledgers.pop(&b);
+        assert_eq!(server.next_peer(), Some(b));
+    }
+
+    #[test]
+    fn concurrent_responses_are_capped_per_peer() {
+        let config = BitswapServerConfig {
+            max_concurrent_responses_per_peer: 2,
+            ..Default::default()
+        };
+        let mut server = BitswapServer::new(config);
+        let (a, b) = (PeerId::random(), PeerId::random());
+        server.enqueue(a, BitswapRequest::new_block(cid(0)));
+        server.enqueue(b, BitswapRequest::new_block(cid(0)));
+        server.ledgers.put(b, ledger(1000, 0));
+        assert_eq!(server.next_peer(), Some(a));
+
+        server.tasks.get_mut(&a).unwrap().active = 2;
+        assert_eq!(server.next_peer(), Some(b));
+        server.tasks.get_mut(&b).unwrap().active = 2;
+        assert_eq!(server.next_peer(), None);
+    }
+
+    #[test]
+    fn queued_wants_are_capped_per_peer() {
+        let config = BitswapServerConfig {
+            max_queued_wants_per_peer: 2,
+            ..Default::default()
+        };
+        let mut server = BitswapServer::new(config);
+        let (a, b) = (PeerId::random(), PeerId::random());
+        for i in 0..3 {
+            server.enqueue(a, BitswapRequest::new_block(cid(i)));
+        }
+        server.enqueue(b, BitswapRequest::new_have(cid(0)));
+        assert_eq!(server.queued_wants(), 3);
+        assert_eq!(server.tasks[&a].wants.len(), 2);
+
+        // Cancels remove the queued want, freeing a slot
+        server.enqueue(a, BitswapRequest::new_cancel(cid(0)));
+        server.enqueue(a, BitswapRequest::new_block(cid(2)));
+        let queued: Vec<_> = server.tasks[&a].wants.iter().map(|want| want.cid).collect();
+        assert_eq!(queued, [cid(1), cid(2)]);
+
+        server.on_peer_disconnected(&a);
+        assert_eq!(server.queued_wants(), 1);
+    }
+
+    #[test]
+    fn ledgers_survive_disconnects() {
+        let mut server = BitswapServer::new(BitswapServerConfig::default());
+        let a = PeerId::random();
+        server.on_block_received(a, 100, false);
+        server.on_peer_disconnected(&a);
+
+        let ledgers = server.stats().ledgers;
+        assert_eq!(ledgers.len(), 1);
+        assert_eq!(ledgers[0].1.bytes_received, 100);
+    }
+
+    #[test]
+    fn nothing_is_queued_when_disabled() {
+        let config = BitswapServerConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let mut server = BitswapServer::new(config);
+        server.enqueue(PeerId::random(), BitswapRequest::new_block(cid(0)));
+        assert_eq!(server.queued_wants(), 0);
+        assert!(!server.stats().serving);
+    }
+}
@@ -160,17 +160,17 @@ pub struct CompactedMessages {
 #[derive(Clone, Debug, PartialEq, Serialize_tuple, Deserialize_tuple, Default)]
 pub struct TipsetBundle {
     /// The blocks in the tipset.
-    pub blocks: Vec<BlockHeader>,
+    pub blocks: Arc<Vec<BlockHeader>>,
 
     /// Compressed messages format.
-    pub messages: Option<CompactedMessages>,
+    pub messages: Option<Arc<CompactedMessages>>,
 }
 
 impl TryFrom<TipsetBundle> for Tipset {
     type Error = String;
 
     fn try_from(tsb: TipsetBundle) -> Result<Self, Self::Error> {
-        Tipset::new(tsb.blocks).map_err(|e| e.to_string())
+        Tipset::new(unwrap_or_clone(tsb.blocks)).map_err(|e| e.to_string())
     }
 }
 
@@ -187,6 +187,7 @@ impl TryFrom<TipsetBundle> for CompactedMessages {
 
     fn try_from(tsb: TipsetBundle) -> Result<Self, Self::Error> {
         tsb.messages
+            .map(unwrap_or_clone)
             .ok_or_else(|| "Request contained no messages".to_string())
     }
 }
@@ -195,7 +196,7 @@ impl TryFrom<TipsetBundle> for FullTipset {
     type Error = String;
 
     fn try_from(tsb: TipsetBundle) -> Result<FullTipset, Self::Error> {
-        fts_from_bundle_parts(tsb.blocks, tsb.messages.as_ref())
+        fts_from_bundle_parts(unwrap_or_clone(tsb.blocks), tsb.messages.as_deref())
     }
 }
 
@@ -203,10 +204,15 @@ impl TryFrom<&TipsetBundle> for FullTipset {
     type Error = String;
 
     fn try_from(tsb: &TipsetBundle) -> Result<FullTipset, Self::Error> {
-        fts_from_bundle_parts(tsb.blocks.clone(), tsb.messages.as_ref())
+        fts_from_bundle_parts(tsb.blocks.to_vec(), tsb.messages.as_deref())
     }
 }
 
+/// Takes the value out of the [`Arc`], which is only copied if it's shared.
+fn unwrap_or_clone<T: Clone>(arc: Arc<T>) -> T {
+    Arc::try_unwrap(arc).unwrap_or_else(|arc| (*arc).clone())
+}
+
 /// Constructs a [`FullTipset`] from headers and compacted messages from a bundle.
 fn fts_from_bundle_parts(
     headers: Vec<BlockHeader>,
@@ -7,42 +7,206 @@ use super::{
 };
 use ahash::{HashMap, HashMapExt};
 use cid::Cid;
-use forest_blocks::{Tipset, TipsetKeys};
+use forest_blocks::{BlockHeader, Tipset, TipsetKeys};
 use forest_chain::{ChainStore, Error as ChainError};
 use forest_db::Store;
 use fvm_ipld_blockstore::Blockstore;
+use libp2p::PeerId;
 use log::debug;
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Window over which the requests of a peer are counted.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits of the serving side of `ChainExchange`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ChainExchangeServerConfig {
+    /// Maximum number of tipsets a request can ask for, longer requests are rejected.
+    pub max_request_len: u64,
+    /// Maximum number of requests served per peer and minute, `0` for unlimited.
+    pub max_requests_per_peer_per_minute: u32,
+    /// Size in bytes above which a response is cut short and returned as partial. The first
+    /// tipset is always returned.
+    pub max_response_bytes: usize,
+    /// Maximum size in bytes of the encoded headers and compacted messages kept in memory to
+    /// serve the following requests, `0` to disable the cache.
+    pub max_cache_bytes: usize,
+}
+
+impl Default for ChainExchangeServerConfig {
+    fn default() -> Self {
+        Self {
+            // Same as Lotus, which uses the fork length threshold.
+            max_request_len: 900,
+            max_requests_per_peer_per_minute: 120,
+            max_response_bytes: 32 * 1024 * 1024,
+            max_cache_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Headers and compacted messages of a tipset with their encoded size, shared by the responses
+/// including the tipset.
+#[derive(Clone)]
+struct CachedTipset {
+    blocks: Arc<Vec<BlockHeader>>,
+    blocks_size: usize,
+    messages: Option<(Arc<CompactedMessages>, usize)>,
+}
+
+impl CachedTipset {
+    fn size(&self) -> usize {
+        self.blocks_size + self.messages.as_ref().map_or(0, |(_, size)| *size)
+    }
+}
+
+/// Least recently used tipsets, bounded by their encoded size.
+struct TipsetCache {
+    entries: LruCache<TipsetKeys, CachedTipset>,
+    size: usize,
+    max_size: usize,
+}
+
+impl TipsetCache {
+    fn new(max_size: usize) -> Self {
+        Self {
+            entries: LruCache::unbounded(),
+            size: 0,
+            max_size,
+        }
+    }
+
+    fn get(&mut self, key: &TipsetKeys) -> Option<CachedTipset> {
+        self.entries.get(key).cloned()
+    }
+
+    fn put(&mut self, key: TipsetKeys, entry: CachedTipset) {
+        let size = entry.size();
+        if let Some(replaced) = self.entries.pop(&key) {
+            self.size -= replaced.size();
+        }
+        if size > self.max_size {
+            return;
+        }
+        while self.size + size > self.max_size {
+            match self.entries.pop_lru() {
+                Some((_, evicted)) => self.size -= evicted.size(),
+                None => break,
+            }
+        }
+        self.entries.put(key, entry);
+        self.size += size;
+    }
+}
+
+/// Requests of a peer in the current rate limiting window.
+struct RequestWindow {
+    started: Instant,
+    count: u32,
+}
 
-/// Builds chain exchange response out of chain data.
-pub fn make_chain_exchange_response<DB>(
-    cs: &ChainStore<DB>,
-    request: &ChainExchangeRequest,
-) -> ChainExchangeResponse
+/// Serving side of `ChainExchange`, building responses out of chain data within the limits of
+/// [`ChainExchangeServerConfig`].
+pub struct ChainExchangeServer<DB> {
+    cs: Arc<ChainStore<DB>>,
+    config: ChainExchangeServerConfig,
+    requests: Mutex<HashMap<PeerId, RequestWindow>>,
+    cache: Option<Mutex<TipsetCache>>,
+}
+
+impl<DB> ChainExchangeServer<DB>
 where
     DB: Blockstore + Store + Clone + Send + Sync + 'static,
 {
-    let mut response_chain: Vec<TipsetBundle> = Vec::with_capacity(request.request_len as usize);
-
-    let mut curr_tipset_cids = request.start.clone();
-
-    loop {
-        let mut tipset_bundle: TipsetBundle = TipsetBundle::default();
-        let tipset = match cs.tipset_from_keys(&TipsetKeys::new(curr_tipset_cids)) {
-            Ok(tipset) => tipset,
-            Err(err) => {
-                debug!("Cannot get tipset from keys: {}", err);
-
-                return ChainExchangeResponse {
-                    chain: vec![],
-                    status: ChainExchangeResponseStatus::InternalError,
-                    message: "Tipset was not found in the database".to_owned(),
-                };
-            }
-        };
+    pub fn new(cs: Arc<ChainStore<DB>>, config: ChainExchangeServerConfig) -> Self {
+        Self {
+            cs,
+            cache: (config.max_cache_bytes > 0)
+                .then(|| Mutex::new(TipsetCache::new(config.max_cache_bytes))),
+            config,
+            requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a request of the peer, and returns `false` if the peer exceeded its rate limit.
+    pub fn allow_request(&self, peer: &PeerId) -> bool {
+        let limit = self.config.max_requests_per_peer_per_minute;
+        if limit == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        let mut requests = self.requests.lock();
+        // Forget the peers whose window is over, so that the table doesn't grow unbounded.
+        if requests.len() > 1024 {
+            requests.retain(|_, window| now.duration_since(window.started) < RATE_LIMIT_WINDOW);
+        }
+        let window = requests.entry(*peer).or_insert(RequestWindow {
+            started: now,
+            count: 0,
+        });
+        if now.duration_since(window.started) >= RATE_LIMIT_WINDOW {
+            window.started = now;
+            window.count = 0;
+        }
+        window.count += 1;
+        window.count <= limit
+    }
+
+    /// Builds the response to a request of the peer, or a `GoAway` one if it's making too many
+    /// requests.
+    pub fn handle_request(
+        &self,
+        peer: &PeerId,
+        request: &ChainExchangeRequest,
+    ) -> ChainExchangeResponse {
+        if !self.allow_request(peer) {
+            debug!("Peer {peer} exceeded the chain exchange rate limit");
+            return ChainExchangeResponse {
+                chain: vec![],
+                status: ChainExchangeResponseStatus::GoAway,
+                message: "Too many requests".to_owned(),
+            };
+        }
+        self.make_response(request)
+    }
+
+    /// Builds chain exchange response out of chain data.
+    pub fn make_response(&self, request: &ChainExchangeRequest) -> ChainExchangeResponse {
+        if let Err(message) = self.validate_request(request) {
+            return ChainExchangeResponse {
+                chain: vec![],
+                status: ChainExchangeResponseStatus::BadRequest,
+                message,
+            };
+        }
+
+        let mut response_chain: Vec<TipsetBundle> = Vec::new();
+        let mut response_size = 0;
+        let mut curr_tipset_cids = request.start.clone();
+
+        loop {
+            let mut tipset_bundle: TipsetBundle = TipsetBundle::default();
+            let mut bundle_size = 0;
+            let tipset = match self.cs.tipset_from_keys(&TipsetKeys::new(curr_tipset_cids)) {
+                Ok(tipset) => tipset,
+                Err(err) => {
+                    debug!("Cannot get tipset from keys: {}", err);
+
+                    return ChainExchangeResponse {
+                        chain: vec![],
+                        status: ChainExchangeResponseStatus::InternalError,
+                        message: "Tipset was not found in the database".to_owned(),
+                    };
+                }
+            };
 
-        if request.include_messages() {
-            match compact_messages(cs.blockstore(), &tipset) {
-                Ok(compacted_messages) => tipset_bundle.messages = Some(compacted_messages),
+            let cached = match self.tipset_data(&tipset, request.include_messages()) {
+                Ok(cached) => cached,
                 Err(err) => {
                     debug!("Cannot compact messages for tipset: {}", err);
 
@@ -52,38 +216,104 @@ where
                         message: "Can not fulfil the request".to_owned(),
                     };
                 }
+            };
+            if let Some((messages, size)) = cached.messages.filter(|_| request.include_messages()) {
+                bundle_size += size;
+                tipset_bundle.messages = Some(messages);
             }
-        }
 
-        curr_tipset_cids = tipset.parents().cids().to_vec();
-        let tipset_epoch = tipset.epoch();
+            curr_tipset_cids = tipset.parents().cids().to_vec();
+            let tipset_epoch = tipset.epoch();
 
-        if request.include_blocks() {
-            // TODO Cloning blocks isn't ideal, this can maybe be switched to serialize this
-            // data in the function. This may not be possible without overriding rpc in libp2p
-            tipset_bundle.blocks = tipset.blocks().to_vec();
+            if request.include_blocks() {
+                bundle_size += cached.blocks_size;
+                tipset_bundle.blocks = cached.blocks;
+            }
+
+            if !response_chain.is_empty()
+                && response_size + bundle_size > self.config.max_response_bytes
+            {
+                break;
+            }
+            response_size += bundle_size;
+            response_chain.push(tipset_bundle);
+
+            if response_chain.len() as u64 >= request.request_len || tipset_epoch == 0 {
+                break;
+            }
         }
 
-        response_chain.push(tipset_bundle);
+        let result_chain_length = response_chain.len() as u64;
 
-        if response_chain.len() as u64 >= request.request_len || tipset_epoch == 0 {
-            break;
+        ChainExchangeResponse {
+            chain: response_chain,
+            status: if result_chain_length < request.request_len {
+                ChainExchangeResponseStatus::PartialResponse
+            } else {
+                ChainExchangeResponseStatus::Success
+            },
+            message: "Success".to_owned(),
         }
     }
 
-    let result_chain_length = response_chain.len() as u64;
+    fn validate_request(&self, request: &ChainExchangeRequest) -> Result<(), String> {
+        if request.start.is_empty() {
+            return Err("No cids in request".to_owned());
+        }
+        if request.request_len == 0 {
+            return Err("Invalid request length of zero".to_owned());
+        }
+        if request.request_len > self.config.max_request_len {
+            return Err(format!(
+                "Request length over maximum allowed ({})",
+                self.config.max_request_len
+            ));
+        }
+        if !request.include_blocks() && !request.include_messages() {
+            return Err("No options set".to_owned());
+        }
+        Ok(())
+    }
 
-    ChainExchangeResponse {
-        chain: response_chain,
-        status: if result_chain_length < request.request_len {
-            ChainExchangeResponseStatus::PartialResponse
-        } else {
-            ChainExchangeResponseStatus::Success
-        },
-        message: "Success".to_owned(),
+    /// Headers of the tipset, along with its compacted messages if `include_messages` is set,
+    /// from the cache when possible.
+    fn tipset_data(
+        &self,
+        tipset: &Tipset,
+        include_messages: bool,
+    ) -> Result<CachedTipset, ChainError> {
+        let cached = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.lock().get(tipset.key()));
+        let mut entry = match cached {
+            Some(entry) if entry.messages.is_some() || !include_messages => return Ok(entry),
+            Some(entry) => entry,
+            None => CachedTipset {
+                blocks: Arc::new(tipset.blocks().to_vec()),
+                blocks_size: tipset.blocks().iter().map(BlockHeader::encoded_len).sum(),
+                messages: None,
+            },
+        };
+        if include_messages {
+            let messages = compact_messages(self.cs.blockstore(), tipset)?;
+            let size = encoded_size(&messages);
+            entry.messages = Some((Arc::new(messages), size));
+        }
+        if let Some(cache) = &self.cache {
+            cache.lock().put(tipset.key().clone(), entry.clone());
+        }
+        Ok(entry)
     }
 }
 
+/// Size of the `CBOR` encoding of a value, counted against the response budget.
+fn encoded_size<T: Serialize>(value: &T) -> usize {
+    fvm_ipld_encoding::to_vec(value)
+        .map(|bytes| bytes.len())
+        .unwrap_or_default()
+}
+
 // Builds CompactedMessages for given Tipset.
 fn compact_messages<DB>(db: &DB, tipset: &Tipset) -> Result<CompactedMessages, ChainError>
 where
@@ -148,13 +378,12 @@ where
 mod tests {
     use super::super::{HEADERS, MESSAGES};
     use super::*;
-    use forest_blocks::BlockHeader;
+    use cid::multihash::{Code::Identity, MultihashDigest};
     use forest_db::MemoryDB;
     use forest_genesis::EXPORT_SR_40;
     use forest_networks::ChainConfig;
     use fvm_ipld_car::load_car;
     use fvm_shared::address::Address;
-    use std::sync::Arc;
     use tokio::io::BufReader;
     use tokio_util::compat::TokioAsyncReadCompatExt;
 
@@ -175,14 +404,15 @@ mod tests {
             .build()
             .unwrap();
 
-        let response = make_chain_exchange_response(
-            &ChainStore::new(db, Arc::new(ChainConfig::default()), &gen_block).unwrap(),
-            &ChainExchangeRequest {
-                start: cids,
-                request_len: 2,
-                options: HEADERS | MESSAGES,
-            },
+        let server = ChainExchangeServer::new(
+            Arc::new(ChainStore::new(db, Arc::new(ChainConfig::default()), &gen_block).unwrap()),
+            ChainExchangeServerConfig::default(),
         );
+        let response = server.make_response(&ChainExchangeRequest {
+            start: cids,
+            request_len: 2,
+            options: HEADERS | MESSAGES,
+        });
 
         // The response will be loaded with tipsets 39 and 38.
         // See:
@@ -231,4 +461,131 @@ mod tests {
         assert_eq!(ts_38_msgs.secp_msg_includes[1].len(), 1);
         assert_eq!(ts_38_msgs.bls_msg_includes[1].len(), 11);
     }
+
+    async fn test_server(
+        config: ChainExchangeServerConfig,
+    ) -> (Vec<Cid>, ChainExchangeServer<MemoryDB>) {
+        let (cids, db) = populate_db().await;
+        let gen_block = BlockHeader::builder()
+            .miner_address(Address::new_id(0))
+            .build()
+            .unwrap();
+        let cs = ChainStore::new(db, Arc::new(ChainConfig::default()), &gen_block).unwrap();
+        (cids, ChainExchangeServer::new(Arc::new(cs), config))
+    }
+
+    #[tokio::test]
+    async fn request_len_over_maximum_test() {
+        let (cids, server) = test_server(ChainExchangeServerConfig {
+            max_request_len: 5,
+            ..Default::default()
+        })
+        .await;
+
+        let response = server.make_response(&ChainExchangeRequest {
+            start: cids,
+            request_len: 6,
+            options: HEADERS,
+        });
+        assert_eq!(response.status, ChainExchangeResponseStatus::BadRequest);
+        assert!(response.chain.is_empty());
+    }
+
+    #[tokio::test]
+    async fn response_size_budget_test() {
+        let (cids, server) = test_server(ChainExchangeServerConfig {
+            max_response_bytes: 1,
+            ..Default::default()
+        })
+        .await;
+
+        // The first tipset is returned even though it's over the budget.
+        let response = server.make_response(&ChainExchangeRequest {
+            start: cids,
+            request_len: 10,
+            options: HEADERS | MESSAGES,
+        });
+        assert_eq!(
+            response.status,
+            ChainExchangeResponseStatus::PartialResponse
+        );
+        assert_eq!(response.chain.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_test() {
+        let (cids, server) = test_server(ChainExchangeServerConfig {
+            max_requests_per_peer_per_minute: 2,
+            ..Default::default()
+        })
+        .await;
+
+        let request = ChainExchangeRequest {
+            start: cids,
+            request_len: 1,
+            options: HEADERS,
+        };
+        let peer = PeerId::random();
+        for _ in 0..2 {
+            let response = server.handle_request(&peer, &request);
+            assert_eq!(response.status, ChainExchangeResponseStatus::Success);
+        }
+        let response = server.handle_request(&peer, &request);
+        assert_eq!(response.status, ChainExchangeResponseStatus::GoAway);
+
+        // Other peers are not affected.
+        let response = server.handle_request(&PeerId::random(), &request);
+        assert_eq!(response.status, ChainExchangeResponseStatus::Success);
+    }
+
+    #[tokio::test]
+    async fn cached_tipsets_are_shared_test() {
+        let (cids, server) = test_server(ChainExchangeServerConfig::default()).await;
+        let request = ChainExchangeRequest {
+            start: cids,
+            request_len: 2,
+            options: HEADERS | MESSAGES,
+        };
+        let first = server.make_response(&request);
+        let second = server.make_response(&request);
+        assert_eq!(first, second);
+        for (a, b) in first.chain.iter().zip(&second.chain) {
+            assert!(Arc::ptr_eq(&a.blocks, &b.blocks));
+            assert!(Arc::ptr_eq(
+                a.messages.as_ref().unwrap(),
+                b.messages.as_ref().unwrap()
+            ));
+        }
+    }
+
+    #[test]
+    fn tipset_cache_size_test() {
+        let key = |i: u8| TipsetKeys::new(vec![Cid::new_v1(0x55, Identity.digest(&[i]))]);
+        let entry = |size| CachedTipset {
+            blocks: Default::default(),
+            blocks_size: size,
+            messages: None,
+        };
+        let mut cache = TipsetCache::new(100);
+        cache.put(key(0), entry(40));
+        cache.put(key(1), entry(40));
+        assert!(cache.get(&key(0)).is_some());
+
+        // The least recently used tipset is evicted
+        cache.put(key(2), entry(40));
+        assert!(cache.get(&key(1)).is_none());
+        assert!(cache.get(&key(0)).is_some());
+        assert!(cache.get(&key(2)).is_some());
+        assert_eq!(cache.size, 80);
+
+        // Replaced entries are accounted for
+        cache.put(key(0), entry(60));
+        assert_eq!(cache.size, 100);
+        assert!(cache.get(&key(2)).is_some());
+
+        // Tipsets larger than the cache are not kept
+        cache.put(key(3), entry(101));
+        assert!(cache.get(&key(3)).is_none());
+        assert_eq!(cache.size, 100);
+    }
 }
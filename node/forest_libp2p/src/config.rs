@@ -1,6 +1,8 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use crate::chain_exchange::ChainExchangeServerConfig;
+use forest_libp2p_bitswap::BitswapServerConfig;
 use libp2p::Multiaddr;
 use serde::{Deserialize, Serialize};
 
@@ -18,6 +20,10 @@ pub struct Libp2pConfig {
     pub kademlia: bool,
     /// Target peer count.
     pub target_peer_count: u32,
+    /// Serving side of `bitswap`.
+    pub bitswap: BitswapServerConfig,
+    /// Serving side of `ChainExchange`.
+    pub chain_exchange: ChainExchangeServerConfig,
 }
 
 impl Default for Libp2pConfig {
@@ -28,6 +34,8 @@ impl Default for Libp2pConfig {
             mdns: false,
             kademlia: true,
             target_peer_count: 75,
+            bitswap: BitswapServerConfig::default(),
+            chain_exchange: ChainExchangeServerConfig::default(),
         }
     }
 }
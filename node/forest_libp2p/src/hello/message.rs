@@ -5,6 +5,16 @@ use cid::Cid;
 use forest_encoding::tuple::*;
 use fvm_shared::bigint::BigInt;
 use fvm_shared::clock::ChainEpoch;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Time in UNIX nanoseconds, as used in [`HelloResponse`].
+pub fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
 
 /// Hello message <https://filecoin-project.github.io/specs/#hello-spec>
 #[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
@@ -25,6 +35,30 @@ pub struct HelloResponse {
     pub sent: u64,
 }
 
+impl HelloResponse {
+    /// Time the peer spent between receiving the request and sending the response, `None` if
+    /// the timestamps are not filled or inconsistent.
+    pub fn processing_time(&self) -> Option<Duration> {
+        if self.arrival == 0 || self.sent < self.arrival {
+            return None;
+        }
+        Some(Duration::from_nanos(self.sent - self.arrival))
+    }
+
+    /// Estimated offset in nanoseconds of the peer clock from the local one, positive if the peer
+    /// is ahead, from the local times the request was sent and the response received, in UNIX
+    /// nanoseconds. `None` if the peer did not fill the timestamps.
+    pub fn clock_offset(&self, request_sent: u64, response_received: u64) -> Option<i64> {
+        if self.arrival == 0 || self.sent == 0 {
+            return None;
+        }
+        let offset = (i128::from(self.arrival) - i128::from(request_sent) + i128::from(self.sent)
+            - i128::from(response_received))
+            / 2;
+        i64::try_from(offset).ok()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -45,4 +79,22 @@ mod tests {
         let msg: HelloRequest = from_slice(&bz).unwrap();
         assert_eq!(msg, orig_msg);
     }
+
+    #[test]
+    fn hello_response_clock_offset() {
+        // The peer clock is 100ns ahead, each way takes 10ns and processing 5ns.
+        let response = HelloResponse {
+            arrival: 1_110,
+            sent: 1_115,
+        };
+        assert_eq!(response.clock_offset(1_000, 1_025), Some(100));
+        assert_eq!(response.processing_time(), Some(Duration::from_nanos(5)));
+
+        let response = HelloResponse {
+            arrival: 0,
+            sent: 0,
+        };
+        assert_eq!(response.clock_offset(1_000, 1_025), None);
+        assert_eq!(response.processing_time(), None);
+    }
 }
@@ -20,6 +20,7 @@ pub use self::peer_manager::*;
 pub use self::service::*;
 
 // Re-export some libp2p types
+pub use forest_libp2p_bitswap::{BitswapServerConfig, BitswapStats, PeerLedger};
 pub use libp2p::core::PeerId;
 pub use libp2p::identity::{ed25519, Keypair};
 pub use libp2p::multiaddr::{Multiaddr, Protocol};
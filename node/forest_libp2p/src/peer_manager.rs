@@ -23,6 +23,9 @@ const LOCAL_INV_ALPHA: u32 = 5;
 /// Global duration multiplier, affects duration delta change.
 const GLOBAL_INV_ALPHA: u32 = 20;
 
+/// Number of failed requests a bad block counts for in the cost of the peer that sent it.
+const BAD_BLOCK_FAILURES: u32 = 10;
+
 #[derive(Debug, Default)]
 /// Contains info about the peer's head [Tipset], as well as the request stats.
 struct PeerInfo {
@@ -116,6 +119,10 @@ impl PeerManager {
                     // Calculate cost based on fail rate and latency
                     let fail_rate = f64::from(info.failures) / f64::from(info.successes);
                     info.average_time.as_secs_f64() + fail_rate * average_time.as_secs_f64()
+                } else if info.average_time != Duration::default() {
+                    // There have been no requests yet, but the latency of the hello handshake
+                    // is known
+                    info.average_time.as_secs_f64() * NEW_PEER_MUL
                 } else {
                     // There have been no failures or successes
                     average_time.as_secs_f64() * NEW_PEER_MUL
@@ -177,6 +184,23 @@ impl PeerManager {
         log_time(peer_stats, dur);
     }
 
+    /// Records the round-trip time of the hello handshake with the peer, used as its latency until
+    /// requests are made to it.
+    pub async fn set_peer_latency(&self, peer: PeerId, rtt: Duration) {
+        trace!("Hello round-trip time with {peer}: {rtt:?}");
+        let mut peers = self.peers.write().await;
+        if peers.bad_peers.remove(&peer) {
+            metrics::BAD_PEERS.dec();
+        };
+        if !peers.full_peers.contains_key(&peer) {
+            metrics::FULL_PEERS.inc();
+        }
+        let peer_stats = peers.full_peers.entry(peer).or_default();
+        if peer_stats.successes + peer_stats.failures == 0 {
+            peer_stats.average_time = rtt;
+        }
+    }
+
     /// Logs a failure for the given peer, and updates the average request duration.
     pub async fn log_failure(&self, peer: PeerId, dur: Duration) {
         debug!("logging failure for {:?}", peer);
@@ -192,6 +216,15 @@ impl PeerManager {
         }
     }
 
+    /// Penalizes a peer that sent a bad block, lowering its priority for requests.
+    pub async fn report_bad_block(&self, peer: PeerId) {
+        debug!("peer {} sent a bad block", peer);
+        let mut peers = self.peers.write().await;
+        if let Some(peer_stats) = peers.full_peers.get_mut(&peer) {
+            peer_stats.failures += BAD_BLOCK_FAILURES;
+        }
+    }
+
     /// Removes a peer from the set and returns true if the value was present previously
     pub async fn mark_peer_bad(&self, peer_id: PeerId) -> bool {
         let mut peers = self.peers.write().await;
@@ -1,13 +1,11 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use super::chain_exchange::{
-    make_chain_exchange_response, ChainExchangeRequest, ChainExchangeResponse,
-};
+use super::chain_exchange::{ChainExchangeRequest, ChainExchangeResponse, ChainExchangeServer};
 use super::{ForestBehaviour, ForestBehaviourEvent, Libp2pConfig};
 use crate::discovery::DiscoveryOut;
 use crate::{
-    hello::{HelloRequest, HelloResponse},
+    hello::{unix_nanos, HelloRequest, HelloResponse},
     rpc::RequestResponseError,
 };
 use crate::{PeerManager, PeerOperation};
@@ -17,11 +15,12 @@ use flume::Sender;
 use forest_blocks::GossipBlock;
 use forest_chain::ChainStore;
 use forest_db::Store;
-use forest_libp2p_bitswap::{BitswapRequestManager, BitswapStore};
+use forest_libp2p_bitswap::{BitswapRequestManager, BitswapStats, BitswapStore};
 use forest_message::SignedMessage;
 use forest_utils::io::read_file_to_vec;
 use futures::channel::oneshot::Sender as OneShotSender;
 use futures::select;
+use futures::FutureExt;
 use futures_util::stream::StreamExt;
 use fvm_ipld_blockstore::Blockstore;
 use libp2p::gossipsub::GossipsubEvent;
@@ -48,7 +47,7 @@ use libp2p::{core::Multiaddr, swarm::SwarmBuilder};
 use log::{debug, error, info, trace, warn};
 use std::path::Path;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime};
 use tokio_stream::wrappers::IntervalStream;
 
 mod metrics {
@@ -92,6 +91,10 @@ const PUBSUB_TOPICS: [&str; 2] = [PUBSUB_BLOCK_STR, PUBSUB_MSG_STR];
 
 pub const BITSWAP_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Interval at which queued `bitswap` wants are served when no event triggers it, e.g. after
+/// the outbound bandwidth limit is reached.
+const BITSWAP_SERVE_INTERVAL: Duration = Duration::from_millis(100);
+
 const BAN_PEER_DURATION: Duration = Duration::from_secs(60 * 60); //1h
 
 type HelloRequestTable =
@@ -181,6 +184,7 @@ pub enum NetRPCMethods {
     NetPeers(OneShotSender<HashMap<PeerId, Vec<Multiaddr>>>),
     NetConnect(OneShotSender<bool>, PeerId, Vec<Multiaddr>),
     NetDisconnect(OneShotSender<()>, PeerId),
+    NetBitswapStats(OneShotSender<BitswapStats>),
 }
 
 /// The `Libp2pService` listens to events from the libp2p swarm.
@@ -273,8 +277,15 @@ where
         let mut cx_request_table = HashMap::new();
         let (cx_response_tx, cx_response_rx) = flume::unbounded();
         let (bitswap_outbound_request_tx, bitswap_outbound_request_rx) = flume::unbounded();
-        let bitswap_request_manager =
-            Arc::new(BitswapRequestManager::new(bitswap_outbound_request_tx));
+        let bitswap_request_manager = Arc::new(BitswapRequestManager::new(
+            bitswap_outbound_request_tx,
+            self.config.bitswap.clone(),
+        ));
+        let chain_exchange_server = Arc::new(ChainExchangeServer::new(
+            self.cs.clone(),
+            self.config.chain_exchange.clone(),
+        ));
+        let mut bitswap_serve_interval = None;
 
         let mut cx_response_rx_stream = cx_response_rx.stream().fuse();
         let mut bitswap_outbound_request_rx_stream = bitswap_outbound_request_rx.stream().fuse();
@@ -283,6 +294,14 @@ where
         let metrics = Metrics::new(&mut libp2p_registry);
         forest_metrics::add_metrics_registry("libp2p".into(), libp2p_registry).await;
         loop {
+            // Serving is only resumed periodically while wants are waiting for it
+            if !bitswap_request_manager.has_queued_wants() {
+                bitswap_serve_interval = None;
+            } else if bitswap_serve_interval.is_none() {
+                let start = tokio::time::Instant::now() + BITSWAP_SERVE_INTERVAL;
+                bitswap_serve_interval =
+                    Some(tokio::time::interval_at(start, BITSWAP_SERVE_INTERVAL));
+            }
             select! {
                 swarm_event = swarm_stream.next() => match swarm_event {
                     // outbound events
@@ -294,6 +313,7 @@ where
                             &self.peer_manager,
                             event,
                             &self.cs,
+                            &chain_exchange_server,
                             &self.genesis_cid,
                             &self.network_sender_out,
                             &mut hello_request_table,
@@ -332,11 +352,15 @@ where
                     }
                 },
                 bitswap_outbound_request_opt = bitswap_outbound_request_rx_stream.next() => {
-                    if let Some((peer, request)) = bitswap_outbound_request_opt {
+                    if let Some((peer, requests)) = bitswap_outbound_request_opt {
                         let bitswap = &mut swarm_stream.get_mut().behaviour_mut().bitswap;
-                        bitswap.send_request(&peer, request);
+                        bitswap.send_requests(&peer, requests);
                     }
                 }
+                _ = tick(&mut bitswap_serve_interval).fuse() => {
+                    let bitswap = &mut swarm_stream.get_mut().behaviour_mut().bitswap;
+                    bitswap_request_manager.serve(bitswap, self.cs.blockstore());
+                },
                 peer_ops_opt = peer_ops_rx_stream.next() => {
                     if let Some(peer_ops) = peer_ops_opt {
                         handle_peer_ops(swarm_stream.get_mut(), peer_ops);
@@ -358,6 +382,16 @@ where
     }
 }
 
+/// Waits for the next tick of the interval, forever if there is none.
+async fn tick(interval: &mut Option<tokio::time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => futures::future::pending().await,
+    }
+}
+
 fn handle_peer_ops(swarm: &mut Swarm<ForestBehaviour>, peer_ops: PeerOperation) {
     use PeerOperation::*;
     match peer_ops {
@@ -469,6 +503,14 @@ async fn handle_network_message(
                     warn!("Failed to disconnect from a peer");
                 }
             }
+            NetRPCMethods::NetBitswapStats(response_channel) => {
+                if response_channel
+                    .send(bitswap_request_manager.stats())
+                    .is_err()
+                {
+                    warn!("Failed to send bitswap stats");
+                }
+            }
         },
     }
 }
@@ -554,7 +596,6 @@ async fn handle_hello_event(
     network_sender_out: &Sender<NetworkEvent>,
     hello_request_table: &mut HelloRequestTable,
 ) {
-    let behaviour = swarm.behaviour_mut();
     match rr_event {
         RequestResponseEvent::Message { peer, message } => match message {
             RequestResponseMessage::Request {
@@ -562,6 +603,9 @@ async fn handle_hello_event(
                 channel,
                 request_id: _,
             } => {
+                // Timestamp the arrival first, the peer uses it to estimate the clock offset.
+                let arrival = unix_nanos(SystemTime::now());
+
                 emit_event(
                     network_sender_out,
                     NetworkEvent::HelloRequestInbound {
@@ -571,15 +615,10 @@ async fn handle_hello_event(
                 )
                 .await;
 
-                let arrival = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .expect("System time before unix epoch")
-                    .as_nanos()
-                    .try_into()
-                    .expect("System time since unix epoch should not exceed u64");
-
                 trace!("Received hello request: {:?}", request);
                 if &request.genesis_cid != genesis_cid {
+                    // The peer is on another network, drop it right away rather than failing
+                    // on its blocks later.
                     peer_manager
                         .ban_peer(
                             peer,
@@ -590,17 +629,14 @@ async fn handle_hello_event(
                             Some(BAN_PEER_DURATION),
                         )
                         .await;
+                    let _ = Swarm::disconnect_peer_id(swarm, peer);
                 } else {
-                    let sent = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .expect("System time before unix epoch")
-                        .as_nanos()
-                        .try_into()
-                        .expect("System time since unix epoch should not exceed u64");
+                    let sent = unix_nanos(SystemTime::now());
 
                     // Send hello response immediately, no need to have the overhead of emitting
                     // channel and polling future here.
-                    if let Err(e) = behaviour
+                    if let Err(e) = swarm
+                        .behaviour_mut()
                         .hello
                         .send_response(channel, HelloResponse { arrival, sent })
                     {
@@ -710,7 +746,7 @@ async fn handle_ping_event(ping_event: ping::Event, peer_manager: &Arc<PeerManag
 
 async fn handle_chain_exchange_event<DB>(
     ce_event: RequestResponseEvent<ChainExchangeRequest, ChainExchangeResponse>,
-    db: &Arc<ChainStore<DB>>,
+    chain_exchange_server: &Arc<ChainExchangeServer<DB>>,
     network_sender_out: &Sender<NetworkEvent>,
     cx_request_table: &mut CxRequestTable,
     cx_response_tx: Sender<(
@@ -735,12 +771,12 @@ async fn handle_chain_exchange_event<DB>(
                         NetworkEvent::ChainExchangeRequestInbound { request_id },
                     )
                     .await;
-                    let db = db.clone();
+                    let server = chain_exchange_server.clone();
                     tokio::task::spawn(async move {
                         if let Err(e) = cx_response_tx.send((
                             request_id,
                             channel,
-                            make_chain_exchange_response(db.as_ref(), &request),
+                            server.handle_request(&peer, &request),
                         )) {
                             debug!("Failed to send ChainExchangeResponse: {e:?}");
                         }
@@ -819,6 +855,7 @@ async fn handle_forest_behaviour_event<DB, P>(
     peer_manager: &Arc<PeerManager>,
     event: ForestBehaviourEvent,
     db: &Arc<ChainStore<DB>>,
+    chain_exchange_server: &Arc<ChainExchangeServer<DB>>,
     genesis_cid: &Cid,
     network_sender_out: &Sender<NetworkEvent>,
     hello_request_table: &mut HelloRequestTable,
@@ -865,7 +902,7 @@ async fn handle_forest_behaviour_event<DB, P>(
         ForestBehaviourEvent::ChainExchange(ce_event) => {
             handle_chain_exchange_event(
                 ce_event,
-                db,
+                chain_exchange_server,
                 network_sender_out,
                 cx_request_table,
                 cx_response_tx,
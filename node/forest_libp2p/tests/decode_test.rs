@@ -10,6 +10,7 @@ use fvm_shared::address::Address;
 use fvm_shared::crypto::signature::Signature;
 use fvm_shared::{bigint::BigInt, message::Message};
 use std::convert::TryFrom;
+use std::sync::Arc;
 
 #[test]
 fn convert_single_tipset_bundle() {
@@ -22,13 +23,13 @@ fn convert_single_tipset_bundle() {
         secp_messages: Vec::new(),
     };
     let bundle = TipsetBundle {
-        blocks: vec![block.header.clone()],
-        messages: Some(CompactedMessages {
+        blocks: Arc::new(vec![block.header.clone()]),
+        messages: Some(Arc::new(CompactedMessages {
             bls_msgs: Vec::new(),
             bls_msg_includes: vec![Vec::new()],
             secp_msgs: Vec::new(),
             secp_msg_includes: vec![Vec::new()],
-        }),
+        })),
     };
 
     let res = ChainExchangeResponse {
@@ -91,13 +92,13 @@ fn tipset_bundle_to_full_tipset() {
     };
 
     let mut tsb = TipsetBundle {
-        blocks: vec![h0, h1],
-        messages: Some(CompactedMessages {
+        blocks: Arc::new(vec![h0, h1]),
+        messages: Some(Arc::new(CompactedMessages {
             secp_msgs: vec![sa, sb, sc, sd],
             secp_msg_includes: vec![vec![0, 1, 3], vec![1, 2, 0]],
             bls_msgs: vec![ua, ub, uc, ud],
             bls_msg_includes: vec![vec![0, 1], vec![2, 3]],
-        }),
+        })),
     };
 
     assert_eq!(
@@ -106,7 +107,7 @@ fn tipset_bundle_to_full_tipset() {
     );
 
     let mut cloned = tsb.clone();
-    if let Some(m) = cloned.messages.as_mut() {
+    if let Some(m) = cloned.messages.as_mut().map(Arc::make_mut) {
         m.secp_msg_includes = vec![vec![0, 4], vec![0]];
     }
     // Invalidate tipset bundle by having invalid index
@@ -115,7 +116,7 @@ fn tipset_bundle_to_full_tipset() {
         "Invalid index should return error"
     );
 
-    if let Some(m) = tsb.messages.as_mut() {
+    if let Some(m) = tsb.messages.as_mut().map(Arc::make_mut) {
         // Invalidate tipset bundle by not having includes same length as number of blocks
         m.secp_msg_includes = vec![vec![0]];
     }
@@ -3,12 +3,15 @@
 
 use cid::Cid;
 use forest_actor_interface::market::{DealProposal, DealState};
+use forest_auth::TokenRegistry;
 use forest_beacon::{Beacon, BeaconSchedule};
 use forest_blocks::{tipset_keys_json::TipsetKeysJson, Tipset};
-use forest_chain::ChainStore;
-use forest_chain_sync::{BadBlockCache, SyncState};
+use forest_chain::{ChainStore, ReorgLogEntry};
+use forest_chain_sync::{BadBlockCache, ClockDriftEstimator, FetchOnMissBlockstore, SyncState};
 use forest_ipld::json::IpldJson;
+use forest_json::address::json::AddressJson;
 use forest_json::cid::CidJson;
+use forest_json::message::json::MessageJson;
 use forest_json::message_receipt::json::ReceiptJson;
 use forest_json::token_amount::json;
 use forest_key_management::KeyStore;
@@ -18,6 +21,7 @@ use forest_message::signed_message::SignedMessage;
 use forest_message_pool::{MessagePool, MpoolRpcProvider};
 use forest_state_manager::StateManager;
 use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::message::Message;
 use jsonrpc_v2::{MapRouter as JsonRpcMapRouter, Server as JsonRpcServer};
@@ -33,21 +37,29 @@ where
     B: Beacon,
 {
     pub keystore: Arc<RwLock<KeyStore>>,
+    /// Issued tokens, loaded once and flushed to the database on every change
+    pub token_registry: Arc<RwLock<TokenRegistry>>,
     pub chain_store: Arc<ChainStore<DB>>,
     pub state_manager: Arc<StateManager<DB>>,
     pub mpool: Arc<MessagePool<MpoolRpcProvider<DB>>>,
-    pub bad_blocks: Arc<BadBlockCache>,
+    pub bad_blocks: Arc<BadBlockCache<DB>>,
     pub sync_state: Arc<SyncRwLock<SyncState>>,
+    pub clock_drift: Arc<ClockDriftEstimator>,
     pub network_send: flume::Sender<NetworkMessage>,
     pub network_name: String,
     pub new_mined_block_tx: flume::Sender<Arc<Tipset>>,
     pub beacon: Arc<BeaconSchedule<B>>,
+    /// Store fetching missing state over `Bitswap`, when enabled
+    pub fetch_on_miss_store: Option<FetchOnMissBlockstore<DB>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RPCSyncState {
     #[serde(rename = "ActiveSyncs")]
     pub active_syncs: Vec<SyncState>,
+    /// Estimated offset of the local clock from the network in milliseconds, positive if ahead
+    #[serde(rename = "ClockDrift", default)]
+    pub clock_drift: Option<i64>,
 }
 
 pub type JsonRpcServerState = Arc<JsonRpcServer<JsonRpcMapRouter>>;
@@ -66,6 +78,22 @@ pub struct BlockMessages {
     pub cids: Vec<Cid>,
 }
 
+/// Message included in the chain, along with its CID.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ApiMessage {
+    pub cid: CidJson,
+    pub message: MessageJson,
+}
+
+/// Size of a DAG, as the number of unique blocks and their total size in bytes.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct ObjStat {
+    pub size: u64,
+    pub links: u64,
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct MessageSendSpec {
@@ -80,7 +108,7 @@ pub struct MarketDeal {
     pub state: DealState,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct MessageLookup {
     pub receipt: ReceiptJson,
@@ -91,6 +119,22 @@ pub struct MessageLookup {
     pub return_dec: IpldJson,
 }
 
+// Multisig API
+/// Pending transaction of a multisig actor.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MsigTransaction {
+    #[serde(rename = "ID")]
+    pub id: i64,
+    pub to: AddressJson,
+    #[serde(with = "json")]
+    pub value: TokenAmount,
+    pub method: u64,
+    /// Base64 encoded parameters
+    pub params: String,
+    pub approved: Vec<AddressJson>,
+}
+
 // Net API
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -100,6 +144,62 @@ pub struct AddrInfo {
     pub addrs: Vec<Multiaddr>,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BitswapStat {
+    /// Whether the node answers the wants of other peers
+    pub serving: bool,
+    pub wantlist: Vec<CidJson>,
+    pub peers: Vec<String>,
+    pub blocks_received: u64,
+    pub data_received: u64,
+    pub blocks_sent: u64,
+    pub data_sent: u64,
+    pub dup_blks_received: u64,
+    pub dup_data_received: u64,
+    /// Wants of other peers waiting to be served
+    pub queued_wants: u64,
+    pub ledgers: Vec<BitswapLedger>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BitswapLedger {
+    pub peer: String,
+    /// Bytes sent over bytes received
+    pub value: f64,
+    pub sent: u64,
+    pub recv: u64,
+    pub exchanged: u64,
+}
+
+/// Reorganization of the heaviest chain, see [`ReorgLogEntry`].
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReorgInfo {
+    /// Time of the reorganization in UNIX seconds
+    pub timestamp: u64,
+    /// Number of tipsets reverted
+    pub depth: u64,
+    pub old_head: TipsetKeysJson,
+    pub old_epoch: ChainEpoch,
+    pub new_head: TipsetKeysJson,
+    pub new_epoch: ChainEpoch,
+}
+
+impl From<ReorgLogEntry> for ReorgInfo {
+    fn from(entry: ReorgLogEntry) -> Self {
+        Self {
+            timestamp: entry.timestamp,
+            depth: entry.depth,
+            old_head: TipsetKeysJson(entry.old_head),
+            old_epoch: entry.old_epoch,
+            new_head: TipsetKeysJson(entry.new_head),
+            new_epoch: entry.new_epoch,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PeerID {
     pub multihash: Multihash,
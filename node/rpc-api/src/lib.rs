@@ -22,6 +22,9 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     // Auth API
     access.insert(auth_api::AUTH_NEW, Access::Admin);
     access.insert(auth_api::AUTH_VERIFY, Access::Read);
+    access.insert(auth_api::AUTH_LIST, Access::Admin);
+    access.insert(auth_api::AUTH_REVOKE, Access::Admin);
+    access.insert(auth_api::AUTH_ROTATE_SECRET, Access::Admin);
 
     // Beacon API
     access.insert(beacon_api::BEACON_GET_ENTRY, Access::Read);
@@ -40,6 +43,14 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(chain_api::CHAIN_GET_TIPSET_HASH, Access::Read);
     access.insert(chain_api::CHAIN_VALIDATE_TIPSET_CHECKPOINTS, Access::Read);
     access.insert(chain_api::CHAIN_GET_NAME, Access::Read);
+    access.insert(chain_api::CHAIN_RECOMPUTE_STATE, Access::Admin);
+    access.insert(chain_api::CHAIN_BACKFILL_MESSAGE_INDEX, Access::Admin);
+    access.insert(chain_api::CHAIN_GET_PATH, Access::Read);
+    access.insert(chain_api::CHAIN_GET_REORG_LOG, Access::Read);
+    access.insert(chain_api::CHAIN_GET_PARENT_MESSAGES, Access::Read);
+    access.insert(chain_api::CHAIN_GET_PARENT_RECEIPTS, Access::Read);
+    access.insert(chain_api::CHAIN_TIPSET_WEIGHT, Access::Read);
+    access.insert(chain_api::CHAIN_STAT_OBJ, Access::Read);
 
     // Message Pool API
     access.insert(mpool_api::MPOOL_PENDING, Access::Read);
@@ -50,26 +61,38 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(sync_api::SYNC_CHECK_BAD, Access::Read);
     access.insert(sync_api::SYNC_MARK_BAD, Access::Admin);
     access.insert(sync_api::SYNC_STATE, Access::Read);
+    access.insert(sync_api::SYNC_CHECKPOINT, Access::Admin);
+    access.insert(sync_api::SYNC_CLEAR_CHECKPOINT, Access::Admin);
+    access.insert(sync_api::SYNC_UNMARK_BAD, Access::Admin);
+    access.insert(sync_api::SYNC_UNMARK_ALL_BAD, Access::Admin);
 
     // Wallet API
     access.insert(wallet_api::WALLET_BALANCE, Access::Write);
     access.insert(wallet_api::WALLET_DEFAULT_ADDRESS, Access::Write);
+    access.insert(wallet_api::WALLET_DELETE, Access::Admin);
     access.insert(wallet_api::WALLET_EXPORT, Access::Admin);
+    access.insert(wallet_api::WALLET_GET_LABEL, Access::Write);
     access.insert(wallet_api::WALLET_HAS, Access::Write);
     access.insert(wallet_api::WALLET_IMPORT, Access::Admin);
+    access.insert(wallet_api::WALLET_LABELS, Access::Write);
     access.insert(wallet_api::WALLET_LIST, Access::Write);
     access.insert(wallet_api::WALLET_NEW, Access::Write);
     access.insert(wallet_api::WALLET_SET_DEFAULT, Access::Write);
+    access.insert(wallet_api::WALLET_SET_LABEL, Access::Write);
     access.insert(wallet_api::WALLET_SIGN, Access::Sign);
     access.insert(wallet_api::WALLET_VERIFY, Access::Read);
 
     // State API
     access.insert(state_api::STATE_CALL, Access::Read);
     access.insert(state_api::STATE_REPLAY, Access::Read);
+    access.insert(state_api::STATE_COMPUTE_TIPSET, Access::Read);
+    access.insert(state_api::STATE_GET_ACTOR, Access::Read);
+    access.insert(state_api::STATE_ENCODE_PARAMS, Access::Read);
     access.insert(state_api::STATE_MARKET_BALANCE, Access::Read);
     access.insert(state_api::STATE_MARKET_DEALS, Access::Read);
     access.insert(state_api::STATE_GET_RECEIPT, Access::Read);
     access.insert(state_api::STATE_WAIT_MSG, Access::Read);
+    access.insert(state_api::STATE_SEARCH_MSG, Access::Read);
     access.insert(state_api::STATE_NETWORK_NAME, Access::Read);
     access.insert(state_api::STATE_NETWORK_VERSION, Access::Read);
 
@@ -79,6 +102,14 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(gas_api::GAS_ESTIMATE_FEE_CAP, Access::Read);
     access.insert(gas_api::GAS_ESTIMATE_MESSAGE_GAS, Access::Read);
 
+    // Multisig API
+    access.insert(msig_api::MSIG_CREATE, Access::Sign);
+    access.insert(msig_api::MSIG_PROPOSE, Access::Sign);
+    access.insert(msig_api::MSIG_APPROVE, Access::Sign);
+    access.insert(msig_api::MSIG_CANCEL, Access::Sign);
+    access.insert(msig_api::MSIG_GET_PENDING, Access::Read);
+    access.insert(msig_api::MSIG_GET_VESTED, Access::Read);
+
     // Common API
     access.insert(common_api::VERSION, Access::Read);
 
@@ -87,6 +118,7 @@ pub static ACCESS_MAP: Lazy<HashMap<&str, Access>> = Lazy::new(|| {
     access.insert(net_api::NET_PEERS, Access::Read);
     access.insert(net_api::NET_CONNECT, Access::Write);
     access.insert(net_api::NET_DISCONNECT, Access::Write);
+    access.insert(net_api::NET_BITSWAP_STAT, Access::Read);
 
     access
 });
@@ -110,6 +142,7 @@ pub const API_INFO_KEY: &str = "FULLNODE_API_INFO";
 /// Authorization API
 pub mod auth_api {
     use chrono::Duration;
+    use forest_auth::TokenInfo;
     use serde::{Deserialize, Serialize};
     use serde_with::{serde_as, DurationSeconds};
 
@@ -120,12 +153,31 @@ pub mod auth_api {
         pub perms: Vec<String>,
         #[serde_as(as = "DurationSeconds<i64>")]
         pub token_exp: Duration,
+        #[serde(default)]
+        pub label: Option<String>,
     }
     pub type AuthNewResult = Vec<u8>;
 
     pub const AUTH_VERIFY: &str = "Filecoin.AuthVerify";
     pub type AuthVerifyParams = (String,);
     pub type AuthVerifyResult = Vec<String>;
+
+    pub const AUTH_LIST: &str = "Filecoin.AuthList";
+    pub type AuthListParams = ();
+    pub type AuthListResult = Vec<TokenInfo>;
+
+    pub const AUTH_REVOKE: &str = "Filecoin.AuthRevoke";
+    pub type AuthRevokeParams = (String,);
+    pub type AuthRevokeResult = ();
+
+    pub const AUTH_ROTATE_SECRET: &str = "Filecoin.AuthRotateSecret";
+    #[serde_as]
+    #[derive(Deserialize, Serialize)]
+    pub struct AuthRotateSecretParams {
+        #[serde_as(as = "DurationSeconds<i64>")]
+        pub token_exp: Duration,
+    }
+    pub type AuthRotateSecretResult = Vec<u8>;
 }
 
 /// Beacon API
@@ -142,13 +194,15 @@ pub mod beacon_api {
 pub mod chain_api {
     use std::path::PathBuf;
 
-    use crate::data_types::BlockMessages;
+    use crate::data_types::{ApiMessage, BlockMessages, ObjStat, ReorgInfo};
     use forest_blocks::{
         header::json::BlockHeaderJson, tipset_json::TipsetJson, tipset_keys_json::TipsetKeysJson,
         TipsetKeys,
     };
+    use forest_chain::headchange_json::HeadChangeJson;
     use forest_json::cid::CidJson;
     use forest_json::message::json::MessageJson;
+    use forest_json::message_receipt::json::ReceiptJson;
     use fvm_shared::clock::ChainEpoch;
 
     pub const CHAIN_GET_MESSAGE: &str = "Filecoin.ChainGetMessage";
@@ -156,6 +210,7 @@ pub mod chain_api {
     pub type ChainGetMessageResult = MessageJson;
 
     pub const CHAIN_EXPORT: &str = "Filecoin.ChainExport";
+    /// The snapshot is compressed with zstd if the output path has a `.zst` extension
     pub type ChainExportParams = (ChainEpoch, i64, PathBuf, TipsetKeysJson, bool);
     pub type ChainExportResult = PathBuf;
 
@@ -202,6 +257,40 @@ pub mod chain_api {
     pub const CHAIN_GET_NAME: &str = "Filecoin.ChainGetName";
     pub type ChainGetNameParams = ();
     pub type ChainGetNameResult = String;
+
+    pub const CHAIN_RECOMPUTE_STATE: &str = "Filecoin.ChainRecomputeState";
+    pub type ChainRecomputeStateParams = (ChainEpoch, ChainEpoch);
+    pub type ChainRecomputeStateResult = usize;
+    /// Maximum number of epochs recomputed by a single `ChainRecomputeState` call
+    pub const CHAIN_RECOMPUTE_STATE_MAX_EPOCHS: ChainEpoch = 2880;
+
+    pub const CHAIN_BACKFILL_MESSAGE_INDEX: &str = "Filecoin.ChainBackfillMessageIndex";
+    pub type ChainBackfillMessageIndexParams = (ChainEpoch, ChainEpoch);
+    pub type ChainBackfillMessageIndexResult = usize;
+
+    pub const CHAIN_GET_PATH: &str = "Filecoin.ChainGetPath";
+    pub type ChainGetPathParams = (TipsetKeysJson, TipsetKeysJson);
+    pub type ChainGetPathResult = Vec<HeadChangeJson>;
+
+    pub const CHAIN_GET_REORG_LOG: &str = "Filecoin.ChainGetReorgLog";
+    pub type ChainGetReorgLogParams = ();
+    pub type ChainGetReorgLogResult = Vec<ReorgInfo>;
+
+    pub const CHAIN_GET_PARENT_MESSAGES: &str = "Filecoin.ChainGetParentMessages";
+    pub type ChainGetParentMessagesParams = (CidJson,);
+    pub type ChainGetParentMessagesResult = Vec<ApiMessage>;
+
+    pub const CHAIN_GET_PARENT_RECEIPTS: &str = "Filecoin.ChainGetParentReceipts";
+    pub type ChainGetParentReceiptsParams = (CidJson,);
+    pub type ChainGetParentReceiptsResult = Vec<ReceiptJson>;
+
+    pub const CHAIN_TIPSET_WEIGHT: &str = "Filecoin.ChainTipSetWeight";
+    pub type ChainTipSetWeightParams = (TipsetKeysJson,);
+    pub type ChainTipSetWeightResult = String;
+
+    pub const CHAIN_STAT_OBJ: &str = "Filecoin.ChainStatObj";
+    pub type ChainStatObjParams = (CidJson, Option<CidJson>);
+    pub type ChainStatObjResult = ObjStat;
 }
 
 /// Message Pool API
@@ -229,6 +318,7 @@ pub mod mpool_api {
 /// Sync API
 pub mod sync_api {
     use crate::data_types::RPCSyncState;
+    use forest_blocks::tipset_keys_json::TipsetKeysJson;
     use forest_json::cid::CidJson;
 
     pub const SYNC_CHECK_BAD: &str = "Filecoin.SyncCheckBad";
@@ -242,10 +332,27 @@ pub mod sync_api {
     pub const SYNC_STATE: &str = "Filecoin.SyncState";
     pub type SyncStateParams = ();
     pub type SyncStateResult = RPCSyncState;
+
+    pub const SYNC_CHECKPOINT: &str = "Filecoin.SyncCheckpoint";
+    pub type SyncCheckpointParams = (TipsetKeysJson,);
+    pub type SyncCheckpointResult = ();
+
+    pub const SYNC_CLEAR_CHECKPOINT: &str = "Filecoin.SyncClearCheckpoint";
+    pub type SyncClearCheckpointParams = ();
+    pub type SyncClearCheckpointResult = ();
+
+    pub const SYNC_UNMARK_BAD: &str = "Filecoin.SyncUnmarkBad";
+    pub type SyncUnmarkBadParams = (CidJson,);
+    pub type SyncUnmarkBadResult = ();
+
+    pub const SYNC_UNMARK_ALL_BAD: &str = "Filecoin.SyncUnmarkAllBad";
+    pub type SyncUnmarkAllBadParams = ();
+    pub type SyncUnmarkAllBadResult = ();
 }
 
 /// Wallet API
 pub mod wallet_api {
+    use ahash::HashMap;
     use forest_json::address::json::AddressJson;
     use forest_json::signature::json::{signature_type::SignatureTypeJson, SignatureJson};
     use forest_key_management::json::KeyInfoJson;
@@ -258,10 +365,18 @@ pub mod wallet_api {
     pub type WalletDefaultAddressParams = ();
     pub type WalletDefaultAddressResult = String;
 
+    pub const WALLET_DELETE: &str = "Filecoin.WalletDelete";
+    pub type WalletDeleteParams = (AddressJson,);
+    pub type WalletDeleteResult = ();
+
     pub const WALLET_EXPORT: &str = "Filecoin.WalletExport";
     pub type WalletExportParams = (String,);
     pub type WalletExportResult = KeyInfoJson;
 
+    pub const WALLET_GET_LABEL: &str = "Filecoin.WalletGetLabel";
+    pub type WalletGetLabelParams = (AddressJson,);
+    pub type WalletGetLabelResult = Option<String>;
+
     pub const WALLET_HAS: &str = "Filecoin.WalletHas";
     pub type WalletHasParams = (String,);
     pub type WalletHasResult = bool;
@@ -270,6 +385,10 @@ pub mod wallet_api {
     pub type WalletImportParams = Vec<KeyInfoJson>;
     pub type WalletImportResult = String;
 
+    pub const WALLET_LABELS: &str = "Filecoin.WalletLabels";
+    pub type WalletLabelsParams = ();
+    pub type WalletLabelsResult = HashMap<String, String>;
+
     pub const WALLET_LIST: &str = "Filecoin.WalletList";
     pub type WalletListParams = ();
     pub type WalletListResult = Vec<AddressJson>;
@@ -282,6 +401,10 @@ pub mod wallet_api {
     pub type WalletSetDefaultParams = (AddressJson,);
     pub type WalletSetDefaultResult = ();
 
+    pub const WALLET_SET_LABEL: &str = "Filecoin.WalletSetLabel";
+    pub type WalletSetLabelParams = (AddressJson, String);
+    pub type WalletSetLabelResult = ();
+
     pub const WALLET_SIGN: &str = "Filecoin.WalletSign";
     pub type WalletSignParams = (AddressJson, Vec<u8>);
     pub type WalletSignResult = SignatureJson;
@@ -296,12 +419,14 @@ pub mod state_api {
     use crate::data_types::{MarketDeal, MessageLookup};
     use ahash::HashMap;
     use forest_blocks::tipset_keys_json::TipsetKeysJson;
+    use forest_json::actor_state::json::ActorStateJson;
     use forest_json::address::json::AddressJson;
     use forest_json::cid::CidJson;
     use forest_json::message::json::MessageJson;
     use forest_json::message_receipt::json::ReceiptJson;
     use forest_shim::version::NetworkVersion;
-    use forest_state_manager::{InvocResult, MarketBalance};
+    use forest_state_manager::{ComputeStateOutput, InvocResult, MarketBalance};
+    use fvm_shared::MethodNum;
 
     pub const STATE_CALL: &str = "Filecoin.StateCall";
     pub type StateCallParams = (MessageJson, TipsetKeysJson);
@@ -311,6 +436,10 @@ pub mod state_api {
     pub type StateReplayParams = (CidJson, TipsetKeysJson);
     pub type StateReplayResult = InvocResult;
 
+    pub const STATE_COMPUTE_TIPSET: &str = "Filecoin.StateComputeTipSet";
+    pub type StateComputeTipSetParams = (TipsetKeysJson,);
+    pub type StateComputeTipSetResult = ComputeStateOutput;
+
     pub const STATE_NETWORK_NAME: &str = "Filecoin.StateNetworkName";
     pub type StateNetworkNameParams = ();
     pub type StateNetworkNameResult = String;
@@ -319,6 +448,15 @@ pub mod state_api {
     pub type StateNetworkVersionParams = (TipsetKeysJson,);
     pub type StateNetworkVersionResult = NetworkVersion;
 
+    pub const STATE_GET_ACTOR: &str = "Filecoin.StateGetActor";
+    pub type StateGetActorParams = (AddressJson, TipsetKeysJson);
+    pub type StateGetActorResult = Option<ActorStateJson>;
+
+    pub const STATE_ENCODE_PARAMS: &str = "Filecoin.StateEncodeParams";
+    pub type StateEncodeParamsParams = (CidJson, MethodNum, serde_json::Value);
+    /// Base64 encoded CBOR parameters
+    pub type StateEncodeParamsResult = String;
+
     pub const STATE_MARKET_BALANCE: &str = "Filecoin.StateMarketBalance";
     pub type StateMarketBalanceParams = (AddressJson, TipsetKeysJson);
     pub type StateMarketBalanceResult = MarketBalance;
@@ -334,6 +472,10 @@ pub mod state_api {
     pub const STATE_WAIT_MSG: &str = "Filecoin.StateWaitMsg";
     pub type StateWaitMsgParams = (CidJson, i64);
     pub type StateWaitMsgResult = MessageLookup;
+
+    pub const STATE_SEARCH_MSG: &str = "Filecoin.StateSearchMsg";
+    pub type StateSearchMsgParams = (CidJson,);
+    pub type StateSearchMsgResult = Option<MessageLookup>;
 }
 
 /// Gas API
@@ -360,6 +502,47 @@ pub mod gas_api {
     pub type GasEstimateMessageGasResult = MessageJson;
 }
 
+/// Multisig API
+pub mod msig_api {
+    use crate::data_types::MsigTransaction;
+    use forest_blocks::tipset_keys_json::TipsetKeysJson;
+    use forest_json::address::json::AddressJson;
+    use forest_json::cid::CidJson;
+    use fvm_shared::clock::ChainEpoch;
+
+    pub const MSIG_CREATE: &str = "Filecoin.MsigCreate";
+    pub type MsigCreateParams = (
+        u64,
+        Vec<AddressJson>,
+        ChainEpoch,
+        String,
+        AddressJson,
+        String,
+    );
+    pub type MsigCreateResult = CidJson;
+
+    pub const MSIG_PROPOSE: &str = "Filecoin.MsigPropose";
+    /// The parameters of the proposed message are base64 encoded, as in `MsigTransaction`
+    pub type MsigProposeParams = (AddressJson, AddressJson, String, AddressJson, u64, String);
+    pub type MsigProposeResult = CidJson;
+
+    pub const MSIG_APPROVE: &str = "Filecoin.MsigApprove";
+    pub type MsigApproveParams = (AddressJson, i64, AddressJson);
+    pub type MsigApproveResult = CidJson;
+
+    pub const MSIG_CANCEL: &str = "Filecoin.MsigCancel";
+    pub type MsigCancelParams = (AddressJson, i64, AddressJson);
+    pub type MsigCancelResult = CidJson;
+
+    pub const MSIG_GET_PENDING: &str = "Filecoin.MsigGetPending";
+    pub type MsigGetPendingParams = (AddressJson, TipsetKeysJson);
+    pub type MsigGetPendingResult = Vec<MsigTransaction>;
+
+    pub const MSIG_GET_VESTED: &str = "Filecoin.MsigGetVested";
+    pub type MsigGetVestedParams = (AddressJson, TipsetKeysJson, TipsetKeysJson);
+    pub type MsigGetVestedResult = String;
+}
+
 /// Common API
 pub mod common_api {
     use super::data_types::APIVersion;
@@ -371,7 +554,7 @@ pub mod common_api {
 
 /// Net API
 pub mod net_api {
-    use crate::data_types::AddrInfo;
+    use crate::data_types::{AddrInfo, BitswapStat};
 
     pub const NET_ADDRS_LISTEN: &str = "Filecoin.NetAddrsListen";
     pub type NetAddrsListenParams = ();
@@ -388,4 +571,8 @@ pub mod net_api {
     pub const NET_DISCONNECT: &str = "Filecoin.NetDisconnect";
     pub type NetDisconnectParams = (String,);
     pub type NetDisconnectResult = ();
+
+    pub const NET_BITSWAP_STAT: &str = "Filecoin.NetBitswapStat";
+    pub type NetBitswapStatParams = ();
+    pub type NetBitswapStatResult = BitswapStat;
 }
@@ -20,3 +20,24 @@ pub async fn auth_verify(
 ) -> Result<AuthVerifyResult, JsonRpcError> {
     call(AUTH_VERIFY, (token,), auth_token).await
 }
+
+/// Lists the tokens issued by the node
+pub async fn auth_list(auth_token: &Option<String>) -> Result<AuthListResult, JsonRpcError> {
+    call(AUTH_LIST, (), auth_token).await
+}
+
+/// Revokes a token by its identifier
+pub async fn auth_revoke(
+    params: AuthRevokeParams,
+    auth_token: &Option<String>,
+) -> Result<AuthRevokeResult, JsonRpcError> {
+    call(AUTH_REVOKE, params, auth_token).await
+}
+
+/// Rotates the JWT secret and returns a new admin token
+pub async fn auth_rotate_secret(
+    params: AuthRotateSecretParams,
+    auth_token: &Option<String>,
+) -> Result<AuthRotateSecretResult, JsonRpcError> {
+    call(AUTH_ROTATE_SECRET, params, auth_token).await
+}
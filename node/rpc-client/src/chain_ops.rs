@@ -67,3 +67,58 @@ pub async fn chain_validate_tipset_checkpoints(
 pub async fn chain_get_name(auth_token: &Option<String>) -> Result<ChainGetNameResult, Error> {
     call(CHAIN_GET_NAME, (), auth_token).await
 }
+
+pub async fn chain_recompute_state(
+    params: ChainRecomputeStateParams,
+    auth_token: &Option<String>,
+) -> Result<ChainRecomputeStateResult, Error> {
+    call(CHAIN_RECOMPUTE_STATE, params, auth_token).await
+}
+
+pub async fn chain_backfill_message_index(
+    params: ChainBackfillMessageIndexParams,
+    auth_token: &Option<String>,
+) -> Result<ChainBackfillMessageIndexResult, Error> {
+    call(CHAIN_BACKFILL_MESSAGE_INDEX, params, auth_token).await
+}
+
+pub async fn chain_get_path(
+    params: ChainGetPathParams,
+    auth_token: &Option<String>,
+) -> Result<ChainGetPathResult, Error> {
+    call(CHAIN_GET_PATH, params, auth_token).await
+}
+
+pub async fn chain_get_reorg_log(
+    auth_token: &Option<String>,
+) -> Result<ChainGetReorgLogResult, Error> {
+    call(CHAIN_GET_REORG_LOG, (), auth_token).await
+}
+
+pub async fn chain_get_parent_messages(
+    params: ChainGetParentMessagesParams,
+    auth_token: &Option<String>,
+) -> Result<ChainGetParentMessagesResult, Error> {
+    call(CHAIN_GET_PARENT_MESSAGES, params, auth_token).await
+}
+
+pub async fn chain_get_parent_receipts(
+    params: ChainGetParentReceiptsParams,
+    auth_token: &Option<String>,
+) -> Result<ChainGetParentReceiptsResult, Error> {
+    call(CHAIN_GET_PARENT_RECEIPTS, params, auth_token).await
+}
+
+pub async fn chain_tipset_weight(
+    params: ChainTipSetWeightParams,
+    auth_token: &Option<String>,
+) -> Result<ChainTipSetWeightResult, Error> {
+    call(CHAIN_TIPSET_WEIGHT, params, auth_token).await
+}
+
+pub async fn chain_stat_obj(
+    params: ChainStatObjParams,
+    auth_token: &Option<String>,
+) -> Result<ChainStatObjResult, Error> {
+    call(CHAIN_STAT_OBJ, params, auth_token).await
+}
@@ -4,7 +4,9 @@
 /// Filecoin RPC client interface methods
 pub mod auth_ops;
 pub mod chain_ops;
+pub mod gas_ops;
 pub mod mpool_ops;
+pub mod msig_ops;
 pub mod net_ops;
 pub mod state_ops;
 pub mod sync_ops;
@@ -32,7 +34,9 @@ pub const RPC_ENDPOINT: &str = "rpc/v0";
 
 pub use self::auth_ops::*;
 pub use self::chain_ops::*;
+pub use self::gas_ops::*;
 pub use self::mpool_ops::*;
+pub use self::msig_ops::*;
 pub use self::net_ops::*;
 pub use self::state_ops::*;
 pub use self::sync_ops::*;
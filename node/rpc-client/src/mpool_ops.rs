@@ -12,6 +12,13 @@ pub async fn mpool_pending(
     call(MPOOL_PENDING, params, auth_token).await
 }
 
+pub async fn mpool_push(
+    params: MpoolPushParams,
+    auth_token: &Option<String>,
+) -> Result<MpoolPushResult, Error> {
+    call(MPOOL_PUSH, params, auth_token).await
+}
+
 pub async fn mpool_push_message(
     params: MpoolPushMessageParams,
     auth_token: &Option<String>,
@@ -0,0 +1,48 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::call;
+use forest_rpc_api::msig_api::*;
+use jsonrpc_v2::Error;
+
+pub async fn msig_create(
+    params: MsigCreateParams,
+    auth_token: &Option<String>,
+) -> Result<MsigCreateResult, Error> {
+    call(MSIG_CREATE, params, auth_token).await
+}
+
+pub async fn msig_propose(
+    params: MsigProposeParams,
+    auth_token: &Option<String>,
+) -> Result<MsigProposeResult, Error> {
+    call(MSIG_PROPOSE, params, auth_token).await
+}
+
+pub async fn msig_approve(
+    params: MsigApproveParams,
+    auth_token: &Option<String>,
+) -> Result<MsigApproveResult, Error> {
+    call(MSIG_APPROVE, params, auth_token).await
+}
+
+pub async fn msig_cancel(
+    params: MsigCancelParams,
+    auth_token: &Option<String>,
+) -> Result<MsigCancelResult, Error> {
+    call(MSIG_CANCEL, params, auth_token).await
+}
+
+pub async fn msig_get_pending(
+    params: MsigGetPendingParams,
+    auth_token: &Option<String>,
+) -> Result<MsigGetPendingResult, Error> {
+    call(MSIG_GET_PENDING, params, auth_token).await
+}
+
+pub async fn msig_get_vested(
+    params: MsigGetVestedParams,
+    auth_token: &Option<String>,
+) -> Result<MsigGetVestedResult, Error> {
+    call(MSIG_GET_VESTED, params, auth_token).await
+}
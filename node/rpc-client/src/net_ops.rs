@@ -32,3 +32,10 @@ pub async fn net_disconnect(
 ) -> Result<NetDisconnectResult, Error> {
     call(NET_DISCONNECT, params, auth_token).await
 }
+
+pub async fn net_bitswap_stat(
+    params: NetBitswapStatParams,
+    auth_token: &Option<String>,
+) -> Result<NetBitswapStatResult, Error> {
+    call(NET_BITSWAP_STAT, params, auth_token).await
+}
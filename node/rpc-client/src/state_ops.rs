@@ -1,2 +1,41 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
+
+use crate::call;
+use forest_rpc_api::state_api::*;
+use jsonrpc_v2::Error;
+
+pub async fn state_call(
+    params: StateCallParams,
+    auth_token: &Option<String>,
+) -> Result<StateCallResult, Error> {
+    call(STATE_CALL, params, auth_token).await
+}
+
+pub async fn state_wait_msg(
+    params: StateWaitMsgParams,
+    auth_token: &Option<String>,
+) -> Result<StateWaitMsgResult, Error> {
+    call(STATE_WAIT_MSG, params, auth_token).await
+}
+
+pub async fn state_search_msg(
+    params: StateSearchMsgParams,
+    auth_token: &Option<String>,
+) -> Result<StateSearchMsgResult, Error> {
+    call(STATE_SEARCH_MSG, params, auth_token).await
+}
+
+pub async fn state_get_actor(
+    params: StateGetActorParams,
+    auth_token: &Option<String>,
+) -> Result<StateGetActorResult, Error> {
+    call(STATE_GET_ACTOR, params, auth_token).await
+}
+
+pub async fn state_encode_params(
+    params: StateEncodeParamsParams,
+    auth_token: &Option<String>,
+) -> Result<StateEncodeParamsResult, Error> {
+    call(STATE_ENCODE_PARAMS, params, auth_token).await
+}
@@ -25,3 +25,29 @@ pub async fn sync_status(
 ) -> Result<SyncStateResult, JsonRpcError> {
     call(SYNC_STATE, params, auth_token).await
 }
+
+pub async fn sync_checkpoint(
+    params: SyncCheckpointParams,
+    auth_token: &Option<String>,
+) -> Result<SyncCheckpointResult, JsonRpcError> {
+    call(SYNC_CHECKPOINT, params, auth_token).await
+}
+
+pub async fn sync_clear_checkpoint(
+    auth_token: &Option<String>,
+) -> Result<SyncClearCheckpointResult, JsonRpcError> {
+    call(SYNC_CLEAR_CHECKPOINT, (), auth_token).await
+}
+
+pub async fn sync_unmark_bad(
+    params: SyncUnmarkBadParams,
+    auth_token: &Option<String>,
+) -> Result<SyncUnmarkBadResult, JsonRpcError> {
+    call(SYNC_UNMARK_BAD, params, auth_token).await
+}
+
+pub async fn sync_unmark_all_bad(
+    auth_token: &Option<String>,
+) -> Result<SyncUnmarkAllBadResult, JsonRpcError> {
+    call(SYNC_UNMARK_ALL_BAD, (), auth_token).await
+}
@@ -18,6 +18,13 @@ pub async fn wallet_default_address(
     call(WALLET_DEFAULT_ADDRESS, (), auth_token).await
 }
 
+pub async fn wallet_delete(
+    address: WalletDeleteParams,
+    auth_token: &Option<String>,
+) -> Result<WalletDeleteResult, Error> {
+    call(WALLET_DELETE, address, auth_token).await
+}
+
 pub async fn wallet_balance(
     address: WalletBalanceParams,
     auth_token: &Option<String>,
@@ -70,3 +77,21 @@ pub async fn wallet_verify(
 ) -> Result<WalletVerifyResult, Error> {
     call(WALLET_VERIFY, message, auth_token).await
 }
+
+pub async fn wallet_get_label(
+    address: WalletGetLabelParams,
+    auth_token: &Option<String>,
+) -> Result<WalletGetLabelResult, Error> {
+    call(WALLET_GET_LABEL, address, auth_token).await
+}
+
+pub async fn wallet_labels(auth_token: &Option<String>) -> Result<WalletLabelsResult, Error> {
+    call(WALLET_LABELS, (), auth_token).await
+}
+
+pub async fn wallet_set_label(
+    params: WalletSetLabelParams,
+    auth_token: &Option<String>,
+) -> Result<WalletSetLabelResult, Error> {
+    call(WALLET_SET_LABEL, params, auth_token).await
+}
@@ -0,0 +1,259 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Encoding of the JSON parameters of built-in actor methods, as accepted by Lotus. The fields
+//! are named as in the Go actors and encoded in the tuple layout the actors expect.
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use forest_actor_interface::{market, miner, multisig};
+use forest_json::address::json::AddressJson;
+use forest_json::token_amount::json as token_amount_json;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::MethodNum;
+use serde::de::DeserializeOwned;
+use serde::{de, Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProposeParamsJson {
+    to: AddressJson,
+    #[serde(with = "token_amount_json")]
+    value: TokenAmount,
+    method: MethodNum,
+    #[serde(default, deserialize_with = "base64_bytes")]
+    params: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TxnIDParamsJson {
+    #[serde(rename = "ID")]
+    id: i64,
+    #[serde(default, deserialize_with = "base64_bytes")]
+    proposal_hash: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AddSignerParamsJson {
+    signer: AddressJson,
+    increase: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RemoveSignerParamsJson {
+    signer: AddressJson,
+    decrease: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SwapSignerParamsJson {
+    from: AddressJson,
+    to: AddressJson,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeNumApprovalsThresholdParamsJson {
+    new_threshold: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct LockBalanceParamsJson {
+    start_epoch: ChainEpoch,
+    unlock_duration: ChainEpoch,
+    #[serde(with = "token_amount_json")]
+    amount: TokenAmount,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct MarketWithdrawBalanceParamsJson {
+    provider_or_client_address: AddressJson,
+    #[serde(with = "token_amount_json")]
+    amount: TokenAmount,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct MinerWithdrawBalanceParamsJson {
+    #[serde(with = "token_amount_json")]
+    amount_requested: TokenAmount,
+}
+
+fn base64_bytes<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    BASE64_STANDARD.decode(s).map_err(de::Error::custom)
+}
+
+fn parse<T: DeserializeOwned>(params: serde_json::Value) -> anyhow::Result<T> {
+    Ok(serde_json::from_value(params)?)
+}
+
+/// Encodes the JSON parameters of a method of the built-in actor with the given manifest name.
+/// Returns `None` if the parameters of the method are unknown, in which case they can only be
+/// encoded as raw DAG-JSON.
+pub(crate) fn encode_params(
+    actor: &str,
+    method: MethodNum,
+    params: serde_json::Value,
+) -> anyhow::Result<Option<RawBytes>> {
+    const MSIG_PROPOSE: MethodNum = multisig::Method::Propose as MethodNum;
+    const MSIG_APPROVE: MethodNum = multisig::Method::Approve as MethodNum;
+    const MSIG_CANCEL: MethodNum = multisig::Method::Cancel as MethodNum;
+    const MSIG_ADD_SIGNER: MethodNum = multisig::Method::AddSigner as MethodNum;
+    const MSIG_REMOVE_SIGNER: MethodNum = multisig::Method::RemoveSigner as MethodNum;
+    const MSIG_SWAP_SIGNER: MethodNum = multisig::Method::SwapSigner as MethodNum;
+    const MSIG_CHANGE_THRESHOLD: MethodNum =
+        multisig::Method::ChangeNumApprovalsThreshold as MethodNum;
+    const MSIG_LOCK_BALANCE: MethodNum = multisig::Method::LockBalance as MethodNum;
+    const MARKET_ADD_BALANCE: MethodNum = market::Method::AddBalance as MethodNum;
+    const MARKET_WITHDRAW_BALANCE: MethodNum = market::Method::WithdrawBalance as MethodNum;
+    const MINER_WITHDRAW_BALANCE: MethodNum = miner::Method::WithdrawBalance as MethodNum;
+    const MINER_CHANGE_OWNER: MethodNum = miner::Method::ChangeOwnerAddress as MethodNum;
+
+    let encoded = match (actor, method) {
+        (multisig::MANIFEST_NAME, MSIG_PROPOSE) => {
+            let p: ProposeParamsJson = parse(params)?;
+            RawBytes::serialize(multisig::ProposeParams {
+                to: p.to.0,
+                value: p.value,
+                method: p.method,
+                params: RawBytes::new(p.params),
+            })?
+        }
+        (multisig::MANIFEST_NAME, MSIG_APPROVE | MSIG_CANCEL) => {
+            let p: TxnIDParamsJson = parse(params)?;
+            RawBytes::serialize(multisig::TxnIDParams {
+                id: multisig::TxnID(p.id),
+                proposal_hash: p.proposal_hash,
+            })?
+        }
+        (multisig::MANIFEST_NAME, MSIG_ADD_SIGNER) => {
+            let p: AddSignerParamsJson = parse(params)?;
+            RawBytes::serialize(multisig::AddSignerParams {
+                signer: p.signer.0,
+                increase: p.increase,
+            })?
+        }
+        (multisig::MANIFEST_NAME, MSIG_REMOVE_SIGNER) => {
+            let p: RemoveSignerParamsJson = parse(params)?;
+            RawBytes::serialize(multisig::RemoveSignerParams {
+                signer: p.signer.0,
+                decrease: p.decrease,
+            })?
+        }
+        (multisig::MANIFEST_NAME, MSIG_SWAP_SIGNER) => {
+            let p: SwapSignerParamsJson = parse(params)?;
+            RawBytes::serialize(multisig::SwapSignerParams {
+                from: p.from.0,
+                to: p.to.0,
+            })?
+        }
+        (multisig::MANIFEST_NAME, MSIG_CHANGE_THRESHOLD) => {
+            let p: ChangeNumApprovalsThresholdParamsJson = parse(params)?;
+            RawBytes::serialize(multisig::ChangeNumApprovalsThresholdParams {
+                new_threshold: p.new_threshold,
+            })?
+        }
+        (multisig::MANIFEST_NAME, MSIG_LOCK_BALANCE) => {
+            let p: LockBalanceParamsJson = parse(params)?;
+            RawBytes::serialize(multisig::LockBalanceParams {
+                start_epoch: p.start_epoch,
+                unlock_duration: p.unlock_duration,
+                amount: p.amount,
+            })?
+        }
+        (market::MANIFEST_NAME, MARKET_ADD_BALANCE)
+        | (miner::MANIFEST_NAME, MINER_CHANGE_OWNER) => {
+            let AddressJson(address) = parse(params)?;
+            RawBytes::serialize(address)?
+        }
+        (market::MANIFEST_NAME, MARKET_WITHDRAW_BALANCE) => {
+            let p: MarketWithdrawBalanceParamsJson = parse(params)?;
+            RawBytes::serialize(market::WithdrawBalanceParams {
+                provider_or_client: p.provider_or_client_address.0,
+                amount: p.amount,
+            })?
+        }
+        (miner::MANIFEST_NAME, MINER_WITHDRAW_BALANCE) => {
+            let p: MinerWithdrawBalanceParamsJson = parse(params)?;
+            RawBytes::serialize(miner::WithdrawBalanceParams {
+                amount_requested: p.amount_requested,
+            })?
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(encoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm_shared::address::Address;
+    use serde_json::json;
+
+    #[test]
+    fn multisig_propose_is_encoded_as_tuple() {
+        let params = json!({
+            "To": "f01234",
+            "Value": "1000",
+            "Method": 0,
+            "Params": BASE64_STANDARD.encode([0x80]),
+        });
+        let encoded = encode_params(
+            multisig::MANIFEST_NAME,
+            multisig::Method::Propose as MethodNum,
+            params,
+        )
+        .unwrap()
+        .unwrap();
+        let expected = RawBytes::serialize(multisig::ProposeParams {
+            to: Address::new_id(1234),
+            value: TokenAmount::from_atto(1000),
+            method: 0,
+            params: RawBytes::new(vec![0x80]),
+        })
+        .unwrap();
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn address_params() {
+        let encoded = encode_params(
+            market::MANIFEST_NAME,
+            market::Method::AddBalance as MethodNum,
+            json!("f01234"),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(encoded, RawBytes::serialize(Address::new_id(1234)).unwrap());
+    }
+
+    #[test]
+    fn invalid_params_are_rejected() {
+        assert!(encode_params(
+            multisig::MANIFEST_NAME,
+            multisig::Method::AddSigner as MethodNum,
+            json!({ "Signer": "f01234" }),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn unknown_methods_are_not_encoded() {
+        assert!(encode_params(multisig::MANIFEST_NAME, 1000, json!({}))
+            .unwrap()
+            .is_none());
+        assert!(encode_params("account", 2, json!({})).unwrap().is_none());
+    }
+}
@@ -5,6 +5,7 @@ use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
 
 use forest_auth::*;
 use forest_beacon::Beacon;
+use forest_db::Store;
 use forest_rpc_api::{auth_api::*, data_types::RPCState};
 use fvm_ipld_blockstore::Blockstore;
 
@@ -14,13 +15,21 @@ pub(crate) async fn auth_new<DB, B>(
     Params(params): Params<AuthNewParams>,
 ) -> Result<AuthNewResult, JsonRpcError>
 where
-    DB: Blockstore,
+    DB: Blockstore + Store,
     B: Beacon,
 {
     let auth_params: AuthNewParams = params;
     let ks = data.keystore.read().await;
     let ki = ks.get(JWT_IDENTIFIER)?;
-    let token = create_token(auth_params.perms, ki.private_key(), auth_params.token_exp)?;
+    let (token, info) = create_labeled_token(
+        auth_params.perms,
+        ki.private_key(),
+        auth_params.token_exp,
+        auth_params.label,
+    )?;
+    let mut registry = data.token_registry.write().await;
+    registry.register(info);
+    registry.flush(data.chain_store.blockstore())?;
     Ok(token.as_bytes().to_vec())
 }
 
@@ -30,13 +39,71 @@ pub(crate) async fn auth_verify<DB, B>(
     Params(params): Params<AuthVerifyParams>,
 ) -> Result<AuthVerifyResult, JsonRpcError>
 where
-    DB: Blockstore,
+    DB: Blockstore + Store,
     B: Beacon,
 {
     let ks = data.keystore.read().await;
     let (header_raw,) = params;
     let token = header_raw.trim_start_matches("Bearer ");
     let ki = ks.get(JWT_IDENTIFIER)?;
-    let perms = verify_token(token, ki.private_key())?;
+    let registry = data.token_registry.read().await;
+    let perms = verify_unrevoked_token(token, ki.private_key(), &registry)?;
     Ok(perms)
 }
+
+/// RPC call to list the non-expired tokens issued by this node
+pub(crate) async fn auth_list<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<AuthListResult, JsonRpcError>
+where
+    DB: Blockstore + Store,
+    B: Beacon,
+{
+    Ok(data.token_registry.read().await.list())
+}
+
+/// RPC call to revoke a token by its identifier
+pub(crate) async fn auth_revoke<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<AuthRevokeParams>,
+) -> Result<AuthRevokeResult, JsonRpcError>
+where
+    DB: Blockstore + Store,
+    B: Beacon,
+{
+    let (id,) = params;
+    let mut registry = data.token_registry.write().await;
+    registry.revoke(&id)?;
+    registry.flush(data.chain_store.blockstore())?;
+    Ok(())
+}
+
+/// RPC call to replace the JWT secret, invalidating every previously issued token.
+/// Returns a fresh admin token signed with the new secret.
+pub(crate) async fn auth_rotate_secret<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<AuthRotateSecretParams>,
+) -> Result<AuthRotateSecretResult, JsonRpcError>
+where
+    DB: Blockstore + Store,
+    B: Beacon,
+{
+    let mut ks = data.keystore.write().await;
+    if ks.get(JWT_IDENTIFIER).is_ok() {
+        ks.remove(JWT_IDENTIFIER.to_owned())?;
+    }
+    ks.put(JWT_IDENTIFIER.to_owned(), generate_priv_key())?;
+    let ki = ks.get(JWT_IDENTIFIER)?;
+
+    let (token, info) = create_labeled_token(
+        ADMIN.to_owned(),
+        ki.private_key(),
+        params.token_exp,
+        Some("admin (rotated)".to_owned()),
+    )?;
+    let mut registry = data.token_registry.write().await;
+    registry.clear();
+    registry.register(info);
+    registry.flush(data.chain_store.blockstore())?;
+    Ok(token.as_bytes().to_vec())
+}
@@ -2,22 +2,29 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 #![allow(clippy::unused_async)]
 
+use ahash::HashSet;
 use anyhow::Result;
+use async_compression::tokio::write::ZstdEncoder;
+use cid::Cid;
 use forest_beacon::Beacon;
 use forest_blocks::{
     header::json::BlockHeaderJson, tipset_json::TipsetJson, tipset_keys_json::TipsetKeysJson,
     BlockHeader, Tipset,
 };
+use forest_chain::{ChainStore, Scale};
 use forest_db::Store;
+use forest_ipld::recurse_links;
 use forest_json::cid::CidJson;
 use forest_json::message::json::MessageJson;
+use forest_json::message_receipt::json::ReceiptJson;
 use forest_rpc_api::{
     chain_api::*,
-    data_types::{BlockMessages, RPCState},
+    data_types::{ApiMessage, BlockMessages, ObjStat, RPCState},
 };
 use forest_utils::db::BlockstoreExt;
 use forest_utils::io::AsyncWriterWithChecksum;
 use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::Cbor;
 use fvm_shared::message::Message;
 use hex::ToHex;
 use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
@@ -56,6 +63,7 @@ where
     B: Beacon,
 {
     let (epoch, recent_roots, out, TipsetKeysJson(tsk), skip_checksum) = params;
+    let compressed = out.extension().unwrap_or_default() == "zst";
 
     let chain_finality = data.state_manager.chain_config().policy.chain_finality;
     if recent_roots < chain_finality {
@@ -64,7 +72,9 @@ where
         ))?;
     }
 
-    let out_tmp = out.with_extension("car.tmp");
+    let mut out_tmp = out.clone().into_os_string();
+    out_tmp.push(".tmp");
+    let out_tmp = PathBuf::from(out_tmp);
     let file = File::create(&out_tmp).await.map_err(JsonRpcError::from)?;
     let writer = AsyncWriterWithChecksum::<Sha256, _>::new(BufWriter::new(file));
 
@@ -72,11 +82,18 @@ where
 
     let start_ts = data.chain_store.tipset_by_height(epoch, head, true)?;
 
-    match data
-        .chain_store
-        .export(&start_ts, recent_roots, writer)
-        .await
-    {
+    // The checksum is computed over the compressed stream, i.e. the content of the file.
+    let export = if compressed {
+        data.chain_store
+            .export(&start_ts, recent_roots, ZstdEncoder::new(writer))
+            .await
+    } else {
+        data.chain_store
+            .export(&start_ts, recent_roots, writer)
+            .await
+    };
+
+    match export {
         Ok(checksum) => {
             std::fs::rename(&out_tmp, &out)?;
             if !skip_checksum {
@@ -298,3 +315,367 @@ where
     let name: String = data.state_manager.chain_config().name.clone();
     Ok(name)
 }
+
+pub(crate) async fn chain_recompute_state<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainRecomputeStateParams>,
+) -> Result<ChainRecomputeStateResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (from, to) = params;
+    if from > to {
+        Err(&format!("invalid epoch range: {from} > {to}"))?;
+    }
+    if to - from >= CHAIN_RECOMPUTE_STATE_MAX_EPOCHS {
+        Err(&format!(
+            "epoch range too large: at most {CHAIN_RECOMPUTE_STATE_MAX_EPOCHS} epochs can be recomputed at once"
+        ))?;
+    }
+    // Tipsets are executed on blocking threads, the recomputation runs as its own task so that
+    // it completes even if the client disconnects.
+    let state_manager = data.state_manager.clone();
+    let recomputed =
+        tokio::spawn(async move { state_manager.recompute_tipset_states(from, to).await })
+            .await??;
+    Ok(recomputed)
+}
+
+pub(crate) async fn chain_backfill_message_index<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainBackfillMessageIndexParams>,
+) -> Result<ChainBackfillMessageIndexResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (from, to) = params;
+    if from > to {
+        Err(&format!("invalid epoch range: {from} > {to}"))?;
+    }
+    let chain_store = data.chain_store.clone();
+    let indexed =
+        tokio::task::spawn_blocking(move || chain_store.backfill_msg_index(from, to)).await??;
+    Ok(indexed)
+}
+
+pub(crate) async fn chain_get_path<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainGetPathParams>,
+) -> Result<ChainGetPathResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (TipsetKeysJson(from), TipsetKeysJson(to)) = params;
+    let chain_store = data.chain_store.clone();
+    let path = tokio::task::spawn_blocking(move || chain_store.get_path(&from, &to)).await??;
+    Ok(path.into_iter().map(Into::into).collect())
+}
+
+pub(crate) async fn chain_get_reorg_log<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<ChainGetReorgLogResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let log = data.chain_store.reorg_log()?;
+    Ok(log.into_iter().map(Into::into).collect())
+}
+
+/// Loads the block header and its parent tipset, `None` for the genesis block.
+fn block_parent_tipset<DB>(
+    chain_store: &ChainStore<DB>,
+    blk_cid: &Cid,
+) -> Result<Option<(BlockHeader, Arc<Tipset>)>, JsonRpcError>
+where
+    DB: Blockstore + Store + Send + Sync,
+{
+    let blk: BlockHeader = chain_store
+        .blockstore()
+        .get_obj(blk_cid)?
+        .ok_or("can't find block with that cid")?;
+    if blk.epoch() == 0 {
+        return Ok(None);
+    }
+    let parent = chain_store.tipset_from_keys(blk.parents())?;
+    Ok(Some((blk, parent)))
+}
+
+pub(crate) async fn chain_get_parent_messages<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainGetParentMessagesParams>,
+) -> Result<ChainGetParentMessagesResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (CidJson(blk_cid),) = params;
+    let (_, parent) = match block_parent_tipset(&data.chain_store, &blk_cid)? {
+        Some(found) => found,
+        None => return Ok(Vec::new()),
+    };
+    let msgs = data.chain_store.messages_for_tipset(&parent)?;
+    Ok(msgs
+        .iter()
+        .map(|msg| {
+            Ok(ApiMessage {
+                cid: CidJson(msg.cid()?),
+                message: MessageJson(msg.message().clone()),
+            })
+        })
+        .collect::<Result<_, anyhow::Error>>()?)
+}
+
+pub(crate) async fn chain_get_parent_receipts<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainGetParentReceiptsParams>,
+) -> Result<ChainGetParentReceiptsResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (CidJson(blk_cid),) = params;
+    let (blk, _) = match block_parent_tipset(&data.chain_store, &blk_cid)? {
+        Some(found) => found,
+        None => return Ok(Vec::new()),
+    };
+    let receipts = forest_chain::get_parent_receipts(data.chain_store.blockstore(), &blk)?;
+    Ok(receipts.into_iter().map(ReceiptJson).collect())
+}
+
+pub(crate) async fn chain_tipset_weight<DB, B, S>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainTipSetWeightParams>,
+) -> Result<ChainTipSetWeightResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+    S: Scale,
+{
+    let (TipsetKeysJson(tsk),) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk)?;
+    let weight = S::weight(data.chain_store.blockstore(), &ts)?;
+    Ok(weight.to_string())
+}
+
+pub(crate) async fn chain_stat_obj<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<ChainStatObjParams>,
+) -> Result<ChainStatObjResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (CidJson(obj_cid), base) = params;
+    let db = data.chain_store.blockstore().clone();
+    let handle = tokio::runtime::Handle::current();
+    // The walk reads the whole DAG from the store, keep it off the runtime threads
+    let stat = tokio::task::spawn_blocking(move || {
+        handle.block_on(stat_obj(&db, obj_cid, base.map(|CidJson(cid)| cid)))
+    })
+    .await??;
+    Ok(stat)
+}
+
+/// Size and number of the blocks of the DAG under `obj_cid` that are not reachable from `base`.
+async fn stat_obj<DB: Blockstore + Sync>(
+    db: &DB,
+    obj_cid: Cid,
+    base: Option<Cid>,
+) -> Result<ObjStat, anyhow::Error> {
+    let load = |cid: Cid| -> Result<Vec<u8>, anyhow::Error> {
+        db.get(&cid)?
+            .ok_or_else(|| anyhow::anyhow!("can't find object {cid}"))
+    };
+
+    // Blocks reachable from the base are walked first, so that they are skipped when walking
+    // the object.
+    let mut walked = HashSet::default();
+    if let Some(base_cid) = base {
+        recurse_links(&mut walked, base_cid, &mut |cid| {
+            let res = load(cid);
+            async move { res }
+        })
+        .await?;
+    }
+
+    let mut stat = ObjStat::default();
+    recurse_links(&mut walked, obj_cid, &mut |cid| {
+        let res = load(cid);
+        if let Ok(bytes) = &res {
+            stat.size += bytes.len() as u64;
+            stat.links += 1;
+        }
+        async move { res }
+    })
+    .await?;
+    Ok(stat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync_api::tests::state_setup;
+    use cid::multihash::Code::Blake2b256;
+    use forest_blocks::TipsetKeys;
+    use forest_chain::test_utils::{child, message, receipt};
+    use forest_chain::{headchange_json::HeadChangeJson, Weight};
+
+    /// Weighs tipsets by their epoch.
+    struct EpochScale;
+
+    impl Scale for EpochScale {
+        fn weight<DB>(_: &DB, ts: &Tipset) -> Result<Weight, anyhow::Error>
+        where
+            DB: Blockstore,
+        {
+            Ok(ts.epoch().into())
+        }
+    }
+
+    fn genesis<DB: Blockstore + Store>(chain_store: &ChainStore<DB>) -> Tipset {
+        Tipset::from(chain_store.genesis().unwrap().unwrap())
+    }
+
+    #[tokio::test]
+    async fn parent_messages_and_receipts() {
+        let (state, _) = state_setup();
+        let db = state.chain_store.blockstore();
+        let genesis = genesis(&state.chain_store);
+        // The last message reuses the nonce of the second one and is not executed.
+        let duplicate = Message {
+            method_num: 1,
+            ..message(1)
+        };
+        let msgs = [message(0), message(1), duplicate];
+        let a1 = child(db, &genesis, 1, &msgs, &[]);
+        let a2 = child(db, &a1, 1, &[], &[receipt(1), receipt(2)]);
+        let blk_cid = CidJson(*a2.cids().first().unwrap());
+
+        let parent_msgs =
+            chain_get_parent_messages(Data(state.clone()), Params((blk_cid.clone(),)))
+                .await
+                .unwrap();
+        let cids: Vec<_> = parent_msgs.iter().map(|msg| msg.cid.0).collect();
+        assert_eq!(cids, vec![msgs[0].cid().unwrap(), msgs[1].cid().unwrap()]);
+
+        let receipts = chain_get_parent_receipts(Data(state.clone()), Params((blk_cid,)))
+            .await
+            .unwrap();
+        let receipts: Vec<_> = receipts.into_iter().map(|ReceiptJson(r)| r).collect();
+        assert_eq!(receipts, vec![receipt(1), receipt(2)]);
+
+        // The genesis block has no parents.
+        let genesis_cid = CidJson(*genesis.cids().first().unwrap());
+        assert!(
+            chain_get_parent_messages(Data(state.clone()), Params((genesis_cid.clone(),)))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            chain_get_parent_receipts(Data(state), Params((genesis_cid,)))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn tipset_weight() {
+        let (state, _) = state_setup();
+        let genesis = genesis(&state.chain_store);
+        let a1 = child(state.chain_store.blockstore(), &genesis, 1, &[], &[]);
+        let a2 = child(state.chain_store.blockstore(), &a1, 1, &[], &[]);
+
+        let weight = chain_tipset_weight::<_, _, EpochScale>(
+            Data(state.clone()),
+            Params((TipsetKeysJson(a2.key().clone()),)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(weight, "2");
+
+        // Unknown tipsets are an error rather than a zero weight.
+        let unknown = TipsetKeys::new(vec![message(0).cid().unwrap()]);
+        assert!(chain_tipset_weight::<_, _, EpochScale>(
+            Data(state),
+            Params((TipsetKeysJson(unknown),))
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn get_path() {
+        let (state, _) = state_setup();
+        let db = state.chain_store.blockstore();
+        let genesis = genesis(&state.chain_store);
+        let a1 = child(db, &genesis, 1, &[], &[]);
+        let a2 = child(db, &a1, 1, &[], &[]);
+        let b1 = child(db, &genesis, 2, &[], &[]);
+
+        let path = chain_get_path(
+            Data(state),
+            Params((
+                TipsetKeysJson(a2.key().clone()),
+                TipsetKeysJson(b1.key().clone()),
+            )),
+        )
+        .await
+        .unwrap();
+        let path: Vec<_> = path
+            .into_iter()
+            .map(|change| match change {
+                HeadChangeJson::Revert(TipsetJson(ts)) => ("revert", ts.key().clone()),
+                HeadChangeJson::Apply(TipsetJson(ts)) => ("apply", ts.key().clone()),
+                HeadChangeJson::Current(_) => panic!("unexpected current head change"),
+            })
+            .collect();
+        assert_eq!(
+            path,
+            vec![
+                ("revert", a2.key().clone()),
+                ("revert", a1.key().clone()),
+                ("apply", b1.key().clone()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stat_obj_excludes_base() {
+        let (state, _) = state_setup();
+        let db = state.chain_store.blockstore();
+        let leaf = db.put_obj(&"leaf", Blake2b256).unwrap();
+        let shared = db.put_obj(&"shared", Blake2b256).unwrap();
+        let base = db.put_obj(&vec![shared], Blake2b256).unwrap();
+        let obj = db.put_obj(&vec![leaf, shared], Blake2b256).unwrap();
+        let size = |cid: &Cid| db.get(cid).unwrap().unwrap().len() as u64;
+
+        let stat = chain_stat_obj(Data(state.clone()), Params((CidJson(obj), None)))
+            .await
+            .unwrap();
+        assert_eq!(stat.links, 3);
+        assert_eq!(stat.size, size(&obj) + size(&leaf) + size(&shared));
+
+        // Blocks reachable from the base are not counted.
+        let stat = chain_stat_obj(
+            Data(state.clone()),
+            Params((CidJson(obj), Some(CidJson(base)))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stat.links, 2);
+        assert_eq!(stat.size, size(&obj) + size(&leaf));
+
+        let missing = message(0).cid().unwrap();
+        assert!(
+            chain_stat_obj(Data(state), Params((CidJson(missing), None)))
+                .await
+                .is_err()
+        );
+    }
+}
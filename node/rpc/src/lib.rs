@@ -1,12 +1,14 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+mod actor_params;
 mod auth_api;
 mod beacon_api;
 mod chain_api;
 mod common_api;
 mod gas_api;
 mod mpool_api;
+mod msig_api;
 mod net_api;
 mod rpc_http_handler;
 mod rpc_util;
@@ -24,8 +26,8 @@ use forest_chain::Scale;
 use forest_db::Store;
 use forest_rpc_api::data_types::RPCState;
 use forest_rpc_api::{
-    auth_api::*, beacon_api::*, chain_api::*, common_api::*, gas_api::*, mpool_api::*, net_api::*,
-    state_api::*, sync_api::*, wallet_api::*,
+    auth_api::*, beacon_api::*, chain_api::*, common_api::*, gas_api::*, mpool_api::*, msig_api::*,
+    net_api::*, state_api::*, sync_api::*, wallet_api::*,
 };
 use fvm_ipld_blockstore::Blockstore;
 use jsonrpc_v2::{Data, Error as JSONRPCError, Server};
@@ -47,6 +49,7 @@ where
     use chain_api::*;
     use gas_api::*;
     use mpool_api::*;
+    use msig_api::*;
     use sync_api::*;
     use wallet_api::*;
 
@@ -57,6 +60,9 @@ where
             // Auth API
             .with_method(AUTH_NEW, auth_new::<DB, B>)
             .with_method(AUTH_VERIFY, auth_verify::<DB, B>)
+            .with_method(AUTH_LIST, auth_list::<DB, B>)
+            .with_method(AUTH_REVOKE, auth_revoke::<DB, B>)
+            .with_method(AUTH_ROTATE_SECRET, auth_rotate_secret::<DB, B>)
             // Beacon API
             .with_method(BEACON_GET_ENTRY, beacon_get_entry::<DB, B>)
             // Chain API
@@ -79,6 +85,29 @@ where
             .with_method(CHAIN_HEAD, chain_head::<DB, B>)
             .with_method(CHAIN_GET_BLOCK, chain_api::chain_get_block::<DB, B>)
             .with_method(CHAIN_GET_NAME, chain_api::chain_get_name::<DB, B>)
+            .with_method(
+                CHAIN_RECOMPUTE_STATE,
+                chain_api::chain_recompute_state::<DB, B>,
+            )
+            .with_method(
+                CHAIN_BACKFILL_MESSAGE_INDEX,
+                chain_api::chain_backfill_message_index::<DB, B>,
+            )
+            .with_method(CHAIN_GET_PATH, chain_api::chain_get_path::<DB, B>)
+            .with_method(CHAIN_GET_REORG_LOG, chain_api::chain_get_reorg_log::<DB, B>)
+            .with_method(
+                CHAIN_GET_PARENT_MESSAGES,
+                chain_api::chain_get_parent_messages::<DB, B>,
+            )
+            .with_method(
+                CHAIN_GET_PARENT_RECEIPTS,
+                chain_api::chain_get_parent_receipts::<DB, B>,
+            )
+            .with_method(
+                CHAIN_TIPSET_WEIGHT,
+                chain_api::chain_tipset_weight::<DB, B, S>,
+            )
+            .with_method(CHAIN_STAT_OBJ, chain_api::chain_stat_obj::<DB, B>)
             // Message Pool API
             .with_method(MPOOL_PENDING, mpool_pending::<DB, B>)
             .with_method(MPOOL_PUSH, mpool_push::<DB, B>)
@@ -87,32 +116,51 @@ where
             .with_method(SYNC_CHECK_BAD, sync_check_bad::<DB, B>)
             .with_method(SYNC_MARK_BAD, sync_mark_bad::<DB, B>)
             .with_method(SYNC_STATE, sync_state::<DB, B>)
+            .with_method(SYNC_CHECKPOINT, sync_checkpoint::<DB, B>)
+            .with_method(SYNC_CLEAR_CHECKPOINT, sync_clear_checkpoint::<DB, B>)
+            .with_method(SYNC_UNMARK_BAD, sync_unmark_bad::<DB, B>)
+            .with_method(SYNC_UNMARK_ALL_BAD, sync_unmark_all_bad::<DB, B>)
             // Wallet API
             .with_method(WALLET_BALANCE, wallet_balance::<DB, B>)
             .with_method(WALLET_DEFAULT_ADDRESS, wallet_default_address::<DB, B>)
+            .with_method(WALLET_DELETE, wallet_delete::<DB, B>)
             .with_method(WALLET_EXPORT, wallet_export::<DB, B>)
+            .with_method(WALLET_GET_LABEL, wallet_get_label::<DB, B>)
             .with_method(WALLET_HAS, wallet_has::<DB, B>)
             .with_method(WALLET_IMPORT, wallet_import::<DB, B>)
+            .with_method(WALLET_LABELS, wallet_labels::<DB, B>)
             .with_method(WALLET_LIST, wallet_list::<DB, B>)
             .with_method(WALLET_NEW, wallet_new::<DB, B>)
             .with_method(WALLET_SET_DEFAULT, wallet_set_default::<DB, B>)
+            .with_method(WALLET_SET_LABEL, wallet_set_label::<DB, B>)
             .with_method(WALLET_SIGN, wallet_sign::<DB, B>)
             .with_method(WALLET_VERIFY, wallet_verify::<DB, B>)
             // State API
             .with_method(STATE_CALL, state_call::<DB, B>)
             .with_method(STATE_REPLAY, state_replay::<DB, B>)
+            .with_method(STATE_COMPUTE_TIPSET, state_compute_tipset::<DB, B>)
             .with_method(STATE_NETWORK_NAME, state_network_name::<DB, B>)
             .with_method(STATE_NETWORK_VERSION, state_get_network_version::<DB, B>)
             .with_method(STATE_REPLAY, state_replay::<DB, B>)
+            .with_method(STATE_GET_ACTOR, state_get_actor::<DB, B>)
+            .with_method(STATE_ENCODE_PARAMS, state_encode_params::<DB, B>)
             .with_method(STATE_MARKET_BALANCE, state_market_balance::<DB, B>)
             .with_method(STATE_MARKET_DEALS, state_market_deals::<DB, B>)
             .with_method(STATE_GET_RECEIPT, state_get_receipt::<DB, B>)
             .with_method(STATE_WAIT_MSG, state_wait_msg::<DB, B>)
+            .with_method(STATE_SEARCH_MSG, state_search_msg::<DB, B>)
             // Gas API
             .with_method(GAS_ESTIMATE_FEE_CAP, gas_estimate_fee_cap::<DB, B>)
             .with_method(GAS_ESTIMATE_GAS_LIMIT, gas_estimate_gas_limit::<DB, B>)
             .with_method(GAS_ESTIMATE_GAS_PREMIUM, gas_estimate_gas_premium::<DB, B>)
             .with_method(GAS_ESTIMATE_MESSAGE_GAS, gas_estimate_message_gas::<DB, B>)
+            // Multisig API
+            .with_method(MSIG_CREATE, msig_create::<DB, B>)
+            .with_method(MSIG_PROPOSE, msig_propose::<DB, B>)
+            .with_method(MSIG_APPROVE, msig_approve::<DB, B>)
+            .with_method(MSIG_CANCEL, msig_cancel::<DB, B>)
+            .with_method(MSIG_GET_PENDING, msig_get_pending::<DB, B>)
+            .with_method(MSIG_GET_VESTED, msig_get_vested::<DB, B>)
             // Common API
             .with_method(VERSION, move || version(block_delay, forest_version))
             // Net API
@@ -120,6 +168,7 @@ where
             .with_method(NET_PEERS, net_api::net_peers::<DB, B>)
             .with_method(NET_CONNECT, net_api::net_connect::<DB, B>)
             .with_method(NET_DISCONNECT, net_api::net_disconnect::<DB, B>)
+            .with_method(NET_BITSWAP_STAT, net_api::net_bitswap_stat::<DB, B>)
             .finish_unwrapped(),
     );
 
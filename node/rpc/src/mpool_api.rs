@@ -11,11 +11,12 @@ use forest_json::cid::{vec::CidJsonVec, CidJson};
 use forest_json::message::json::MessageJson;
 use forest_json::signed_message::json::SignedMessageJson;
 use forest_message::SignedMessage;
-use forest_rpc_api::data_types::RPCState;
+use forest_rpc_api::data_types::{MessageSendSpec, RPCState};
 use forest_rpc_api::mpool_api::*;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::Cbor;
 use fvm_shared::address::Protocol;
+use fvm_shared::message::Message;
 use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
 use std::convert::TryFrom;
 
@@ -106,6 +107,22 @@ where
 {
     let (MessageJson(umsg), spec) = params;
 
+    let smsg = sign_and_push_message(&data, umsg, spec).await?;
+
+    Ok(SignedMessageJson(smsg))
+}
+
+/// Estimate the gas of a message, assign it the next nonce of its sender, sign it with the
+/// wallet of the node and add it to `mpool`
+pub(crate) async fn sign_and_push_message<DB, B>(
+    data: &Data<RPCState<DB, B>>,
+    umsg: Message,
+    spec: Option<MessageSendSpec>,
+) -> Result<SignedMessage, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
     let from = umsg.from;
 
     let mut keystore = data.keystore.as_ref().write().await;
@@ -120,7 +137,7 @@ where
             "Expected nonce for MpoolPushMessage is 0, and will be calculated for you.".into(),
         );
     }
-    let mut umsg = estimate_message_gas::<DB, B>(&data, umsg, spec, Default::default()).await?;
+    let mut umsg = estimate_message_gas::<DB, B>(data, umsg, spec, Default::default()).await?;
     if umsg.gas_premium > umsg.gas_fee_cap {
         return Err("After estimation, gas premium is greater than gas fee cap".into());
     }
@@ -144,5 +161,5 @@ where
 
     data.mpool.as_ref().push(smsg.clone()).await?;
 
-    Ok(SignedMessageJson(smsg))
+    Ok(smsg)
 }
@@ -0,0 +1,261 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use super::mpool_api::sign_and_push_message;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use forest_actor_interface::{init, multisig, system};
+use forest_beacon::Beacon;
+use forest_blocks::Tipset;
+use forest_db::Store;
+use forest_json::address::json::AddressJson;
+use forest_json::cid::CidJson;
+use forest_rpc_api::data_types::{MsigTransaction, RPCState};
+use forest_rpc_api::msig_api::*;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{Cbor, RawBytes};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
+use std::str::FromStr;
+
+/// Create a multisig wallet through the init actor, return the message CID
+pub(crate) async fn msig_create<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigCreateParams>,
+) -> Result<MsigCreateResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (required, signers, unlock_duration, value, AddressJson(from), gas_premium) = params;
+    let signers: Vec<Address> = signers.into_iter().map(|AddressJson(addr)| addr).collect();
+    if signers.is_empty() {
+        return Err("A multisig needs at least one signer".into());
+    }
+    if required == 0 || required > signers.len() as u64 {
+        return Err(format!(
+            "Required approvals must be between 1 and the number of signers ({})",
+            signers.len()
+        )
+        .into());
+    }
+
+    let heaviest_tipset = data.state_manager.chain_store().heaviest_tipset();
+    let system_actor = data
+        .state_manager
+        .get_actor(&system::ADDRESS, *heaviest_tipset.parent_state())?
+        .ok_or("System actor not found")?;
+    let code_cid = system::builtin_actor_code(
+        data.state_manager.blockstore(),
+        &system_actor,
+        multisig::MANIFEST_NAME,
+    )?;
+
+    // The vesting schedule, if any, starts at the current epoch
+    let constructor_params = multisig::ConstructorParams {
+        signers,
+        num_approvals_threshold: required,
+        unlock_duration,
+        start_epoch: heaviest_tipset.epoch(),
+    };
+    let exec_params = init::ExecParams {
+        code_cid,
+        constructor_params: RawBytes::serialize(constructor_params)?,
+    };
+
+    let message = Message {
+        from,
+        to: init::ADDRESS,
+        value: parse_token_amount(&value)?,
+        method_num: init::Method::Exec as u64,
+        params: RawBytes::serialize(exec_params)?,
+        gas_premium: parse_token_amount(&gas_premium)?,
+        ..Default::default()
+    };
+    push_message(&data, message).await
+}
+
+/// Propose a transaction to a multisig wallet, return the message CID
+pub(crate) async fn msig_propose<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigProposeParams>,
+) -> Result<MsigProposeResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), AddressJson(to), value, AddressJson(from), method, params) = params;
+
+    let propose_params = multisig::ProposeParams {
+        to,
+        value: parse_token_amount(&value)?,
+        method,
+        params: RawBytes::new(BASE64_STANDARD.decode(params)?),
+    };
+    let message = Message {
+        from,
+        to: msig,
+        method_num: multisig::Method::Propose as u64,
+        params: RawBytes::serialize(propose_params)?,
+        ..Default::default()
+    };
+    push_message(&data, message).await
+}
+
+/// Approve a pending transaction of a multisig wallet, return the message CID
+pub(crate) async fn msig_approve<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigApproveParams>,
+) -> Result<MsigApproveResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), txn_id, AddressJson(from)) = params;
+    let message = txn_id_message(&data, msig, txn_id, from, multisig::Method::Approve as u64)?;
+    push_message(&data, message).await
+}
+
+/// Cancel a pending transaction of a multisig wallet, return the message CID
+pub(crate) async fn msig_cancel<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigCancelParams>,
+) -> Result<MsigCancelResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), txn_id, AddressJson(from)) = params;
+    let message = txn_id_message(&data, msig, txn_id, from, multisig::Method::Cancel as u64)?;
+    push_message(&data, message).await
+}
+
+/// Return the pending transactions of a multisig wallet
+pub(crate) async fn msig_get_pending<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigGetPendingParams>,
+) -> Result<MsigGetPendingResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), tsk) = params;
+    let ts = data
+        .state_manager
+        .chain_store()
+        .tipset_from_keys(&tsk.into())?;
+    let state = load_multisig_state(&data, &msig, &ts)?;
+
+    Ok(state
+        .pending_transactions(data.state_manager.blockstore())?
+        .into_iter()
+        .map(|(id, txn)| MsigTransaction {
+            id: id.0,
+            to: txn.to.into(),
+            value: txn.value,
+            method: txn.method,
+            params: BASE64_STANDARD.encode(txn.params.bytes()),
+            approved: txn.approved.into_iter().map(AddressJson::from).collect(),
+        })
+        .collect())
+}
+
+/// Return the amount of funds of a multisig wallet vested between two tipsets
+pub(crate) async fn msig_get_vested<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<MsigGetVestedParams>,
+) -> Result<MsigGetVestedResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (AddressJson(msig), start_tsk, end_tsk) = params;
+    let chain_store = data.state_manager.chain_store();
+    let start = chain_store.tipset_from_keys(&start_tsk.into())?;
+    let end = chain_store.tipset_from_keys(&end_tsk.into())?;
+    if start.epoch() > end.epoch() {
+        return Err("Start tipset is after the end tipset".into());
+    }
+
+    let state = load_multisig_state(&data, &msig, &end)?;
+    let vested = state.locked_balance(start.epoch()) - state.locked_balance(end.epoch());
+    Ok(vested.atto().to_string())
+}
+
+/// Loads the state of a multisig actor at the given tipset, failing if the actor is not a
+/// multisig.
+fn load_multisig_state<DB, B>(
+    data: &Data<RPCState<DB, B>>,
+    msig: &Address,
+    ts: &Tipset,
+) -> Result<multisig::State, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let state_manager = &data.state_manager;
+    let actor = state_manager
+        .get_actor(msig, *ts.parent_state())?
+        .ok_or_else(|| format!("Actor {msig} not found"))?;
+    let system_actor = state_manager
+        .get_actor(&system::ADDRESS, *ts.parent_state())?
+        .ok_or("System actor not found")?;
+    let code = system::builtin_actor_code(
+        state_manager.blockstore(),
+        &system_actor,
+        multisig::MANIFEST_NAME,
+    )?;
+    if actor.code != code {
+        return Err(format!("Actor {msig} is not a multisig").into());
+    }
+    Ok(multisig::State::load(state_manager.blockstore(), &actor)?)
+}
+
+/// Builds a message approving or cancelling a pending transaction, identified by its ID and the
+/// hash of its proposal.
+fn txn_id_message<DB, B>(
+    data: &Data<RPCState<DB, B>>,
+    msig: Address,
+    txn_id: i64,
+    from: Address,
+    method_num: u64,
+) -> Result<Message, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let heaviest_tipset = data.state_manager.chain_store().heaviest_tipset();
+    let state = load_multisig_state(data, &msig, &heaviest_tipset)?;
+    let txn = state.pending_transaction(data.state_manager.blockstore(), txn_id)?;
+
+    let params = multisig::TxnIDParams {
+        id: multisig::TxnID(txn_id),
+        proposal_hash: multisig::proposal_hash(&txn)?,
+    };
+    Ok(Message {
+        from,
+        to: msig,
+        method_num,
+        params: RawBytes::serialize(params)?,
+        ..Default::default()
+    })
+}
+
+async fn push_message<DB, B>(
+    data: &Data<RPCState<DB, B>>,
+    message: Message,
+) -> Result<CidJson, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let smsg = sign_and_push_message(data, message, None).await?;
+    Ok(CidJson(smsg.cid()?))
+}
+
+fn parse_token_amount(amount: &str) -> Result<TokenAmount, JsonRpcError> {
+    Ok(TokenAmount::from_atto(BigInt::from_str(amount)?))
+}
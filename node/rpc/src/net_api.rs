@@ -7,9 +7,10 @@ use log::error;
 
 use forest_beacon::Beacon;
 use forest_db::Store;
+use forest_json::cid::CidJson;
 use forest_libp2p::{NetRPCMethods, NetworkMessage, PeerId};
 use forest_rpc_api::{
-    data_types::{AddrInfo, RPCState},
+    data_types::{AddrInfo, BitswapLedger, BitswapStat, RPCState},
     net_api::*,
 };
 use fvm_ipld_blockstore::Blockstore;
@@ -105,3 +106,42 @@ pub(crate) async fn net_disconnect<
 
     Ok(())
 }
+
+pub(crate) async fn net_bitswap_stat<
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<NetBitswapStatResult, JsonRpcError> {
+    let (tx, rx) = oneshot::channel();
+    let req = NetworkMessage::JSONRPCRequest {
+        method: NetRPCMethods::NetBitswapStats(tx),
+    };
+
+    data.network_send.send_async(req).await?;
+    let stats = rx.await?;
+
+    Ok(BitswapStat {
+        serving: stats.serving,
+        wantlist: stats.wantlist.into_iter().map(CidJson).collect(),
+        peers: stats.peers.iter().map(|peer| peer.to_string()).collect(),
+        blocks_received: stats.blocks_received,
+        data_received: stats.data_received,
+        blocks_sent: stats.blocks_sent,
+        data_sent: stats.data_sent,
+        dup_blks_received: stats.dup_blocks_received,
+        dup_data_received: stats.dup_data_received,
+        queued_wants: stats.queued_wants as u64,
+        ledgers: stats
+            .ledgers
+            .into_iter()
+            .map(|(peer, ledger)| BitswapLedger {
+                peer: peer.to_string(),
+                value: ledger.debt_ratio(),
+                sent: ledger.bytes_sent,
+                recv: ledger.bytes_received,
+                exchanged: ledger.exchanged,
+            })
+            .collect(),
+    })
+}
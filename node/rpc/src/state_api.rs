@@ -2,13 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 #![allow(clippy::unused_async)]
 
+use crate::actor_params::encode_params;
 use ahash::{HashMap, HashMapExt};
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
 use cid::Cid;
-use forest_actor_interface::market;
+use forest_actor_interface::{market, system};
 use forest_beacon::Beacon;
-use forest_blocks::tipset_keys_json::TipsetKeysJson;
+use forest_blocks::{tipset_keys_json::TipsetKeysJson, Tipset};
 use forest_db::Store;
+use forest_interpreter::VMTrace;
 use forest_ipld::json::IpldJson;
+use forest_json::actor_state::json::ActorStateJson;
+use forest_json::address::json::AddressJson;
 use forest_json::cid::CidJson;
 use forest_rpc_api::{
     data_types::{MarketDeal, MessageLookup, RPCState},
@@ -16,6 +22,8 @@ use forest_rpc_api::{
 };
 use forest_state_manager::InvocResult;
 use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::receipt::Receipt;
 use jsonrpc_v2::{Data, Error as JsonRpcError, Params};
 use libipld_core::ipld::Ipld;
 
@@ -36,7 +44,12 @@ pub(crate) async fn state_call<
         .state_manager
         .chain_store()
         .tipset_from_keys(&key.into())?;
-    Ok(state_manager.call(&mut message, Some(tipset))?)
+    match &data.fetch_on_miss_store {
+        Some(store) => {
+            Ok(state_manager.call_with_store(&mut message, Some(tipset), store.for_call())?)
+        }
+        None => Ok(state_manager.call(&mut message, Some(tipset))?),
+    }
 }
 
 /// returns the result of executing the indicated message, assuming it was executed in the indicated tipset.
@@ -54,13 +67,23 @@ pub(crate) async fn state_replay<
         .state_manager
         .chain_store()
         .tipset_from_keys(&key.into())?;
-    let (msg, ret) = state_manager.replay(&tipset, cid).await?;
+    let (msg, ret) = state_manager.replay(&tipset, cid, VMTrace::Traced).await?;
 
-    Ok(InvocResult {
-        msg,
-        msg_rct: Some(ret.msg_receipt),
-        error: ret.failure_info.map(|e| e.to_string()),
-    })
+    Ok(InvocResult::from_apply_ret(msg, &ret))
+}
+
+/// recomputes the state of the indicated tipset, returning the state root and the execution
+/// trace of every message applied.
+pub(crate) async fn state_compute_tipset<
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<StateComputeTipSetParams>,
+) -> Result<StateComputeTipSetResult, JsonRpcError> {
+    let (TipsetKeysJson(tsk),) = params;
+    let tipset = data.chain_store.tipset_from_keys(&tsk)?;
+    Ok(data.state_manager.compute_state_traced(&tipset).await?)
 }
 
 /// gets network name from state manager
@@ -90,6 +113,50 @@ pub(crate) async fn state_get_network_version<
     Ok(data.state_manager.get_network_version(ts.epoch()))
 }
 
+/// returns the actor at the given address in the state of the given tipset, if any
+pub(crate) async fn state_get_actor<
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<StateGetActorParams>,
+) -> Result<StateGetActorResult, JsonRpcError> {
+    let (AddressJson(address), TipsetKeysJson(tsk)) = params;
+    let ts = data.chain_store.tipset_from_keys(&tsk)?;
+    let actor = data.state_manager.get_actor(&address, *ts.parent_state())?;
+    Ok(actor.map(ActorStateJson))
+}
+
+/// encodes the JSON parameters of a method of the actor with the given code to CBOR. The
+/// parameters of built-in actor methods use the Lotus JSON layout, others are read as DAG-JSON.
+pub(crate) async fn state_encode_params<
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<StateEncodeParamsParams>,
+) -> Result<StateEncodeParamsResult, JsonRpcError> {
+    let (CidJson(code), method, params) = params;
+    let state_manager = &data.state_manager;
+    let heaviest_tipset = state_manager.chain_store().heaviest_tipset();
+    let system_actor = state_manager
+        .get_actor(&system::ADDRESS, *heaviest_tipset.parent_state())?
+        .ok_or("System actor not found")?;
+    let name = system::builtin_actor_name(state_manager.blockstore(), &system_actor, &code)?;
+    let encoded = match name {
+        Some(name) => encode_params(&name, method, params.clone())?,
+        None => None,
+    };
+    let encoded = match encoded {
+        Some(encoded) => encoded,
+        None => {
+            let IpldJson(ipld) = serde_json::from_value(params)?;
+            RawBytes::serialize(ipld)?
+        }
+    };
+    Ok(BASE64_STANDARD.encode(encoded.bytes()))
+}
+
 /// looks up the Escrow and Locked balances of the given address in the Storage Market
 pub(crate) async fn state_market_balance<
     DB: Blockstore + Store + Clone + Send + Sync + 'static,
@@ -181,6 +248,29 @@ pub(crate) async fn state_wait_msg<
     let (tipset, receipt) = state_manager.wait_for_message(cid, confidence).await?;
     let tipset = tipset.ok_or("wait for msg returned empty tuple")?;
     let receipt = receipt.ok_or("wait for msg returned empty receipt")?;
+    message_lookup(cid, &tipset, receipt)
+}
+
+/// looks back in the chain for a message, without waiting for it to arrive on chain.
+pub(crate) async fn state_search_msg<
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<StateSearchMsgParams>,
+) -> Result<StateSearchMsgResult, JsonRpcError> {
+    let (CidJson(cid),) = params;
+    data.state_manager
+        .search_for_message(cid)?
+        .map(|(tipset, receipt)| message_lookup(cid, &tipset, receipt))
+        .transpose()
+}
+
+fn message_lookup(
+    cid: Cid,
+    tipset: &Tipset,
+    receipt: Receipt,
+) -> Result<MessageLookup, JsonRpcError> {
     let ipld: Ipld = if receipt.return_data.bytes().is_empty() {
         Ipld::Null
     } else {
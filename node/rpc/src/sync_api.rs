@@ -3,7 +3,9 @@
 #![allow(clippy::unused_async)]
 
 use forest_beacon::Beacon;
+use forest_blocks::tipset_keys_json::TipsetKeysJson;
 use forest_chain_sync::SyncState;
+use forest_db::Store;
 use forest_json::cid::CidJson;
 use forest_rpc_api::data_types::{RPCState, RPCSyncState};
 use forest_rpc_api::sync_api::*;
@@ -17,7 +19,7 @@ pub(crate) async fn sync_check_bad<DB, B>(
     Params(params): Params<SyncCheckBadParams>,
 ) -> Result<SyncCheckBadResult, JsonRpcError>
 where
-    DB: Blockstore,
+    DB: Blockstore + Store,
     B: Beacon,
 {
     let (CidJson(cid),) = params;
@@ -30,12 +32,73 @@ pub(crate) async fn sync_mark_bad<DB, B>(
     Params(params): Params<SyncMarkBadParams>,
 ) -> Result<SyncMarkBadResult, JsonRpcError>
 where
-    DB: Blockstore,
+    DB: Blockstore + Store,
     B: Beacon,
 {
     let (CidJson(cid),) = params;
     data.bad_blocks
-        .put(cid, "Marked bad manually through RPC API".to_string());
+        .put_permanent(cid, "Marked bad manually through RPC API".to_string());
+    Ok(())
+}
+
+/// Removes a block from the bad blocks, so that it can be synced again.
+pub(crate) async fn sync_unmark_bad<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<SyncUnmarkBadParams>,
+) -> Result<SyncUnmarkBadResult, JsonRpcError>
+where
+    DB: Blockstore + Store,
+    B: Beacon,
+{
+    let (CidJson(cid),) = params;
+    data.bad_blocks.remove(&cid);
+    Ok(())
+}
+
+/// Removes all the blocks from the bad blocks.
+pub(crate) async fn sync_unmark_all_bad<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<SyncUnmarkAllBadResult, JsonRpcError>
+where
+    DB: Blockstore + Store,
+    B: Beacon,
+{
+    data.bad_blocks.clear();
+    Ok(())
+}
+
+/// Pins the node to the chain of a tipset: chains that don't include it are rejected, and the
+/// head is moved to it if the heaviest chain doesn't. The checkpoint is persisted.
+pub(crate) async fn sync_checkpoint<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<SyncCheckpointParams>,
+) -> Result<SyncCheckpointResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    let (TipsetKeysJson(tsk),) = params;
+    if tsk.cids().is_empty() {
+        return Err("checkpoint tipset keys can't be empty".into());
+    }
+    let chain_store = data.chain_store.clone();
+    tokio::task::spawn_blocking(move || {
+        let ts = chain_store.tipset_from_keys(&tsk)?;
+        chain_store.set_checkpoint(ts)
+    })
+    .await??;
+    Ok(())
+}
+
+/// Removes the checkpoint, so that the node follows the heaviest chain again.
+pub(crate) async fn sync_clear_checkpoint<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<SyncClearCheckpointResult, JsonRpcError>
+where
+    DB: Blockstore + Store + Clone + Send + Sync + 'static,
+    B: Beacon,
+{
+    data.chain_store.clear_checkpoint()?;
     Ok(())
 }
 
@@ -52,19 +115,22 @@ where
     B: Beacon,
 {
     let active_syncs = vec![clone_state(data.sync_state.as_ref()).await];
-    Ok(RPCSyncState { active_syncs })
+    Ok(RPCSyncState {
+        active_syncs,
+        clock_drift: data.clock_drift.estimate_millis(),
+    })
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use forest_beacon::{BeaconPoint, BeaconSchedule, MockBeacon};
     use forest_blocks::{BlockHeader, Tipset};
     use forest_chain::ChainStore;
-    use forest_chain_sync::SyncStage;
+    use forest_chain_sync::{BadBlockCache, ClockDriftEstimator, SyncStage};
     use forest_db::{MemoryDB, Store};
     use forest_key_management::{KeyStore, KeyStoreConfig};
-    use forest_libp2p::NetworkMessage;
+    use forest_libp2p::{NetworkMessage, PeerManager};
     use forest_message_pool::{MessagePool, MpoolRpcProvider};
     use forest_networks::ChainConfig;
     use forest_state_manager::StateManager;
@@ -76,7 +142,7 @@ mod tests {
 
     const TEST_NET_NAME: &str = "test";
 
-    fn state_setup() -> (
+    pub(crate) fn state_setup() -> (
         Arc<RPCState<MemoryDB, MockBeacon>>,
         flume::Receiver<NetworkMessage>,
     ) {
@@ -140,14 +206,21 @@ mod tests {
         let state = Arc::new(RPCState {
             state_manager,
             keystore: Arc::new(RwLock::new(KeyStore::new(KeyStoreConfig::Memory).unwrap())),
+            token_registry: Default::default(),
             mpool: Arc::new(pool),
-            bad_blocks: Default::default(),
+            bad_blocks: Arc::new(BadBlockCache::new(
+                cs_for_chain.blockstore().clone(),
+                Arc::new(PeerManager::default()),
+                None,
+            )),
             sync_state: Arc::new(parking_lot::RwLock::new(Default::default())),
+            clock_drift: Arc::new(ClockDriftEstimator::new(Duration::from_secs(1))),
             network_send,
             network_name: TEST_NET_NAME.to_owned(),
             chain_store: cs_for_chain,
             beacon,
             new_mined_block_tx,
+            fetch_on_miss_store: None,
         });
         (state, network_rx)
     }
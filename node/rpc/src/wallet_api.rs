@@ -62,6 +62,21 @@ where
     Ok(addr.to_string())
 }
 
+/// Delete the key of an Address from the Wallet
+pub(crate) async fn wallet_delete<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<WalletDeleteParams>,
+) -> Result<WalletDeleteResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    let (address,) = params;
+    let mut keystore = data.keystore.write().await;
+    forest_key_management::delete_key(&address.0, &mut keystore)?;
+    Ok(())
+}
+
 /// Export `KeyInfo` from the Wallet given its address
 pub(crate) async fn wallet_export<DB, B>(
     data: Data<RPCState<DB, B>>,
@@ -80,6 +95,31 @@ where
     Ok(KeyInfoJson(key_info))
 }
 
+/// Return the label of an Address of the Wallet, if any
+pub(crate) async fn wallet_get_label<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<WalletGetLabelParams>,
+) -> Result<WalletGetLabelResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    let (address,) = params;
+    let keystore = data.keystore.read().await;
+    Ok(forest_key_management::get_label(&address.0, &keystore))
+}
+
+/// Return the labels of the Addresses of the Wallet, indexed by Address
+pub(crate) async fn wallet_labels<DB, B>(
+    data: Data<RPCState<DB, B>>,
+) -> Result<WalletLabelsResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    Ok(data.keystore.read().await.labels().clone())
+}
+
 /// Return whether or not a Key is in the Wallet
 pub(crate) async fn wallet_has<DB, B>(
     data: Data<RPCState<DB, B>>,
@@ -188,6 +228,21 @@ where
     Ok(())
 }
 
+/// Set the label of an Address of the Wallet, an empty label removes it
+pub(crate) async fn wallet_set_label<DB, B>(
+    data: Data<RPCState<DB, B>>,
+    Params(params): Params<WalletSetLabelParams>,
+) -> Result<WalletSetLabelResult, JsonRpcError>
+where
+    DB: Blockstore,
+    B: Beacon,
+{
+    let (address, label) = params;
+    let mut keystore = data.keystore.write().await;
+    forest_key_management::set_label(&address.0, &label, &mut keystore)?;
+    Ok(())
+}
+
 /// Sign a vector of bytes
 pub(crate) async fn wallet_sign<DB, B>(
     data: Data<RPCState<DB, B>>,
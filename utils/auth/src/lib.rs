@@ -1,8 +1,9 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::collections::BTreeMap;
+
 use chrono::{Duration, Utc};
-use jsonrpc_v2::Error as JsonRpcError;
 use jsonwebtoken::errors::Result as JWTResult;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header};
 use once_cell::sync::Lazy;
@@ -10,11 +11,14 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use forest_db::Store;
 use forest_key_management::KeyInfo;
 use fvm_shared::crypto::signature::SignatureType;
 
 /// constant string that is used to identify the JWT secret key in `KeyStore`
 pub const JWT_IDENTIFIER: &str = "auth-jwt-private";
+/// Key under which the registry of issued tokens is persisted in the `Store`
+pub const JWT_TOKENS_KEY: &str = "auth-jwt-tokens";
 /// Admin permissions
 pub static ADMIN: Lazy<Vec<String>> = Lazy::new(|| {
     vec![
@@ -44,6 +48,12 @@ pub enum Error {
     /// Missing authentication header
     #[error("Missing authentication header")]
     NoAuthHeader,
+    /// Token has been revoked
+    #[error("Token has been revoked")]
+    RevokedToken,
+    /// Token identifier is not known to the registry
+    #[error("Token not found")]
+    TokenNotFound,
     #[error("{0}")]
     Other(String),
 }
@@ -55,35 +65,174 @@ struct Claims {
     allow: Vec<String>,
     // Expiration time (as UTC timestamp)
     exp: usize,
+    // Token identifier, absent on tokens issued before revocation support
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    jti: Option<String>,
+    // Issued at time (as UTC timestamp)
+    #[serde(default)]
+    iat: usize,
+    // Human readable label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+}
+
+/// Information about an issued token, as recorded in the [`TokenRegistry`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TokenInfo {
+    pub id: String,
+    pub label: Option<String>,
+    pub perms: Vec<String>,
+    pub issued_at: usize,
+    pub expires_at: usize,
+    pub revoked: bool,
+}
+
+impl TokenInfo {
+    /// Returns `true` if the token expiration time is in the past
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Utc::now().timestamp() as usize
+    }
+}
+
+impl Claims {
+    fn into_token_info(self, token: &str) -> TokenInfo {
+        TokenInfo {
+            id: self.jti.unwrap_or_else(|| legacy_token_id(token)),
+            label: self.label,
+            perms: self.allow,
+            issued_at: self.iat,
+            expires_at: self.exp,
+            revoked: false,
+        }
+    }
+}
+
+/// Registry of issued tokens and their revocation status, persisted in the
+/// `Store` under [`JWT_TOKENS_KEY`]
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenRegistry {
+    tokens: BTreeMap<String, TokenInfo>,
+}
+
+impl TokenRegistry {
+    /// Loads the registry from the `Store`, returning an empty registry if none was persisted
+    pub fn load<DB: Store>(db: &DB) -> anyhow::Result<Self> {
+        match db.read(JWT_TOKENS_KEY)? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Persists the registry to the `Store`, dropping expired tokens
+    pub fn flush<DB: Store>(&mut self, db: &DB) -> anyhow::Result<()> {
+        self.tokens.retain(|_, info| !info.is_expired());
+        db.write(JWT_TOKENS_KEY, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    /// Records a newly issued token
+    pub fn register(&mut self, info: TokenInfo) {
+        self.tokens.insert(info.id.clone(), info);
+    }
+
+    /// Marks the token with the given identifier as revoked
+    pub fn revoke(&mut self, id: &str) -> Result<(), Error> {
+        let info = self.tokens.get_mut(id).ok_or(Error::TokenNotFound)?;
+        info.revoked = true;
+        Ok(())
+    }
+
+    /// Returns `true` if the token with the given identifier is recorded
+    pub fn contains(&self, id: &str) -> bool {
+        self.tokens.contains_key(id)
+    }
+
+    /// Returns `true` if the token with the given identifier has been revoked
+    pub fn is_revoked(&self, id: &str) -> bool {
+        self.tokens
+            .get(id)
+            .map(|info| info.revoked)
+            .unwrap_or(false)
+    }
+
+    /// Forgets every issued token, used when the JWT secret is rotated
+    pub fn clear(&mut self) {
+        self.tokens.clear();
+    }
+
+    /// Returns all non-expired tokens, ordered by identifier
+    pub fn list(&self) -> Vec<TokenInfo> {
+        self.tokens
+            .values()
+            .filter(|info| !info.is_expired())
+            .cloned()
+            .collect()
+    }
 }
 
 /// Create a new JWT Token
 pub fn create_token(perms: Vec<String>, key: &[u8], token_exp: Duration) -> JWTResult<String> {
-    let exp_time = Utc::now() + token_exp;
+    create_labeled_token(perms, key, token_exp, None).map(|(token, _)| token)
+}
+
+/// Create a new JWT Token with a fresh identifier and an optional label, returning the
+/// token alongside the information to record in the [`TokenRegistry`]
+pub fn create_labeled_token(
+    perms: Vec<String>,
+    key: &[u8],
+    token_exp: Duration,
+    label: Option<String>,
+) -> JWTResult<(String, TokenInfo)> {
+    let now = Utc::now();
+    let exp_time = now + token_exp;
     let payload = Claims {
         allow: perms,
         exp: exp_time.timestamp() as usize,
+        jti: Some(generate_token_id()),
+        iat: now.timestamp() as usize,
+        label,
     };
-    encode(&Header::default(), &payload, &EncodingKey::from_secret(key))
+    let token = encode(&Header::default(), &payload, &EncodingKey::from_secret(key))?;
+    let info = payload.into_token_info(&token);
+    Ok((token, info))
+}
+
+/// Decode and verify a JWT Token, returning its recorded information
+pub fn decode_token(token: &str, key: &[u8]) -> JWTResult<TokenInfo> {
+    let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::default());
+    let decoded = decode::<Claims>(token, &DecodingKey::from_secret(key), &validation)?;
+    Ok(decoded.claims.into_token_info(token))
 }
 
 /// Verify JWT Token and return the allowed permissions from token
 pub fn verify_token(token: &str, key: &[u8]) -> JWTResult<Vec<String>> {
-    let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::default());
-    let token = decode::<Claims>(token, &DecodingKey::from_secret(key), &validation)?;
-    Ok(token.claims.allow)
+    decode_token(token, key).map(|info| info.perms)
 }
 
-/// Check whether or not header has required permissions
-pub fn has_perms(header_raw: String, required: &str, key: &[u8]) -> Result<(), JsonRpcError> {
-    if header_raw.starts_with("Bearer ") {
-        let token = header_raw.trim_start_matches("Bearer ");
-        let perms = verify_token(token, key).map_err(|err| Error::Other(err.to_string()))?;
-        if !perms.contains(&required.to_string()) {
-            return Err(JsonRpcError::from(Error::InvalidPermissions));
-        }
+/// Verify JWT Token against the revocation list and return the allowed permissions from token
+pub fn verify_unrevoked_token(
+    token: &str,
+    key: &[u8],
+    registry: &TokenRegistry,
+) -> Result<Vec<String>, Error> {
+    let info = decode_token(token, key).map_err(|err| Error::Other(err.to_string()))?;
+    if registry.is_revoked(&info.id) {
+        return Err(Error::RevokedToken);
     }
-    Ok(())
+    Ok(info.perms)
+}
+
+fn generate_token_id() -> String {
+    format!("{:032x}", rand::thread_rng().gen::<u128>())
+}
+
+/// Derives the identifier of a token issued before tokens had one
+fn legacy_token_id(token: &str) -> String {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(16)
+        .hash(token.as_bytes());
+    hash.to_hex().to_string()
 }
 
 pub fn generate_priv_key() -> KeyInfo {
@@ -92,3 +241,103 @@ pub fn generate_priv_key() -> KeyInfo {
     // for key type
     KeyInfo::new(SignatureType::BLS, priv_key.to_vec())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use forest_db::MemoryDB;
+
+    #[test]
+    fn labeled_token_roundtrip() {
+        let key = generate_priv_key();
+        let (token, info) = create_labeled_token(
+            ADMIN.clone(),
+            key.private_key(),
+            Duration::hours(1),
+            Some("indexer".to_owned()),
+        )
+        .unwrap();
+        let decoded = decode_token(&token, key.private_key()).unwrap();
+        assert_eq!(decoded, info);
+        assert_eq!(decoded.label.as_deref(), Some("indexer"));
+        assert_eq!(decoded.id.len(), 32);
+    }
+
+    #[test]
+    fn revoked_token_is_rejected() {
+        let key = generate_priv_key();
+        let db = MemoryDB::default();
+        let mut registry = TokenRegistry::load(&db).unwrap();
+        let (token, info) =
+            create_labeled_token(READ.clone(), key.private_key(), Duration::hours(1), None)
+                .unwrap();
+        registry.register(info.clone());
+        registry.flush(&db).unwrap();
+
+        assert_eq!(
+            verify_unrevoked_token(&token, key.private_key(), &registry).unwrap(),
+            *READ
+        );
+
+        let mut registry = TokenRegistry::load(&db).unwrap();
+        registry.revoke(&info.id).unwrap();
+        registry.flush(&db).unwrap();
+
+        let registry = TokenRegistry::load(&db).unwrap();
+        assert!(registry.is_revoked(&info.id));
+        assert!(matches!(
+            verify_unrevoked_token(&token, key.private_key(), &registry),
+            Err(Error::RevokedToken)
+        ));
+    }
+
+    #[test]
+    fn legacy_token_can_be_revoked() {
+        let key = generate_priv_key();
+        let claims = Claims {
+            allow: READ.clone(),
+            exp: (Utc::now() + Duration::hours(1)).timestamp() as usize,
+            jti: None,
+            iat: 0,
+            label: None,
+        };
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(key.private_key()),
+        )
+        .unwrap();
+
+        let info = decode_token(&token, key.private_key()).unwrap();
+        assert_eq!(info.id, legacy_token_id(&token));
+        assert_eq!(info.id.len(), 32);
+
+        let mut registry = TokenRegistry::default();
+        registry.register(info.clone());
+        registry.revoke(&info.id).unwrap();
+        assert!(matches!(
+            verify_unrevoked_token(&token, key.private_key(), &registry),
+            Err(Error::RevokedToken)
+        ));
+    }
+
+    #[test]
+    fn revoke_unknown_token() {
+        let mut registry = TokenRegistry::default();
+        assert!(matches!(registry.revoke("foo"), Err(Error::TokenNotFound)));
+    }
+
+    #[test]
+    fn expired_tokens_are_pruned() {
+        let key = generate_priv_key();
+        let db = MemoryDB::default();
+        let mut registry = TokenRegistry::default();
+        let (_, info) =
+            create_labeled_token(READ.clone(), key.private_key(), Duration::hours(-1), None)
+                .unwrap();
+        registry.register(info);
+        assert!(registry.list().is_empty());
+        registry.flush(&db).unwrap();
+        assert_eq!(TokenRegistry::load(&db).unwrap(), TokenRegistry::default());
+    }
+}
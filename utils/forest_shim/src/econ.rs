@@ -3,9 +3,10 @@
 
 use fvm_shared::econ::TokenAmount as TokenAmount_v2;
 use fvm_shared3::econ::TokenAmount as TokenAmount_v3;
-use num_bigint::BigInt;
+use num_bigint::{BigInt, BigUint, Sign};
 use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign, Mul};
+use std::str::FromStr;
 
 // FIXME: Transparent Debug trait impl
 // FIXME: Consider 'type TokenAmount = TokenAmount_v3'
@@ -104,3 +105,302 @@ impl AddAssign for TokenAmount {
         self.0.add_assign(other.0)
     }
 }
+
+/// Denominations of FIL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Atto,
+    Femto,
+    Pico,
+    Nano,
+    Micro,
+    Milli,
+    Fil,
+}
+
+impl Unit {
+    const ALL: [Unit; 7] = [
+        Unit::Fil,
+        Unit::Milli,
+        Unit::Micro,
+        Unit::Nano,
+        Unit::Pico,
+        Unit::Femto,
+        Unit::Atto,
+    ];
+
+    /// Number of decimal places between the unit and attoFIL.
+    fn exponent(self) -> u32 {
+        match self {
+            Unit::Atto => 0,
+            Unit::Femto => 3,
+            Unit::Pico => 6,
+            Unit::Nano => 9,
+            Unit::Micro => 12,
+            Unit::Milli => 15,
+            Unit::Fil => 18,
+        }
+    }
+
+    fn scale(self) -> BigUint {
+        BigUint::from(10u32).pow(self.exponent())
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Unit::Atto => "attoFIL",
+            Unit::Femto => "femtoFIL",
+            Unit::Pico => "picoFIL",
+            Unit::Nano => "nanoFIL",
+            Unit::Micro => "microFIL",
+            Unit::Milli => "milliFIL",
+            Unit::Fil => "FIL",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Unit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Unit::ALL
+            .into_iter()
+            .find(|unit| unit.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow::anyhow!("Unknown FIL denomination: {s}"))
+    }
+}
+
+/// Token amount written with a FIL denomination, e.g. `1.5 FIL`, `200 nanoFIL` or
+/// `10 attoFIL`. Amounts without a denomination are in attoFIL, as in Lotus.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct FIL(pub TokenAmount);
+
+impl FIL {
+    /// Formats the amount in the given unit, with at most `max_decimals` decimal places if set.
+    /// The amount is truncated, not rounded.
+    pub fn format_in(&self, unit: Unit, max_decimals: Option<usize>) -> String {
+        let atto = self.0.atto();
+        let sign = if atto.sign() == Sign::Minus { "-" } else { "" };
+        let scale = unit.scale();
+        let integer = atto.magnitude() / &scale;
+        let fraction = (atto.magnitude() % &scale).to_string();
+
+        let mut decimals = format!("{fraction:0>width$}", width = unit.exponent() as usize);
+        if let Some(max_decimals) = max_decimals {
+            decimals.truncate(max_decimals);
+        }
+        let decimals = decimals.trim_end_matches('0');
+        if decimals.is_empty() {
+            format!("{sign}{integer} {unit}")
+        } else {
+            format!("{sign}{integer}.{decimals} {unit}")
+        }
+    }
+
+    /// Formats the amount in the largest unit in which it is at least one, with at most four
+    /// decimal places, e.g. `1.2345 FIL` or `12 nanoFIL`.
+    pub fn format_human(&self) -> String {
+        let magnitude = self.0.atto().magnitude();
+        let unit = Unit::ALL
+            .into_iter()
+            .find(|unit| magnitude >= &unit.scale())
+            .unwrap_or(Unit::Fil);
+        self.format_in(unit, Some(4))
+    }
+}
+
+impl std::fmt::Display for FIL {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.format_in(Unit::Fil, None))
+    }
+}
+
+impl FromStr for FIL {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let (number, unit) = (number.trim(), unit.trim());
+        let unit = if unit.is_empty() {
+            Unit::Atto
+        } else {
+            unit.parse()?
+        };
+
+        let (integer, decimals) = number.split_once('.').unwrap_or((number, ""));
+        let decimals = decimals.trim_end_matches('0');
+        if integer.is_empty() && decimals.is_empty() {
+            anyhow::bail!("Invalid FIL amount: {s}");
+        }
+        if !integer
+            .chars()
+            .chain(decimals.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            anyhow::bail!("Invalid FIL amount: {s}");
+        }
+        if decimals.len() > unit.exponent() as usize {
+            anyhow::bail!("Too many decimal places for {unit}: {s}");
+        }
+
+        let digits = format!(
+            "{integer}{decimals:0<width$}",
+            width = unit.exponent() as usize
+        );
+        let atto = BigInt::from_str(&digits)?;
+        Ok(FIL(TokenAmount::from_atto(atto)))
+    }
+}
+
+impl From<TokenAmount> for FIL {
+    fn from(amount: TokenAmount) -> Self {
+        FIL(amount)
+    }
+}
+
+impl From<FIL> for TokenAmount {
+    fn from(amount: FIL) -> Self {
+        amount.0
+    }
+}
+
+impl From<FIL> for TokenAmount_v2 {
+    fn from(amount: FIL) -> Self {
+        amount.0.into()
+    }
+}
+
+impl From<TokenAmount_v2> for FIL {
+    fn from(amount: TokenAmount_v2) -> Self {
+        FIL(amount.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fil(atto: u128) -> FIL {
+        FIL(TokenAmount::from_atto(atto))
+    }
+
+    #[test]
+    fn unit_from_str() {
+        for unit in Unit::ALL {
+            assert_eq!(unit.to_string().parse::<Unit>().unwrap(), unit);
+        }
+        assert_eq!("nanofil".parse::<Unit>().unwrap(), Unit::Nano);
+        assert_eq!("MILLIFIL".parse::<Unit>().unwrap(), Unit::Milli);
+        assert!("".parse::<Unit>().is_err());
+        assert!("nano".parse::<Unit>().is_err());
+        assert!("kiloFIL".parse::<Unit>().is_err());
+    }
+
+    #[test]
+    fn fil_from_str() {
+        let cases = [
+            ("1 FIL", 1_000_000_000_000_000_000),
+            ("1 milliFIL", 1_000_000_000_000_000),
+            ("1 microFIL", 1_000_000_000_000),
+            ("1 nanoFIL", 1_000_000_000),
+            ("1 picoFIL", 1_000_000),
+            ("1 femtoFIL", 1_000),
+            ("1 attoFIL", 1),
+            ("200nanoFIL", 200_000_000_000),
+            ("  2.5 fil ", 2_500_000_000_000_000_000),
+            (".5 FIL", 500_000_000_000_000_000),
+            ("3. FIL", 3_000_000_000_000_000_000),
+            ("0 FIL", 0),
+        ];
+        for (s, atto) in cases {
+            assert_eq!(s.parse::<FIL>().unwrap(), fil(atto), "{s}");
+        }
+        // Amounts without a denomination are in attoFIL
+        assert_eq!("100000".parse::<FIL>().unwrap(), fil(100_000));
+        assert!("1.5".parse::<FIL>().is_err());
+    }
+
+    #[test]
+    fn fil_from_str_precision() {
+        // Trailing zeros don't count as decimal places
+        assert_eq!("1.000 attoFIL".parse::<FIL>().unwrap(), fil(1));
+        assert_eq!("1.5 femtoFIL".parse::<FIL>().unwrap(), fil(1_500));
+        assert_eq!("0.000000000000000001 FIL".parse::<FIL>().unwrap(), fil(1));
+        assert!("1.5 attoFIL".parse::<FIL>().is_err());
+        assert!("1.2345 femtoFIL".parse::<FIL>().is_err());
+        assert!("0.0000000000000000001 FIL".parse::<FIL>().is_err());
+    }
+
+    #[test]
+    fn fil_from_str_invalid() {
+        for s in [
+            "",
+            " ",
+            "FIL",
+            ".",
+            ". FIL",
+            "-1",
+            "-1 FIL",
+            "1,5 FIL",
+            "1.2.3",
+            "1 kiloFIL",
+        ] {
+            assert!(s.parse::<FIL>().is_err(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn format_in() {
+        let amount = fil(1_234_567_000_000_000_000);
+        assert_eq!(amount.format_in(Unit::Fil, None), "1.234567 FIL");
+        assert_eq!(amount.format_in(Unit::Fil, Some(2)), "1.23 FIL");
+        // Truncated, not rounded
+        assert_eq!(amount.format_in(Unit::Fil, Some(3)), "1.234 FIL");
+        assert_eq!(amount.format_in(Unit::Fil, Some(0)), "1 FIL");
+        assert_eq!(amount.format_in(Unit::Milli, None), "1234.567 milliFIL");
+        assert_eq!(
+            amount.format_in(Unit::Atto, None),
+            "1234567000000000000 attoFIL"
+        );
+        assert_eq!(fil(0).format_in(Unit::Nano, None), "0 nanoFIL");
+        assert_eq!(
+            FIL(TokenAmount::from_atto(-1_500)).format_in(Unit::Femto, None),
+            "-1.5 femtoFIL"
+        );
+        assert_eq!(fil(1_500).to_string(), "0.0000000000000015 FIL");
+    }
+
+    #[test]
+    fn format_human() {
+        assert_eq!(fil(0).format_human(), "0 FIL");
+        assert_eq!(fil(999).format_human(), "999 attoFIL");
+        assert_eq!(fil(1_000).format_human(), "1 femtoFIL");
+        assert_eq!(fil(12_000_000_000).format_human(), "12 nanoFIL");
+        assert_eq!(fil(1_234_567_000_000_000_000).format_human(), "1.2345 FIL");
+        assert_eq!(
+            fil(999_999_999_000_000_000).format_human(),
+            "999.9999 milliFIL"
+        );
+        assert_eq!(
+            FIL(TokenAmount::from_atto(-2_500_000_000)).format_human(),
+            "-2.5 nanoFIL"
+        );
+    }
+
+    #[test]
+    fn format_in_roundtrip() {
+        let amounts = [0, 1, 999, 1_500, 1_234_567_000_000_000_000, u128::MAX];
+        for atto in amounts {
+            for unit in Unit::ALL {
+                let formatted = fil(atto).format_in(unit, None);
+                assert_eq!(formatted.parse::<FIL>().unwrap(), fil(atto), "{formatted}");
+            }
+        }
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use std::{pin::Pin, task::Poll};
 
+use async_compression::tokio::write::ZstdEncoder;
 use digest::{Digest, Output};
 use pin_project_lite::pin_project;
 use tokio::io::AsyncWrite;
@@ -58,6 +59,14 @@ impl<D: Digest, W> Checksum<D> for AsyncWriterWithChecksum<D, W> {
     }
 }
 
+/// Checksum of the compressed stream. The encoder has to be shut down before finalizing the
+/// checksum, for the end of the stream to be written.
+impl<D: Digest, W: Checksum<D>> Checksum<D> for ZstdEncoder<W> {
+    fn finalize(&mut self) -> Output<D> {
+        self.get_mut().finalize()
+    }
+}
+
 impl<D: Digest, W> AsyncWriterWithChecksum<D, W> {
     pub fn new(writer: W) -> Self {
         Self {
@@ -90,6 +99,24 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    async fn zstd_checksum_is_computed_over_compressed_stream() {
+        let writer = AsyncWriterWithChecksum::<Sha256, _>::new(Vec::new());
+        let mut writer = ZstdEncoder::new(writer);
+
+        for old_god in ["cthulhu", "azathoth", "dagon"] {
+            writer.write_all(old_god.as_bytes()).await.unwrap();
+        }
+        writer.shutdown().await.unwrap();
+
+        let compressed = writer.get_ref().inner.clone();
+        assert_ne!(compressed, b"cthulhuazathothdagon");
+        assert_eq!(
+            Checksum::<Sha256>::finalize(&mut writer),
+            Sha256::digest(&compressed)
+        );
+    }
+
     #[tokio::test]
     async fn digest_of_nothing() {
         let buffer = Vec::new();
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use anyhow::bail;
+use async_compression::tokio::bufread::ZstdDecoder;
 use cid::Cid;
 use forest_blocks::{BlockHeader, Tipset, TipsetKeys};
 use forest_db::Store;
@@ -16,7 +17,7 @@ use std::time;
 use tokio::fs::File;
 use tokio::io::AsyncRead;
 use tokio::io::BufReader;
-use tokio_util::compat::TokioAsyncReadCompatExt;
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
 use url::Url;
 
 #[cfg(feature = "testing")]
@@ -117,13 +118,14 @@ where
     let cids = if is_remote_file {
         info!("Downloading file...");
         let url = Url::parse(path)?;
+        let compressed = is_zstd(url.path());
         let reader = FetchProgress::fetch_from_url(url).await?;
-        load_and_retrieve_header(sm.blockstore(), reader, skip_load).await?
+        load_and_retrieve_header(sm.blockstore(), reader, skip_load, compressed).await?
     } else {
         info!("Reading file...");
         let file = File::open(&path).await?;
         let reader = FetchProgress::fetch_from_file(file).await?;
-        load_and_retrieve_header(sm.blockstore(), reader, skip_load).await?
+        load_and_retrieve_header(sm.blockstore(), reader, skip_load, is_zstd(path)).await?
     };
 
     info!("Loaded .car file in {}s", stopwatch.elapsed().as_secs());
@@ -162,22 +164,48 @@ where
     Ok(())
 }
 
-/// Loads car file into database, and returns the block header CIDs from the CAR header.
+/// Whether the file at the given path is a zstd compressed CAR file, based on its extension.
+fn is_zstd(path: &str) -> bool {
+    path.ends_with(".zst")
+}
+
+/// Loads car file into database, and returns the block header CIDs from the CAR header. Compressed
+/// files are decompressed on the fly.
 async fn load_and_retrieve_header<DB, R>(
     store: &DB,
     reader: FetchProgress<R>,
     skip_load: bool,
+    compressed: bool,
 ) -> Result<Vec<Cid>, anyhow::Error>
 where
     DB: Blockstore,
     R: AsyncRead + Send + Unpin,
 {
-    let mut compat = reader.compat();
-    let result = if skip_load {
-        CarReader::new(&mut compat).await?.header.roots
+    if compressed {
+        let mut compat = ZstdDecoder::new(BufReader::new(reader)).compat();
+        let result = load_or_read_header(store, &mut compat, skip_load).await?;
+        compat.into_inner().into_inner().into_inner().finish();
+        Ok(result)
     } else {
-        load_car(store, &mut compat).await?
-    };
-    compat.into_inner().finish();
-    Ok(result)
+        let mut compat = reader.compat();
+        let result = load_or_read_header(store, &mut compat, skip_load).await?;
+        compat.into_inner().finish();
+        Ok(result)
+    }
+}
+
+async fn load_or_read_header<DB, R>(
+    store: &DB,
+    reader: &mut Compat<R>,
+    skip_load: bool,
+) -> Result<Vec<Cid>, anyhow::Error>
+where
+    DB: Blockstore,
+    R: AsyncRead + Send + Unpin,
+{
+    Ok(if skip_load {
+        CarReader::new(reader).await?.header.roots
+    } else {
+        load_car(store, reader).await?
+    })
 }
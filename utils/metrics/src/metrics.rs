@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use lazy_static::lazy_static;
-use prometheus::core::{AtomicU64, GenericCounterVec, Opts};
+use prometheus::{
+    core::{AtomicU64, GenericCounter, GenericCounterVec, Opts},
+    Histogram, HistogramOpts,
+};
 
 lazy_static! {
     pub static ref LRU_CACHE_HIT: Box<GenericCounterVec<AtomicU64>> = {
@@ -31,6 +34,39 @@ lazy_static! {
             .expect("Registering the lru_cache_miss metric with the metrics registry must succeed");
         lru_cache_miss
     };
+    pub static ref REORG_TOTAL: Box<GenericCounter<AtomicU64>> = {
+        let reorg_total = Box::new(
+            GenericCounter::<AtomicU64>::new(
+                "chain_reorg_total",
+                "Total number of reorganizations of the heaviest chain",
+            )
+            .expect("Defining the chain_reorg_total metric must succeed"),
+        );
+        prometheus::default_registry()
+            .register(reorg_total.clone())
+            .expect(
+                "Registering the chain_reorg_total metric with the metrics registry must succeed",
+            );
+        reorg_total
+    };
+    pub static ref REORG_DEPTH: Box<Histogram> = {
+        let reorg_depth = Box::new(
+            Histogram::with_opts(HistogramOpts {
+                common_opts: Opts::new(
+                    "chain_reorg_depth",
+                    "Number of tipsets reverted by reorganizations of the heaviest chain",
+                ),
+                buckets: vec![1.0, 2.0, 3.0, 5.0, 10.0, 20.0, 50.0, 100.0, 900.0],
+            })
+            .expect("Defining the chain_reorg_depth metric must succeed"),
+        );
+        prometheus::default_registry()
+            .register(reorg_depth.clone())
+            .expect(
+                "Registering the chain_reorg_depth metric with the metrics registry must succeed",
+            );
+        reorg_depth
+    };
 }
 
 pub mod labels {
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use ahash::HashMap;
+use anyhow::Context;
 use backoff::{future::retry, ExponentialBackoff};
 use blake2b_simd::{Hash, State as Blake2b};
 use forest_utils::net::{https_client, hyper};
@@ -22,6 +23,7 @@ const PARAM_DIR: &str = "filecoin-proof-parameters";
 const DIR_ENV: &str = "FIL_PROOFS_PARAMETER_CACHE";
 const GATEWAY_ENV: &str = "IPFS_GATEWAY";
 const TRUST_PARAMS_ENV: &str = "TRUST_PARAMS";
+const MANIFEST_ENV: &str = "FOREST_PROOFS_PARAMETER_MANIFEST";
 const DEFAULT_PARAMETERS: &str = include_str!("parameters.json");
 
 /// Sector size options for fetching.
@@ -34,13 +36,79 @@ pub enum SectorSizeOpt {
     Size(SectorSize),
 }
 
-type ParameterMap = HashMap<String, ParameterData>;
+impl SectorSizeOpt {
+    fn includes(&self, name: &str, info: &ParameterData) -> bool {
+        match self {
+            SectorSizeOpt::Keys => !name.ends_with("params"),
+            SectorSizeOpt::Size(size) => {
+                *size as u64 == info.sector_size || !name.ends_with(".params")
+            }
+            SectorSizeOpt::All => true,
+        }
+    }
+}
+
+/// Parameter manifest, mapping file names to their description.
+pub type ParameterMap = HashMap<String, ParameterData>;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
-struct ParameterData {
-    cid: String,
-    digest: String,
-    sector_size: u64,
+pub struct ParameterData {
+    /// CID of the file on IPFS
+    pub cid: String,
+    /// First 32 hexadecimal characters of the blake2b digest of the file
+    pub digest: String,
+    /// Sector size the parameters are used for, or 0 for sector size independent files
+    pub sector_size: u64,
+}
+
+/// Location the parameter files are fetched from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamSource {
+    /// IPFS gateway URL, to which the CID of the files is appended
+    Gateway(String),
+    /// Local directory or mirror, holding the files under their name or their CID
+    Directory(PathBuf),
+}
+
+impl ParamSource {
+    /// Parses a gateway URL, a `file://` URL or a local directory path.
+    pub fn parse(source: &str) -> Self {
+        if let Some(path) = source.strip_prefix("file://") {
+            ParamSource::Directory(PathBuf::from(path))
+        } else if source.starts_with("http://") || source.starts_with("https://") {
+            ParamSource::Gateway(source.to_owned())
+        } else {
+            ParamSource::Directory(PathBuf::from(source))
+        }
+    }
+
+    /// Source set by the `IPFS_GATEWAY` environment variable, defaulting to the Filecoin proofs
+    /// gateway.
+    pub fn from_env() -> Self {
+        std::env::var(GATEWAY_ENV)
+            .map(|gw| Self::parse(&gw))
+            .unwrap_or_else(|_| ParamSource::Gateway(GATEWAY.to_owned()))
+    }
+}
+
+/// Loads the built-in parameter manifest, extended with the manifest at `path`. When no path is
+/// given, the `FOREST_PROOFS_PARAMETER_MANIFEST` environment variable is used if set. Entries of
+/// the extra manifest override built-in ones with the same name, so that new parameter sets can
+/// be added without recompiling.
+pub fn load_manifest(path: Option<&Path>) -> anyhow::Result<ParameterMap> {
+    let mut params: ParameterMap = serde_json::from_str(DEFAULT_PARAMETERS)?;
+
+    let path = path
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var(MANIFEST_ENV).ok().map(PathBuf::from));
+    if let Some(path) = path {
+        let extra = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read parameter manifest {path:?}"))?;
+        let extra: ParameterMap = serde_json::from_str(&extra)
+            .with_context(|| format!("Invalid parameter manifest {path:?}"))?;
+        params.extend(extra);
+    }
+    Ok(params)
 }
 
 // Proof parameter file directory. Defaults to %DATA_DIR/filecoin-proof-parameters unless
@@ -66,38 +134,35 @@ pub fn set_proofs_parameter_cache_dir_env(data_dir: &Path) {
 }
 
 /// Get proofs parameters and all verification keys for a given sector size given
-/// a parameter JSON manifest.
+/// a parameter manifest.
 pub async fn get_params(
     data_dir: &Path,
-    param_json: &str,
+    params: ParameterMap,
     storage_size: SectorSizeOpt,
+    source: ParamSource,
 ) -> Result<(), anyhow::Error> {
     fs::create_dir_all(param_dir(data_dir)).await?;
 
-    let params: ParameterMap = serde_json::from_str(param_json)?;
     let mut tasks = Vec::with_capacity(params.len());
 
     params
         .into_iter()
-        .filter(|(name, info)| match storage_size {
-            SectorSizeOpt::Keys => !name.ends_with("params"),
-            SectorSizeOpt::Size(size) => {
-                size as u64 == info.sector_size || !name.ends_with(".params")
-            }
-            SectorSizeOpt::All => true,
-        })
+        .filter(|(name, info)| storage_size.includes(name, info))
         .for_each(|(name, info)| {
             let data_dir_clone = data_dir.to_owned();
+            let source = source.clone();
             tasks.push(tokio::task::spawn(async move {
-                fetch_verify_params(&data_dir_clone, &name, Arc::new(info)).await
+                fetch_verify_params(&data_dir_clone, &name, Arc::new(info), &source).await
             }))
         });
 
     let mut errors = vec![];
 
     for t in tasks {
-        if let Err(err) = t.await {
-            errors.push(err);
+        match t.await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => errors.push(err),
+            Err(err) => errors.push(err.into()),
         }
     }
 
@@ -112,19 +177,51 @@ pub async fn get_params(
     }
 }
 
-/// Get proofs parameters and all verification keys for a given sector size using default manifest.
+/// Get proofs parameters and all verification keys for a given sector size using the default
+/// manifest and source, as overridden by the environment.
 #[inline]
 pub async fn get_params_default(
     data_dir: &Path,
     storage_size: SectorSizeOpt,
 ) -> Result<(), anyhow::Error> {
-    get_params(data_dir, DEFAULT_PARAMETERS, storage_size).await
+    get_params(
+        data_dir,
+        load_manifest(None)?,
+        storage_size,
+        ParamSource::from_env(),
+    )
+    .await
+}
+
+/// Checks the parameter files already present in the parameter directory against the digests of
+/// the manifest, without fetching anything. Returns the outcome for every file of the manifest,
+/// sorted by name. `TRUST_PARAMS` is ignored.
+pub async fn verify_params(
+    data_dir: &Path,
+    params: ParameterMap,
+    storage_size: SectorSizeOpt,
+) -> Vec<(String, Result<(), io::Error>)> {
+    let dir = param_dir(data_dir);
+    let mut params: Vec<_> = params
+        .into_iter()
+        .filter(|(name, info)| storage_size.includes(name, info))
+        .collect();
+    params.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut results = Vec::with_capacity(params.len());
+    for (name, info) in params {
+        let path: Arc<Path> = Arc::from(dir.join(&name).as_path());
+        let result = verify_file(path, Arc::new(info)).await;
+        results.push((name, result));
+    }
+    results
 }
 
 async fn fetch_verify_params(
     data_dir: &Path,
     name: &str,
     info: Arc<ParameterData>,
+    source: &ParamSource,
 ) -> Result<(), anyhow::Error> {
     let path: PathBuf = param_dir(data_dir).join(name);
     let path: Arc<Path> = Arc::from(path.as_path());
@@ -138,7 +235,7 @@ async fn fetch_verify_params(
         }
     }
 
-    fetch_params(&path, &info).await?;
+    fetch_params(&path, name, &info, source).await?;
 
     check_file(path, info).await.map_err(|e| {
         // TODO remove invalid file
@@ -146,15 +243,32 @@ async fn fetch_verify_params(
     })
 }
 
-async fn fetch_params(path: &Path, info: &ParameterData) -> Result<(), anyhow::Error> {
-    let gw = std::env::var(GATEWAY_ENV).unwrap_or_else(|_| GATEWAY.to_owned());
-    debug!("Fetching {:?} from {}", path, gw);
-    let url = format!("{}{}", gw, info.cid);
+async fn fetch_params(
+    path: &Path,
+    name: &str,
+    info: &ParameterData,
+    source: &ParamSource,
+) -> Result<(), anyhow::Error> {
+    match source {
+        ParamSource::Gateway(gw) => {
+            debug!("Fetching {:?} from {}", path, gw);
+            let url = format!("{}{}", gw, info.cid);
 
-    retry(ExponentialBackoff::default(), || async {
-        Ok(fetch_params_inner(&url, path).await?)
-    })
-    .await
+            retry(ExponentialBackoff::default(), || async {
+                Ok(fetch_params_inner(&url, path).await?)
+            })
+            .await
+        }
+        ParamSource::Directory(dir) => {
+            let mirrored = [dir.join(name), dir.join(&info.cid)]
+                .into_iter()
+                .find(|p| p.is_file())
+                .with_context(|| format!("Parameter file {name} not found in {dir:?}"))?;
+            debug!("Copying {:?} from {:?}", path, mirrored);
+            fs::copy(&mirrored, path).await?;
+            Ok(())
+        }
+    }
 }
 
 async fn fetch_params_inner(url: impl AsRef<str>, path: &Path) -> Result<(), anyhow::Error> {
@@ -189,6 +303,10 @@ async fn check_file(path: Arc<Path>, info: Arc<ParameterData>) -> Result<(), io:
         return Ok(());
     }
 
+    verify_file(path, info).await
+}
+
+async fn verify_file(path: Arc<Path>, info: Arc<ParameterData>) -> Result<(), io::Error> {
     let cloned_path = path.clone();
     let hash = tokio::task::spawn_blocking(move || -> Result<Hash, io::Error> {
         let file = SyncFile::open(cloned_path.as_ref())?;
@@ -214,3 +332,147 @@ async fn check_file(path: Arc<Path>, info: Arc<ParameterData>) -> Result<(), io:
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(content: &[u8]) -> String {
+        blake2b_simd::blake2b(content).to_hex()[..32].to_owned()
+    }
+
+    fn param(cid: &str, content: &[u8]) -> ParameterData {
+        ParameterData {
+            cid: cid.to_owned(),
+            digest: digest(content),
+            sector_size: 0,
+        }
+    }
+
+    #[test]
+    fn parse_source() {
+        assert_eq!(
+            ParamSource::parse("https://proofs.filecoin.io/ipfs/"),
+            ParamSource::Gateway("https://proofs.filecoin.io/ipfs/".to_owned())
+        );
+        assert_eq!(
+            ParamSource::parse("file:///srv/params"),
+            ParamSource::Directory(PathBuf::from("/srv/params"))
+        );
+        assert_eq!(
+            ParamSource::parse("/srv/params"),
+            ParamSource::Directory(PathBuf::from("/srv/params"))
+        );
+    }
+
+    #[test]
+    fn manifest_extends_builtin_one() {
+        let builtin: ParameterMap = serde_json::from_str(DEFAULT_PARAMETERS).unwrap();
+        let (name, _) = builtin.iter().next().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+        let extra: ParameterMap = [
+            (name.clone(), param("bafyoverride", b"override")),
+            ("v99-new.vk".to_owned(), param("bafynew", b"new")),
+        ]
+        .into_iter()
+        .collect();
+        std::fs::write(&path, serde_json::to_string(&extra).unwrap()).unwrap();
+
+        let manifest = load_manifest(Some(&path)).unwrap();
+        assert_eq!(manifest.len(), builtin.len() + 1);
+        assert_eq!(manifest[name].cid, "bafyoverride");
+        assert_eq!(manifest["v99-new.vk"].digest, digest(b"new"));
+    }
+
+    #[test]
+    fn invalid_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_manifest(Some(&dir.path().join("missing.json"))).is_err());
+
+        let path = dir.path().join("manifest.json");
+        std::fs::write(&path, r#"{"v28.vk": {"cid": "bafy"}}"#).unwrap();
+        assert!(load_manifest(Some(&path)).is_err());
+    }
+
+    #[test]
+    fn manifest_from_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+        let extra: ParameterMap = [("v99-env.vk".to_owned(), param("bafyenv", b"env"))]
+            .into_iter()
+            .collect();
+        std::fs::write(&path, serde_json::to_string(&extra).unwrap()).unwrap();
+
+        std::env::set_var(MANIFEST_ENV, &path);
+        let manifest = load_manifest(None);
+        std::env::remove_var(MANIFEST_ENV);
+        assert_eq!(manifest.unwrap()["v99-env.vk"].cid, "bafyenv");
+    }
+
+    #[tokio::test]
+    async fn verify_checksums() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let dir = param_dir(data_dir.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.vk"), b"a").unwrap();
+        std::fs::write(dir.join("b.vk"), b"corrupted").unwrap();
+
+        let params: ParameterMap = [
+            ("a.vk".to_owned(), param("bafya", b"a")),
+            ("b.vk".to_owned(), param("bafyb", b"b")),
+            ("c.vk".to_owned(), param("bafyc", b"c")),
+        ]
+        .into_iter()
+        .collect();
+        let results = verify_params(data_dir.path(), params, SectorSizeOpt::All).await;
+
+        let names: Vec<_> = results.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["a.vk", "b.vk", "c.vk"]);
+        assert!(results[0].1.is_ok());
+        let mismatch = results[1].1.as_ref().unwrap_err();
+        assert!(mismatch.to_string().contains("Checksum mismatch"));
+        assert_eq!(
+            results[2].1.as_ref().unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_from_directory() {
+        let mirror = tempfile::tempdir().unwrap();
+        // Files can be mirrored under their name or their CID
+        std::fs::write(mirror.path().join("a.vk"), b"a").unwrap();
+        std::fs::write(mirror.path().join("bafyb"), b"b").unwrap();
+        let params: ParameterMap = [
+            ("a.vk".to_owned(), param("bafya", b"a")),
+            ("b.vk".to_owned(), param("bafyb", b"b")),
+        ]
+        .into_iter()
+        .collect();
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let source = ParamSource::Directory(mirror.path().to_owned());
+        get_params(
+            data_dir.path(),
+            params.clone(),
+            SectorSizeOpt::All,
+            source.clone(),
+        )
+        .await
+        .unwrap();
+        let results = verify_params(data_dir.path(), params, SectorSizeOpt::All).await;
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+
+        // Mirrored files are checked against the manifest
+        std::fs::write(mirror.path().join("c.vk"), b"corrupted").unwrap();
+        let params: ParameterMap = [("c.vk".to_owned(), param("bafyc", b"c"))]
+            .into_iter()
+            .collect();
+        let err = get_params(data_dir.path(), params, SectorSizeOpt::All, source)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+    }
+}
@@ -18,6 +18,7 @@ use fvm_shared::address::Address;
 use fvm_shared::crypto::signature::Signature;
 use fvm_shared::{bigint::BigInt, message::Message};
 use std::convert::TryFrom;
+use std::sync::Arc;
 
 /// Defines a `TipsetKey` used in testing
 pub fn template_key(data: &[u8]) -> Cid {
@@ -148,13 +149,13 @@ pub fn construct_tipset_bundle(epoch: i64, weight: u64) -> TipsetBundle {
     let includes: Vec<Vec<u64>> = (0..headers.len()).map(|_| Vec::new()).collect();
 
     TipsetBundle {
-        blocks: headers,
-        messages: Some(CompactedMessages {
+        blocks: Arc::new(headers),
+        messages: Some(Arc::new(CompactedMessages {
             bls_msgs: vec![bls],
             secp_msgs: vec![secp],
             bls_msg_includes: includes.clone(),
             secp_msg_includes: includes,
-        }),
+        })),
     }
 }
 
@@ -16,6 +16,9 @@ pub const ADDRESS: Address = Address::new_id(1);
 /// Init actor method.
 pub type Method = fil_actor_init_v8::Method;
 
+/// Init actor `Exec` method parameters.
+pub type ExecParams = fil_actor_init_v9::ExecParams;
+
 pub fn is_v8_init_cid(cid: &Cid) -> bool {
     let known_cids = vec![
         // calibnet v8
@@ -18,9 +18,14 @@ use anyhow::Context;
 /// Market actor address.
 pub const ADDRESS: Address = Address::new_id(5);
 
+/// Name of the market actor in the built-in actors manifest.
+pub const MANIFEST_NAME: &str = "storagemarket";
+
 /// Market actor method.
 pub type Method = fil_actor_market_v8::Method;
 
+pub type WithdrawBalanceParams = fil_actor_market_v9::WithdrawBalanceParams;
+
 pub fn is_v8_market_cid(cid: &Cid) -> bool {
     let known_cids = vec![
         // calibnet v8
@@ -21,9 +21,14 @@ use std::borrow::Cow;
 use anyhow::Context;
 
 use crate::power::Claim;
+/// Name of the miner actor in the built-in actors manifest.
+pub const MANIFEST_NAME: &str = "storageminer";
+
 /// Miner actor method.
 pub type Method = fil_actor_miner_v8::Method;
 
+pub type WithdrawBalanceParams = fil_actor_miner_v9::WithdrawBalanceParams;
+
 pub fn is_v8_miner_cid(cid: &Cid) -> bool {
     let known_cids = vec![
         // calibnet v8
@@ -1,2 +1,240 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::Context;
+use forest_encoding::blake2b_256;
+use forest_utils::db::BlockstoreExt;
+use fvm::state_tree::ActorState;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{to_vec, RawBytes};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::MethodNum;
+use serde::Serialize;
+
+/// Name of the multisig actor in the built-in actors manifest.
+pub const MANIFEST_NAME: &str = "multisig";
+
+/// Multisig actor method.
+pub type Method = fil_actor_multisig_v9::Method;
+
+pub use fil_actor_multisig_v9::{
+    AddSignerParams, ChangeNumApprovalsThresholdParams, ConstructorParams, LockBalanceParams,
+    ProposeParams, RemoveSignerParams, SwapSignerParams, Transaction, TxnID, TxnIDParams,
+};
+
+/// Multisig actor state. The state layout is unchanged since actors v8, so it is decoded with
+/// the v9 types.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum State {
+    V9(fil_actor_multisig_v9::State),
+}
+
+impl State {
+    /// Loads the state of a multisig actor. Callers are expected to have checked the actor code
+    /// against the built-in actors manifest.
+    pub fn load<BS>(store: &BS, actor: &ActorState) -> anyhow::Result<State>
+    where
+        BS: Blockstore,
+    {
+        store
+            .get_obj(&actor.state)?
+            .map(State::V9)
+            .context("Actor state doesn't exist in store")
+    }
+
+    /// Returns the signers of the multisig.
+    pub fn signers(&self) -> &[Address] {
+        match self {
+            State::V9(st) => &st.signers,
+        }
+    }
+
+    /// Returns the number of approvals required to execute a transaction.
+    pub fn threshold(&self) -> u64 {
+        match self {
+            State::V9(st) => st.num_approvals_threshold,
+        }
+    }
+
+    /// Returns the amount of funds still locked by the vesting schedule at the given epoch.
+    pub fn locked_balance(&self, epoch: ChainEpoch) -> TokenAmount {
+        match self {
+            State::V9(st) => st.amount_locked(epoch - st.start_epoch),
+        }
+    }
+
+    /// Returns the pending transactions, ordered by ID.
+    pub fn pending_transactions<BS>(&self, store: &BS) -> anyhow::Result<Vec<(TxnID, Transaction)>>
+    where
+        BS: Blockstore,
+    {
+        let root = match self {
+            State::V9(st) => st.pending_txs,
+        };
+        let map = fil_actors_runtime::make_map_with_root::<_, Transaction>(&root, store)?;
+        let mut pending = Vec::new();
+        map.for_each(|key, txn| {
+            pending.push((TxnID(parse_varint_key(key)?), txn.clone()));
+            Ok(())
+        })?;
+        pending.sort_by_key(|(id, _)| id.0);
+        Ok(pending)
+    }
+
+    /// Returns the pending transaction with the given ID.
+    pub fn pending_transaction<BS>(&self, store: &BS, id: i64) -> anyhow::Result<Transaction>
+    where
+        BS: Blockstore,
+    {
+        self.pending_transactions(store)?
+            .into_iter()
+            .find_map(|(txn_id, txn)| (txn_id.0 == id).then_some(txn))
+            .with_context(|| format!("No pending transaction with ID {id}"))
+    }
+}
+
+/// Transaction IDs are keyed in the pending transactions HAMT by their zig-zag varint encoding.
+fn parse_varint_key(key: &[u8]) -> anyhow::Result<i64> {
+    let mut value = 0u64;
+    for (i, byte) in key.iter().enumerate() {
+        if i >= 10 {
+            break;
+        }
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+        }
+    }
+    anyhow::bail!("Invalid transaction ID key {key:?}")
+}
+
+#[derive(Serialize_tuple)]
+struct ProposalHashData<'a> {
+    requester: Option<&'a Address>,
+    to: &'a Address,
+    value: &'a TokenAmount,
+    method: &'a MethodNum,
+    params: &'a RawBytes,
+}
+
+/// Computes the hash identifying a pending transaction, which has to be provided when approving
+/// or cancelling it.
+pub fn proposal_hash(txn: &Transaction) -> anyhow::Result<Vec<u8>> {
+    let data = ProposalHashData {
+        requester: txn.approved.first(),
+        to: &txn.to,
+        value: &txn.value,
+        method: &txn.method,
+        params: &txn.params,
+    };
+    Ok(blake2b_256(&to_vec(&data)?).to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cid::Cid;
+
+    #[test]
+    fn varint_keys() {
+        // Zig-zag varint encodings, as produced by `binary.PutVarint` in Lotus and
+        // `VarInt::encode_var_vec` in the built-in actors
+        let vectors: &[(&[u8], i64)] = &[
+            (&[0x00], 0),
+            (&[0x01], -1),
+            (&[0x02], 1),
+            (&[0x03], -2),
+            (&[0x7e], 63),
+            (&[0x7f], -64),
+            (&[0x80, 0x01], 64),
+            (&[0xac, 0x02], 150),
+            (
+                &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+                i64::MAX,
+            ),
+            (
+                &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+                i64::MIN,
+            ),
+        ];
+        for (key, id) in vectors {
+            assert_eq!(parse_varint_key(key).unwrap(), *id, "key {key:?}");
+        }
+        assert!(parse_varint_key(&[]).is_err());
+        assert!(parse_varint_key(&[0x80]).is_err());
+        assert!(parse_varint_key(&[0xff; 11]).is_err());
+    }
+
+    #[test]
+    fn proposal_hash_matches_actor_encoding() {
+        let txn = Transaction {
+            to: Address::new_id(102),
+            value: TokenAmount::from_atto(1),
+            method: 2,
+            params: RawBytes::default(),
+            approved: vec![Address::new_id(101), Address::new_id(103)],
+        };
+        // CBOR tuple of the requester, the destination, the value, the method and the params,
+        // hashed by the built-in actors with blake2b-256
+        let encoded = [
+            0x85, // array(5)
+            0x42, 0x00, 0x65, // f0101
+            0x42, 0x00, 0x66, // f0102
+            0x42, 0x00, 0x01, // 1 attoFIL
+            0x02, // method 2
+            0x40, // no params
+        ];
+        assert_eq!(proposal_hash(&txn).unwrap(), blake2b_256(&encoded).to_vec());
+
+        // Only the first approval, the proposer, is part of the hash
+        let mut other_approvals = txn.clone();
+        other_approvals.approved.truncate(1);
+        assert_eq!(
+            proposal_hash(&other_approvals).unwrap(),
+            proposal_hash(&txn).unwrap()
+        );
+
+        let unapproved = Transaction {
+            approved: Vec::new(),
+            value: TokenAmount::from_atto(0),
+            ..txn
+        };
+        let encoded = [
+            0x85, // array(5)
+            0xf6, // no requester
+            0x42, 0x00, 0x66, // f0102
+            0x40, // zero
+            0x02, // method 2
+            0x40, // no params
+        ];
+        assert_eq!(
+            proposal_hash(&unapproved).unwrap(),
+            blake2b_256(&encoded).to_vec()
+        );
+    }
+
+    #[test]
+    fn locked_balance_vests_linearly() {
+        let state = State::V9(fil_actor_multisig_v9::State {
+            signers: vec![Address::new_id(101)],
+            num_approvals_threshold: 1,
+            next_tx_id: TxnID(0),
+            initial_balance: TokenAmount::from_atto(1000),
+            start_epoch: 100,
+            unlock_duration: 10,
+            pending_txs: Cid::default(),
+        });
+        let locked = |epoch| state.locked_balance(epoch).atto().clone();
+        assert_eq!(locked(0), 1000.into());
+        assert_eq!(locked(100), 1000.into());
+        assert_eq!(locked(103), 700.into());
+        assert_eq!(locked(105), 500.into());
+        assert_eq!(locked(109), 100.into());
+        assert_eq!(locked(110), 0.into());
+        assert_eq!(locked(1000), 0.into());
+    }
+}
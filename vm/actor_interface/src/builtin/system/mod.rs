@@ -1,7 +1,54 @@
 // Copyright 2019-2023 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use anyhow::Context;
+use cid::Cid;
+use forest_utils::db::BlockstoreExt;
+use fvm::state_tree::ActorState;
+use fvm_ipld_blockstore::Blockstore;
 use fvm_shared::address::Address;
 
 /// System actor address.
 pub const ADDRESS: Address = Address::new_id(0);
+
+/// System actor state.
+pub type State = fil_actor_system_v9::State;
+
+/// Returns the code CID of the built-in actor with the given name (e.g. `multisig`), as listed
+/// in the manifest referenced by the system actor state.
+pub fn builtin_actor_code<BS>(store: &BS, actor: &ActorState, name: &str) -> anyhow::Result<Cid>
+where
+    BS: Blockstore,
+{
+    builtin_actors(store, actor)?
+        .into_iter()
+        .find_map(|(actor_name, code)| (actor_name == name).then_some(code))
+        .with_context(|| format!("No {name} actor in the built-in actors manifest"))
+}
+
+/// Returns the name of the built-in actor with the given code CID, if it is listed in the
+/// manifest referenced by the system actor state.
+pub fn builtin_actor_name<BS>(
+    store: &BS,
+    actor: &ActorState,
+    code: &Cid,
+) -> anyhow::Result<Option<String>>
+where
+    BS: Blockstore,
+{
+    Ok(builtin_actors(store, actor)?
+        .into_iter()
+        .find_map(|(actor_name, actor_code)| (actor_code == *code).then_some(actor_name)))
+}
+
+fn builtin_actors<BS>(store: &BS, actor: &ActorState) -> anyhow::Result<Vec<(String, Cid)>>
+where
+    BS: Blockstore,
+{
+    let state: State = store
+        .get_obj(&actor.state)?
+        .context("Actor state doesn't exist in store")?;
+    store
+        .get_obj(&state.builtin_actors)?
+        .context("Built-in actors manifest doesn't exist in store")
+}
@@ -6,8 +6,10 @@ mod fvm;
 mod instrumented_kernel;
 #[cfg(feature = "instrumented_kernel")]
 mod metrics;
+mod trace;
 mod vm;
 
+pub use self::trace::*;
 pub use self::vm::*;
 
 use ::fvm::state_tree::StateTree as FvmStateTree;
@@ -0,0 +1,311 @@
+// Copyright 2019-2023 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fvm::executor::ApplyRet;
+use fvm::gas::GasCharge;
+use fvm::trace::ExecutionEvent;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::receipt::Receipt;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+/// Whether or not the VM records execution traces of the messages it applies.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VMTrace {
+    /// Collect execution traces, at the expense of execution speed
+    Traced,
+    /// Skip trace collection
+    #[default]
+    NotTraced,
+}
+
+impl VMTrace {
+    /// Should tracing be collected?
+    pub fn is_traced(&self) -> bool {
+        matches!(self, VMTrace::Traced)
+    }
+}
+
+/// Gas charged during the execution of a call.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GasTrace {
+    pub name: String,
+    pub total_gas: i64,
+    pub compute_gas: i64,
+    pub storage_gas: i64,
+}
+
+impl From<&GasCharge<'_>> for GasTrace {
+    fn from(charge: &GasCharge<'_>) -> Self {
+        GasTrace {
+            name: charge.name.to_string(),
+            total_gas: charge.total().round_up(),
+            compute_gas: charge.compute_gas.round_up(),
+            storage_gas: charge.storage_gas.round_up(),
+        }
+    }
+}
+
+/// Execution trace of a message as a tree of nested actor calls, each carrying the gas
+/// charged while executing it and its receipt.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ExecutionTrace {
+    #[serde(with = "forest_json::message::json")]
+    pub msg: Message,
+    #[serde(with = "forest_json::message_receipt::json")]
+    pub msg_rct: Receipt,
+    pub error: Option<String>,
+    pub gas_charges: Vec<GasTrace>,
+    pub subcalls: Vec<ExecutionTrace>,
+}
+
+impl ExecutionTrace {
+    fn new_call(msg: Message) -> Self {
+        ExecutionTrace {
+            msg,
+            msg_rct: Receipt {
+                exit_code: ExitCode::OK,
+                return_data: RawBytes::default(),
+                gas_used: 0,
+            },
+            error: None,
+            gas_charges: Vec::new(),
+            subcalls: Vec::new(),
+        }
+    }
+
+    /// Builds the call tree of an applied message from the events recorded by the FVM. The
+    /// returned root carries the message and receipt as applied; nested calls report the gas
+    /// charged within them as `gas_used`.
+    ///
+    /// The VM must have been created with [`VMTrace::Traced`], otherwise the root has no
+    /// gas charges nor subcalls.
+    pub fn from_apply_ret(msg: &Message, ret: &ApplyRet) -> Self {
+        let mut root = Self::from_events(msg, &ret.exec_trace);
+        root.msg_rct = ret.msg_receipt.clone();
+        root.error = ret.failure_info.as_ref().map(|info| info.to_string());
+        root
+    }
+
+    /// Builds the call tree of `msg` from the recorded execution events, the outermost call being
+    /// the message itself.
+    fn from_events(msg: &Message, events: &[ExecutionEvent]) -> Self {
+        let mut stack: Vec<ExecutionTrace> = Vec::new();
+        let mut root = ExecutionTrace::new_call(msg.clone());
+
+        for event in events {
+            match event {
+                ExecutionEvent::GasCharge(charge) => match stack.last_mut() {
+                    Some(call) => call.gas_charges.push(charge.into()),
+                    None => root.gas_charges.push(charge.into()),
+                },
+                ExecutionEvent::Call {
+                    from,
+                    to,
+                    method,
+                    params,
+                    value,
+                } => stack.push(ExecutionTrace::new_call(Message {
+                    version: 0,
+                    from: Address::new_id(*from),
+                    to: *to,
+                    sequence: 0,
+                    value: value.clone(),
+                    method_num: *method,
+                    params: params.clone(),
+                    gas_limit: 0,
+                    gas_fee_cap: Default::default(),
+                    gas_premium: Default::default(),
+                })),
+                ExecutionEvent::CallReturn(return_data) => Self::finish_call(
+                    &mut stack,
+                    &mut root,
+                    ExitCode::OK,
+                    return_data.clone(),
+                    None,
+                ),
+                ExecutionEvent::CallAbort(exit_code) => {
+                    Self::finish_call(&mut stack, &mut root, *exit_code, RawBytes::default(), None)
+                }
+                ExecutionEvent::CallError(err) => Self::finish_call(
+                    &mut stack,
+                    &mut root,
+                    ExitCode::SYS_ASSERTION_FAILED,
+                    RawBytes::default(),
+                    Some(format!("{err:?}")),
+                ),
+                // `ExecutionEvent` is `#[non_exhaustive]`, events added by later FVM versions
+                // aren't part of the call tree.
+                event => debug!("Ignoring execution event {event:?}"),
+            }
+        }
+        root
+    }
+
+    /// Pops the innermost pending call off the stack and attaches it to its caller.
+    fn finish_call(
+        stack: &mut Vec<ExecutionTrace>,
+        root: &mut ExecutionTrace,
+        exit_code: ExitCode,
+        return_data: RawBytes,
+        error: Option<String>,
+    ) {
+        if let Some(mut call) = stack.pop() {
+            call.msg_rct = Receipt {
+                exit_code,
+                return_data,
+                gas_used: call
+                    .gas_charges
+                    .iter()
+                    .map(|gas| gas.total_gas)
+                    .sum::<i64>()
+                    + call
+                        .subcalls
+                        .iter()
+                        .map(|sub| sub.msg_rct.gas_used)
+                        .sum::<i64>(),
+            };
+            call.error = error;
+            match stack.last_mut() {
+                Some(parent) => parent.subcalls.push(call),
+                // The outermost call is the message itself
+                None => {
+                    root.gas_charges.extend(call.gas_charges);
+                    root.subcalls.extend(call.subcalls);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fvm::gas::Gas;
+    use fvm_shared::econ::TokenAmount;
+
+    fn message(to: u64) -> Message {
+        Message {
+            version: 0,
+            from: Address::new_id(100),
+            to: Address::new_id(to),
+            sequence: 0,
+            value: TokenAmount::default(),
+            method_num: 2,
+            params: RawBytes::default(),
+            gas_limit: 0,
+            gas_fee_cap: Default::default(),
+            gas_premium: Default::default(),
+        }
+    }
+
+    fn call(from: u64, to: u64) -> ExecutionEvent {
+        ExecutionEvent::Call {
+            from,
+            to: Address::new_id(to),
+            method: 2,
+            params: RawBytes::default(),
+            value: TokenAmount::default(),
+        }
+    }
+
+    fn gas(amount: i64) -> ExecutionEvent {
+        ExecutionEvent::GasCharge(GasCharge::new(
+            "OnMethodInvocation",
+            Gas::new(amount),
+            Gas::new(0),
+        ))
+    }
+
+    fn ret(data: &[u8]) -> ExecutionEvent {
+        ExecutionEvent::CallReturn(RawBytes::new(data.to_vec()))
+    }
+
+    #[test]
+    fn nested_sends() {
+        let events = [
+            gas(1),
+            call(100, 101),
+            gas(2),
+            call(101, 102),
+            gas(3),
+            call(102, 103),
+            gas(4),
+            ret(&[3]),
+            ret(&[2]),
+            call(101, 104),
+            gas(5),
+            ret(&[4]),
+            ret(&[1]),
+        ];
+        let root = ExecutionTrace::from_events(&message(101), &events);
+
+        // The charges of the outermost call belong to the message itself.
+        assert_eq!(root.gas_charges.len(), 2);
+        assert_eq!(root.subcalls.len(), 2);
+
+        let first = &root.subcalls[0];
+        assert_eq!(first.msg.to, Address::new_id(102));
+        assert_eq!(first.msg_rct.return_data, RawBytes::new(vec![2]));
+        assert_eq!(first.msg_rct.gas_used, 3 + 4);
+        assert_eq!(first.subcalls.len(), 1);
+        assert_eq!(first.subcalls[0].msg.from, Address::new_id(102));
+        assert_eq!(first.subcalls[0].msg.to, Address::new_id(103));
+        assert_eq!(first.subcalls[0].msg_rct.gas_used, 4);
+
+        let second = &root.subcalls[1];
+        assert_eq!(second.msg.to, Address::new_id(104));
+        assert_eq!(second.msg_rct.gas_used, 5);
+        assert!(second.subcalls.is_empty());
+    }
+
+    #[test]
+    fn reverted_subcall() {
+        // The subcall fails but its caller handles the failure and returns.
+        let events = [
+            call(100, 101),
+            call(101, 102),
+            gas(2),
+            ExecutionEvent::CallAbort(ExitCode::USR_ILLEGAL_STATE),
+            gas(1),
+            ret(&[1]),
+        ];
+        let root = ExecutionTrace::from_events(&message(101), &events);
+
+        assert_eq!(root.subcalls.len(), 1);
+        let reverted = &root.subcalls[0];
+        assert_eq!(reverted.msg_rct.exit_code, ExitCode::USR_ILLEGAL_STATE);
+        assert_eq!(reverted.msg_rct.return_data, RawBytes::default());
+        assert_eq!(reverted.msg_rct.gas_used, 2);
+        assert_eq!(reverted.error, None);
+        assert_eq!(root.gas_charges.len(), 1);
+    }
+
+    #[test]
+    fn aborted_subcalls() {
+        // The innermost call aborts and the abort propagates up to the message.
+        let events = [
+            call(100, 101),
+            call(101, 102),
+            call(102, 103),
+            gas(4),
+            ExecutionEvent::CallAbort(ExitCode::USR_FORBIDDEN),
+            ExecutionEvent::CallAbort(ExitCode::USR_FORBIDDEN),
+            ExecutionEvent::CallAbort(ExitCode::USR_FORBIDDEN),
+        ];
+        let root = ExecutionTrace::from_events(&message(101), &events);
+
+        assert_eq!(root.subcalls.len(), 1);
+        let outer = &root.subcalls[0];
+        assert_eq!(outer.msg_rct.exit_code, ExitCode::USR_FORBIDDEN);
+        assert_eq!(outer.msg_rct.gas_used, 4);
+        assert_eq!(outer.subcalls.len(), 1);
+        assert_eq!(outer.subcalls[0].msg_rct.exit_code, ExitCode::USR_FORBIDDEN);
+        assert_eq!(outer.subcalls[0].msg_rct.gas_used, 4);
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use crate::fvm::ForestExterns;
+use crate::VMTrace;
 use ahash::HashSet;
 use cid::Cid;
 use forest_actor_interface::{cron, reward, system, AwardBlockRewardParams};
@@ -80,6 +81,7 @@ where
         lb_fn: Box<dyn Fn(ChainEpoch) -> anyhow::Result<Cid>>,
         multi_engine: &MultiEngine,
         chain_config: Arc<ChainConfig>,
+        enable_tracing: VMTrace,
     ) -> Result<Self, anyhow::Error> {
         let network_version = chain_config.network_version(epoch);
         let config = NetworkConfig::new(network_version.into());
@@ -87,6 +89,9 @@ where
         let mut context = config.for_epoch(epoch, root);
         context.set_base_fee(base_fee);
         context.set_circulating_supply(circ_supply);
+        if enable_tracing.is_traced() {
+            context.enable_tracing();
+        }
         let fvm: fvm::machine::DefaultMachine<DB, ForestExterns<DB>> =
             fvm::machine::DefaultMachine::new(
                 &engine,
@@ -240,6 +245,8 @@ where
 
     /// Applies the state transition for a single message.
     /// Returns `ApplyRet` structure which contains the message receipt and some meta data.
+    /// When the VM was created with [`VMTrace::Traced`], the `ApplyRet` also holds the FVM
+    /// execution events, see [`crate::ExecutionTrace::from_apply_ret`].
     pub fn apply_message(&mut self, msg: &ChainMessage) -> Result<ApplyRet, anyhow::Error> {
         check_message(msg.message())?;
 